@@ -24,11 +24,10 @@ def get_time_unit() -> str:
 def check_time_unit(results: PostProcess) -> np.ndarray:
     # Conversion to hour if duration too long
     if results.time[-1] > 10000:
-        t = np.array(results.time) / 3600.0
         set_time_unit_to_hour()
-        return t
+        return results.time_in("h")
     else:
-        return np.array(results.time)
+        return results.time_in(results.unit("time"))
 
 
 def get_post_process(name: str, root: str = "./results"):
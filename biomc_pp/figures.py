@@ -24,7 +24,7 @@ def plot_biomass_concentration(time, pp):
     x = np.sum(pp.get_biomass_concentration(), axis=1)
     title = "Biomass concentration according to time"
     xaxis_title = f"Time [{get_time_unit()}]"
-    yaxis_title = "Biomass concentration [g]"
+    yaxis_title = f"Biomass concentration [{pp.unit('concentration')}]"
     fig = go.Figure()
     fig.add_trace(
         go.Scatter(
@@ -63,7 +63,7 @@ def plot_local_biomass_concentration(time, pp, compartment):
     x = pp.get_biomass_concentration()[:, compartment]
     title = ("Biomass concentration according to time",)
     xaxis_title = f"Time [{get_time_unit()}]"
-    yaxis_title = "Biomass concentration [g]"
+    yaxis_title = f"Biomass concentration [{pp.unit('concentration')}]"
 
     fig = go.Figure()
     fig.add_trace(
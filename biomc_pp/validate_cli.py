@@ -0,0 +1,34 @@
+import argparse
+import sys
+
+from biomc_pp import validate
+
+
+def main(argv=None) -> int:
+    parser = argparse.ArgumentParser(
+        description="Check the cross-file consistency of a BioMC result folder"
+    )
+    parser.add_argument("folder", help="Name of the result folder")
+    parser.add_argument("--root", default="./results", help="Root directory of results")
+    parser.add_argument(
+        "--strict",
+        action="store_true",
+        help="Return a non-zero exit code on warnings as well",
+    )
+    args = parser.parse_args(argv)
+
+    diagnostics = validate(args.folder, args.root)
+    for d in diagnostics:
+        print(f"[{d['severity']}] {d['file']}:{d['object']}: {d['message']}")
+
+    n_error = sum(d["severity"] == "Error" for d in diagnostics)
+    n_warning = len(diagnostics) - n_error
+    print(f"{n_error} error(s), {n_warning} warning(s)")
+
+    if n_error > 0 or (args.strict and n_warning > 0):
+        return 1
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
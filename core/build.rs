@@ -0,0 +1,10 @@
+use std::env;
+
+/// Sets `cfg(hdf5_1_10)` when `hdf5-sys` is built against HDF5 >= 1.10, whose version features
+/// are forwarded to dependents as `DEP_HDF5_VERSION_<major>_<minor>_<micro>`.
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(hdf5_1_10)");
+    if env::var_os("DEP_HDF5_VERSION_1_10_0").is_some() {
+        println!("cargo:rustc-cfg=hdf5_1_10");
+    }
+}
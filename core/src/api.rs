@@ -1,7 +1,20 @@
-use crate::datamodel::{Weight,tallies::Tallies};
+use crate::datamodel::{bio_exports, Weight,tallies::{Tallies, TallyTable}};
 
 use crate::error::ApiError;
-use ndarray::{Array1, Array2, ArrayView2, ArrayView3};
+use crate::metadata::{FinalSummary, InitialParameters, RunMetadata};
+use crate::impl_unique::total_mass;
+use crate::process::filter::ParticleFilter;
+use crate::process::growth::{log_linear_rate, savitzky_golay_rate, GrowthRate};
+use crate::process::integrate::{cumulative_trapezoid, integrate, window_average, Rule};
+use crate::process::interpolate::{resample_axis, Method, Resampled};
+use crate::process::spectral::{autocorrelation_time, periodogram, Periodogram};
+use crate::process::steady::{detect, Detector, SteadyState};
+use crate::units::{Quantity, Unit};
+use crate::zoning::{map_last_axis, Zoning};
+use ndarray::{
+    Array1, Array2, Array3, ArrayView, ArrayView1, ArrayView2, ArrayView3, Axis, Dimension, Ix1,
+    Ix2, Ix3, RemoveAxis,
+};
 
 /// `Phase` enum represents different states or phases of a substance.
 #[derive(Clone, PartialEq, Copy)]
@@ -10,6 +23,51 @@ pub enum Phase {
     Gas,
 }
 
+/// Identifies a species either by its index or by its name.
+///
+/// Names are the ones returned by [`PostProcessReader::species_names`].
+///
+/// # Example
+/// ```ignore
+/// let glucose = pp.get_spatial_average_concentration("glucose", Phase::Liquid);
+/// let same = pp.get_spatial_average_concentration(0, Phase::Liquid);
+/// ```
+pub trait SpeciesKey {
+    /// Resolves the species index among the available `names`
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError>;
+}
+
+impl SpeciesKey for usize {
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError> {
+        if *self < names.len() {
+            Ok(*self)
+        } else {
+            Err(ApiError::OutOfRange(*self, names.len()))
+        }
+    }
+}
+
+impl SpeciesKey for &str {
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError> {
+        names
+            .iter()
+            .position(|n| n == self)
+            .ok_or_else(|| ApiError::SpeciesError(self.to_string(), names.to_vec()))
+    }
+}
+
+impl SpeciesKey for String {
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError> {
+        self.as_str().species_index(names)
+    }
+}
+
+impl SpeciesKey for &String {
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError> {
+        self.as_str().species_index(names)
+    }
+}
+
 /// Type of estimator to retrieve data from MC Particle
 #[derive(Copy, Clone)]
 pub enum Estimator {
@@ -17,6 +75,43 @@ pub enum Estimator {
     Weighted,
 }
 
+/// Several properties of the particles of one export, see
+/// [`PostProcessReader::get_particle_table`].
+///
+/// Row `i` of every column describes the same particle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleTable {
+    pub keys: Vec<String>,
+    /// One array per key
+    pub columns: Vec<Array1<f64>>,
+    /// Index of the partial file of each particle
+    pub rank: Array1<usize>,
+    /// Index of each particle within its partial file
+    pub index: Array1<usize>,
+}
+
+impl ParticleTable {
+    pub fn n_particle(&self) -> usize {
+        self.rank.len()
+    }
+
+    pub fn column(&self, key: &str) -> Option<&Array1<f64>> {
+        self.keys
+            .iter()
+            .position(|k| k == key)
+            .map(|i| &self.columns[i])
+    }
+
+    /// Columns stacked in an array of shape (n_particle, n_key)
+    pub fn to_array(&self) -> Array2<f64> {
+        let mut array = Array2::zeros((self.n_particle(), self.keys.len()));
+        for (mut target, column) in array.columns_mut().into_iter().zip(&self.columns) {
+            target.assign(column);
+        }
+        array
+    }
+}
+
 /// A trait for postprocessing operations on simulation results.
 ///
 /// This trait defines various methods for analyzing and retrieving data from simulation results.
@@ -29,6 +124,9 @@ pub trait PostProcessReader {
 
     fn v_liquid(&self) -> ArrayView2<'_, f64>;
 
+    /// Returns the gas volume of each compartment, `None` if the gas phase is not present
+    fn v_gas(&self) -> Option<ArrayView2<'_, f64>>;
+
     /// Returns a weight chosen for simulation 
     ///
     /// # Returns
@@ -41,6 +139,40 @@ pub trait PostProcessReader {
     /// * `Option<&Tallies>: Some if tallies exported 
     fn tallies(&self) -> Option<&Tallies>;
 
+    /// Returns the parameters of the simulation at its start, see [`InitialParameters`].
+    fn get_initial_parameters(&self) -> InitialParameters;
+
+    /// Returns the results written at the end of the simulation, `None` while it is running.
+    fn get_final_summary(&self) -> Option<FinalSummary>;
+
+    /// Returns the parallel layout and the shapes of the run, see [`RunMetadata`].
+    fn get_run_metadata(&self) -> RunMetadata;
+
+    /// Returns the tallies with named columns, cumulative counts and rates, see [`TallyTable`].
+    fn get_tally_table(&self) -> Result<TallyTable, ApiError> {
+        let tallies = self
+            .tallies()
+            .ok_or_else(|| ApiError::RecordsError("tallies".to_string()))?;
+        TallyTable::new(tallies, self.time())
+    }
+
+    /// Returns the unit in which a kind of series is returned.
+    ///
+    /// Every series is returned in SI units as written by BioMC: time in s, concentrations in
+    /// kg/m3, volumes in m3 and rates per second.
+    fn unit(&self, quantity: Quantity) -> Unit {
+        quantity.unit()
+    }
+
+    /// Returns the time data converted to the given unit.
+    ///
+    /// # Arguments
+    /// * `unit` - Time unit, e.g. `Unit::Hour`.
+    fn time_in(&self, unit: Unit) -> Result<Array1<f64>, ApiError> {
+        self.unit(Quantity::Time)
+            .convert_array(&self.time_array(), unit)
+    }
+
     /// Returns a 1D array view of the time data from the simulation results.
     ///
     /// # Returns
@@ -56,6 +188,34 @@ pub trait PostProcessReader {
     /// * `usize` - The number of biological export events, or `0` if no events are found.
     fn get_max_n_export_bio(&self) -> usize;
 
+    /// Returns the exports at which biological data have been written by at least one rank.
+    ///
+    /// Exports are indices of [`PostProcessReader::time`], ranks only write the exports at which
+    /// they hold particles, see [`crate::datamodel::bio_exports`].
+    fn get_bio_exports(&self) -> Vec<usize>;
+
+    /// Returns the time of each export of [`PostProcessReader::get_bio_exports`].
+    fn get_bio_export_time(&self) -> Array1<f64> {
+        let time = self.time();
+        self.get_bio_exports().iter().map(|i| time[*i]).collect()
+    }
+
+    /// Finds the biological export nearest to a time.
+    ///
+    /// # Arguments
+    /// * `time` - Time in s.
+    ///
+    /// # Returns
+    /// * `usize` - Export index to pass to e.g. [`PostProcessReader::get_properties`], the
+    ///   earlier export on ties.
+    fn nearest_bio_export(&self, time: f64) -> Result<usize, ApiError> {
+        let exports = self.get_bio_exports();
+        let bio_time = self.get_bio_export_time().to_vec();
+        bio_exports::nearest(&bio_time, time)
+            .map(|i| exports[i])
+            .ok_or_else(|| ApiError::Default(format!("No biological export near t={}", time)))
+    }
+
     /// Retrieves the total number of export events from the simulation results.
     ///
     /// This count includes all types of export actions.
@@ -64,6 +224,38 @@ pub trait PostProcessReader {
     /// * `usize` - The total number of export events.
     fn n_export(&self) -> usize;
     
+    /// Returns the names of the species, ordered as the last axis of concentrations.
+    ///
+    /// Names are read from the result file when present, otherwise the user-provided mapping is
+    /// used and species are named `species_{i}` as a last resort.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - One name per species
+    fn species_names(&self) -> Vec<String>;
+
+    /// Returns the index of a species identified by its index or its name
+    ///
+    /// # Arguments
+    /// * `species` - Index or name of the species.
+    ///
+    /// # Returns
+    /// * `Result<usize, ApiError>` - The index, or an error if the species does not exist
+    fn species_index(&self, species: impl SpeciesKey) -> Result<usize, ApiError> {
+        species.species_index(&self.species_names())
+    }
+
+    /// Checks that the run has the phase `phase`, to be called before
+    /// [`PostProcessReader::get_concentrations`] which panics on a missing phase.
+    ///
+    /// # Returns
+    /// * `Result<(), ApiError>` - [`ApiError::missing_gas`] for the gas phase of a gas-free run.
+    fn check_phase(&self, phase: Phase) -> Result<(), ApiError> {
+        match phase {
+            Phase::Gas if self.v_gas().is_none() => Err(ApiError::missing_gas()),
+            _ => Ok(()),
+        }
+    }
+
     /// Returns model's property names  
     ///
     /// # Returns
@@ -73,12 +265,17 @@ pub trait PostProcessReader {
     /// Computes the spatial average concentration for a specific species and phase.
     ///
     /// # Arguments
-    /// * `species` - The index of the species for which to calculate the average.
+    /// * `species` - The index or the name of the species for which to calculate the average.
     /// * `phase` - The phase (e.g., liquid or gas) to consider.
     ///
     /// # Returns
-    /// * `Array1<f64>` - A 1D array containing the spatial average concentrations over time.
-    fn get_spatial_average_concentration(&self, species: usize, phase: Phase) -> Array1<f64>;
+    /// * `Result<Array1<f64>, ApiError>` - The spatial average concentrations over time, or an
+    ///   error if the species does not exist or if the phase is not present.
+    fn get_spatial_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError>;
 
     fn get_spatial_average_property(&self, key:&str) ->  Result<Array2<f64>, ApiError>;
 
@@ -86,26 +283,36 @@ pub trait PostProcessReader {
 
     fn get_concentrations(&self, phase: Phase) -> ArrayView3<f64>;
 
-    fn get_spatial_average_mtr(&self, species: usize) -> Result<Array1<f64>, ApiError>;
+    fn get_spatial_average_mtr(&self, species: impl SpeciesKey) -> Result<Array1<f64>, ApiError>;
 
-    fn get_variance_concentration(&self,species:usize,phase:Phase)-> Result<Array1<f64>, ApiError>;
+    /// Spatial variance of a species concentration at each export: the sum over the compartments
+    /// of the volume-weighted squared deviations from the spatial average, not divided by the
+    /// total volume.
+    fn get_variance_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError>;
 
     
     
 
     /// Computes the time average concentration for a specific species, position, and phase.
     ///
+    /// The average is taken over the export times from the first export, with the trapezoidal
+    /// rule so that unevenly spaced exports are weighted by their duration.
+    ///
     /// # Arguments
-    /// * `species` - The index of the species for which to calculate the average.
-    /// * `position` - The position in the simulation domain to consider.
+    /// * `species` - The index or the name of the species for which to calculate the average.
+    /// * `position` - Index of the compartment to consider.
     /// * `phase` - The phase (e.g., liquid or gas) to consider.
     ///
     /// # Returns
-    /// * `Result<Array1<f64>, String>` - A 1D array containing the time average concentrations,
-    ///   or an error message if the calculation fails.
+    /// * `Result<Array1<f64>, ApiError>` - Average from the first export up to each export, or
+    ///   `OutOfRange` if the compartment does not exist.
     fn get_time_average_concentration(
         &self,
-        species: usize,
+        species: impl SpeciesKey,
         position: usize,
         phase: Phase,
     ) -> Result<Array1<f64>, ApiError>;
@@ -143,6 +350,15 @@ pub trait PostProcessReader {
     ///   or an error message if the retrieval fails.
     fn get_properties(&self, key: &str, i_export: usize) -> Result<Array1<f64>, ApiError>;
 
+    /// Reads several properties of the particles at a given export in a single pass over the
+    /// partial files.
+    ///
+    /// # Returns
+    /// * `Result<ParticleTable, ApiError>` - One column per key with the rank and local index of
+    ///   each particle.
+    fn get_particle_table(&self, keys: &[&str], i_export: usize)
+        -> Result<ParticleTable, ApiError>;
+
     /// Calculates the time-averaged population mean for a specific property key.
     ///
     /// # Arguments
@@ -196,6 +412,539 @@ pub trait PostProcessReader {
     /// # Returns
     /// * `Result<f64, String>` - The population mean, or an error message if the calculation fails.
     fn get_population_mean(&self, key: &str, i_export: usize) -> Result<f64, ApiError>;
+
+    /// Fetches a property of the particles selected by a filter at a given export index.
+    ///
+    /// # Arguments
+    /// * `key` - The key identifying the property to retrieve.
+    /// * `i_export` - The export index for which to retrieve the property.
+    /// * `filter` - Predicate on the properties of each particle.
+    ///
+    /// # Returns
+    /// * `Result<(Array1<f64>, f64), ApiError>` - Values of the selected particles and the
+    ///   fraction of particles selected.
+    fn get_properties_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(Array1<f64>, f64), ApiError>;
+
+    /// Retrieves histogram data of the particles selected by a filter.
+    ///
+    /// # Returns
+    /// * `Result<(Vec<f64>, Vec<f64>, f64), ApiError>` - The histogram bins and counts and the
+    ///   fraction of particles selected.
+    fn get_histogram_filtered(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+        filter: &ParticleFilter,
+    ) -> Result<(Vec<f64>, Vec<f64>, f64), ApiError>;
+
+    /// Retrieves the mean of a property over the particles selected by a filter.
+    ///
+    /// # Returns
+    /// * `Result<(f64, f64), ApiError>` - The mean, NaN if no particle is selected, and the
+    ///   fraction of particles selected.
+    fn get_population_mean_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError>;
+}
+
+/// Zone-level quantities, see [`Zoning`].
+///
+/// Concentrations are volume-weighted over the compartments of each zone, particle properties
+/// are weighted by the number of particles and counts are summed.
+/// Every method is available on any [`PostProcessReader`].
+pub trait ZoneReader: PostProcessReader {
+    /// Returns the volume of each zone, shape (nt, n_zone).
+    fn get_zone_volume(&self, zoning: &Zoning, phase: Phase) -> Result<Array2<f64>, ApiError> {
+        let volume = phase_volume(self, phase)?;
+        zoning.check(volume.ncols())?;
+        Ok(zoning.sum(&volume))
+    }
+
+    /// Returns the volume-weighted concentrations of each zone, shape (nt, n_zone, n_species).
+    fn get_zone_concentrations(
+        &self,
+        zoning: &Zoning,
+        phase: Phase,
+    ) -> Result<Array3<f64>, ApiError> {
+        let volume = phase_volume(self, phase)?;
+        zoning.check(volume.ncols())?;
+        let concentrations = self.get_concentrations(phase);
+        Ok(map_last_axis(&concentrations, zoning.n_zone(), |c| {
+            zoning.weighted_mean(c, &volume)
+        }))
+    }
+
+    /// Returns the biomass concentration of each zone, shape (nt, n_zone).
+    fn get_zone_biomass_concentration(&self, zoning: &Zoning) -> Result<Array2<f64>, ApiError> {
+        let volume = self.v_liquid();
+        zoning.check(volume.ncols())?;
+        let biomass = self.get_biomass_concentration()?;
+        Ok(zoning.weighted_mean(&biomass.view(), &volume))
+    }
+
+    /// Returns the mean of a particle property in each zone, shape (nt, n_zone).
+    ///
+    /// Compartment means are weighted by the number of particles, so that the result is the mean
+    /// over the particles of the zone.
+    fn get_zone_spatial_average_property(
+        &self,
+        key: &str,
+        zoning: &Zoning,
+    ) -> Result<Array2<f64>, ApiError> {
+        let number = self.get_number_particle();
+        zoning.check(number.ncols())?;
+        // Empty compartments have a NaN mean and no weight
+        let property = self
+            .get_spatial_average_property(key)?
+            .mapv_into(|x| if x.is_nan() { 0. } else { x });
+        Ok(zoning.weighted_mean(&property.view(), &number.view()))
+    }
+
+    /// Returns the number of particles in each zone, shape (nt, n_zone).
+    fn get_zone_number_particle(&self, zoning: &Zoning) -> Result<Array2<f64>, ApiError> {
+        let number = self.get_number_particle();
+        zoning.check(number.ncols())?;
+        Ok(zoning.sum(&number.view()))
+    }
+
+    /// Returns the volume-weighted variance of a species concentration within each zone,
+    /// shape (nt, n_zone), see [`Zoning::weighted_variance`].
+    fn get_zone_variance_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+        zoning: &Zoning,
+    ) -> Result<Array2<f64>, ApiError> {
+        let species = self.species_index(species)?;
+        let volume = phase_volume(self, phase)?;
+        zoning.check(volume.ncols())?;
+        let concentrations = self.get_concentrations(phase);
+        let c = concentrations.index_axis(ndarray::Axis(2), species);
+        Ok(zoning.weighted_variance(&c, &volume))
+    }
+}
+
+impl<T: PostProcessReader> ZoneReader for T {}
+
+fn phase_volume<R: PostProcessReader + ?Sized>(
+    reader: &R,
+    phase: Phase,
+) -> Result<ArrayView2<'_, f64>, ApiError> {
+    match phase {
+        Phase::Liquid => Ok(reader.v_liquid()),
+        Phase::Gas => reader.v_gas().ok_or_else(ApiError::missing_gas),
+    }
+}
+
+/// Growth rate estimators that smooth the Monte Carlo noise of [`ModelEstimator::mu_direct`]
+/// and give a standard error at each export, see [`GrowthRate`].
+///
+/// `window` is the number of exports of the sliding regression window.
+/// Every method is available on any [`PostProcessReader`].
+pub trait GrowthEstimator: PostProcessReader {
+    /// Returns the total mass represented by the particles at each export, `Σ mass·weight`.
+    fn get_total_mass(&self) -> Result<Array1<f64>, ApiError> {
+        total_mass(self)
+    }
+
+    /// Specific growth rate from a Savitzky-Golay polynomial of degree `order` fitted to the
+    /// total mass.
+    fn mu_savitzky_golay(&self, window: usize, order: usize) -> Result<GrowthRate, ApiError> {
+        check_window(self, window, order)?;
+        let mass = self.get_total_mass()?;
+        Ok(savitzky_golay_rate(self.time(), &mass.to_vec(), window, order))
+    }
+
+    /// Specific growth rate from a line fitted to the logarithm of the total mass.
+    fn mu_log_linear(&self, window: usize) -> Result<GrowthRate, ApiError> {
+        check_window(self, window, 1)?;
+        let mass = self.get_total_mass()?;
+        Ok(log_linear_rate(self.time(), &mass.to_vec(), window))
+    }
+
+    /// Growth rate in number from a line fitted to the logarithm of
+    /// [`PostProcessReader::get_growth_in_number`].
+    fn mu_number(&self, window: usize) -> Result<GrowthRate, ApiError> {
+        check_window(self, window, 1)?;
+        let number = self.get_growth_in_number();
+        Ok(log_linear_rate(self.time(), &number.to_vec(), window))
+    }
+}
+
+impl<T: PostProcessReader> GrowthEstimator for T {}
+
+/// The window must fit a polynomial of degree `order` to the exports
+fn check_window<R: PostProcessReader + ?Sized>(
+    reader: &R,
+    window: usize,
+    order: usize,
+) -> Result<(), ApiError> {
+    let nt = reader.n_export();
+    if window <= order || window > nt {
+        return Err(ApiError::OutOfRange(window, nt));
+    }
+    Ok(())
+}
+
+/// Resampling of the time series onto a custom grid, see [`Method`].
+///
+/// Grids are in seconds and must be increasing. With [`Method::BinAverage`] the grid holds the
+/// edges of the bins.
+///
+/// # Example
+/// ```ignore
+/// let grid = uniform_grid(0., 3600., 60.);
+/// let x = pp.resample_biomass_concentration(&grid, Method::MonotoneCubic)?;
+/// ```
+pub trait Resampler: PostProcessReader {
+    /// Resamples any series of the run along its first axis, one row per export.
+    fn resample<D: Dimension + RemoveAxis>(
+        &self,
+        values: ArrayView<f64, D>,
+        grid: &[f64],
+        method: Method,
+    ) -> Result<Resampled<D>, ApiError> {
+        check_grid(grid, method)?;
+        if values.len_of(Axis(0)) != self.n_export() {
+            return Err(ApiError::ShapeError);
+        }
+        Ok(resample_axis(self.time(), &values, grid, method))
+    }
+
+    /// Concentration fields resampled, shape (n_grid, n_compartment, n_species).
+    fn resample_concentrations(
+        &self,
+        phase: Phase,
+        grid: &[f64],
+        method: Method,
+    ) -> Result<Resampled<Ix3>, ApiError> {
+        self.check_phase(phase)?;
+        self.resample(self.get_concentrations(phase), grid, method)
+    }
+
+    /// Biomass concentration resampled, shape (n_grid, n_compartment).
+    fn resample_biomass_concentration(
+        &self,
+        grid: &[f64],
+        method: Method,
+    ) -> Result<Resampled<Ix2>, ApiError> {
+        self.resample(self.get_biomass_concentration()?.view(), grid, method)
+    }
+
+    /// Estimator of a particle property resampled, see [`ModelEstimator::estimate_time`].
+    fn resample_estimate(
+        &self,
+        etype: Estimator,
+        key: &str,
+        grid: &[f64],
+        method: Method,
+    ) -> Result<Resampled<Ix1>, ApiError>
+    where
+        Self: ModelEstimator,
+    {
+        self.resample(self.estimate_time(etype, key)?.view(), grid, method)
+    }
+
+    /// Cumulative tallies resampled, columns as in [`PostProcessReader::get_tally_table`].
+    fn resample_tallies(&self, grid: &[f64], method: Method) -> Result<Resampled<Ix2>, ApiError> {
+        let table = self.get_tally_table()?;
+        check_grid(grid, method)?;
+        let time = table.time.to_vec();
+        Ok(resample_axis(&time, &table.cumulative.view(), grid, method))
+    }
+}
+
+impl<T: PostProcessReader> Resampler for T {}
+
+/// Grids must be increasing, with at least one bin for [`Method::BinAverage`]
+fn check_grid(grid: &[f64], method: Method) -> Result<(), ApiError> {
+    let n_min = if method == Method::BinAverage { 2 } else { 1 };
+    if grid.len() < n_min {
+        return Err(ApiError::OutOfRange(grid.len(), n_min));
+    }
+    if grid.iter().any(|t| !t.is_finite()) || grid.windows(2).any(|w| w[1] <= w[0]) {
+        return Err(ApiError::Default(
+            "Resampling grid must be finite and increasing".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Integrals over time of the spatial averages, see [`Rule`].
+///
+/// Cumulative integrals start at 0 at the first export and use the trapezoidal rule. Window
+/// averages are restricted to the exported time range.
+///
+/// # Example
+/// ```ignore
+/// let fed = pp.cumulative_mtr("oxygen")?;
+/// let x_mean = pp.window_average_biomass_concentration(3600., 7200.)?;
+/// ```
+pub trait TimeIntegrator: PostProcessReader {
+    /// Integral of a series of the run over the export times, one value per export.
+    fn integrate(&self, series: ArrayView1<f64>, rule: Rule) -> Result<f64, ApiError> {
+        check_series(self, &series)?;
+        Ok(integrate(self.time(), &series, rule))
+    }
+
+    /// Integral of a series of the run from the first export to each export.
+    fn cumulative(&self, series: ArrayView1<f64>) -> Result<Array1<f64>, ApiError> {
+        check_series(self, &series)?;
+        Ok(cumulative_trapezoid(self.time(), &series))
+    }
+
+    /// Time average of a series of the run over `[start, end]` (s).
+    fn window_average(
+        &self,
+        series: ArrayView1<f64>,
+        start: f64,
+        end: f64,
+    ) -> Result<f64, ApiError> {
+        check_series(self, &series)?;
+        if start.is_nan() || end.is_nan() || start >= end {
+            return Err(ApiError::Default(format!("Empty time window [{}, {}]", start, end)));
+        }
+        Ok(window_average(self.time(), &series, start, end))
+    }
+
+    /// Area under the spatial average concentration, in kg.s/m3.
+    fn cumulative_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
+        self.cumulative(self.get_spatial_average_concentration(species, phase)?.view())
+    }
+
+    /// Area under the spatial average biomass concentration, in kg.s/m3.
+    fn cumulative_biomass_concentration(&self) -> Result<Array1<f64>, ApiError> {
+        self.cumulative(self.get_spatial_average_biomass_concentration()?.view())
+    }
+
+    /// Mass transferred per unit volume since the first export, in kg/m3.
+    fn cumulative_mtr(&self, species: impl SpeciesKey) -> Result<Array1<f64>, ApiError> {
+        self.cumulative(self.get_spatial_average_mtr(species)?.view())
+    }
+
+    /// Time average of the spatial average concentration over `[start, end]`.
+    fn window_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+        start: f64,
+        end: f64,
+    ) -> Result<f64, ApiError> {
+        let species = self.species_index(species)?;
+        let c = self.get_spatial_average_concentration(species, phase)?;
+        self.window_average(c.view(), start, end)
+    }
+
+    /// Time average of the spatial average biomass concentration over `[start, end]`.
+    fn window_average_biomass_concentration(&self, start: f64, end: f64) -> Result<f64, ApiError> {
+        let x = self.get_spatial_average_biomass_concentration()?;
+        self.window_average(x.view(), start, end)
+    }
+
+    /// Time average of the spatial average mass transfer rate over `[start, end]`.
+    fn window_average_mtr(
+        &self,
+        species: impl SpeciesKey,
+        start: f64,
+        end: f64,
+    ) -> Result<f64, ApiError> {
+        let mtr = self.get_spatial_average_mtr(species)?;
+        self.window_average(mtr.view(), start, end)
+    }
+}
+
+impl<T: PostProcessReader> TimeIntegrator for T {}
+
+/// Series must have one value per export
+fn check_series<R: PostProcessReader + ?Sized>(
+    reader: &R,
+    series: &ArrayView1<f64>,
+) -> Result<(), ApiError> {
+    if series.len() != reader.n_export() {
+        return Err(ApiError::ShapeError);
+    }
+    Ok(())
+}
+
+/// Steady state of the series of a run, see [`Detector`].
+///
+/// # Example
+/// ```ignore
+/// if let Some(steady) = pp.steady_state_biomass(Detector::mser(), 0.95)? {
+///     println!("X = {} [{}, {}] from t={}", steady.mean, steady.lower, steady.upper, steady.time);
+/// }
+/// ```
+pub trait SteadyStateDetector: PostProcessReader {
+    /// Detects the steady state of a series with one value per export.
+    fn steady_state(
+        &self,
+        series: ArrayView1<f64>,
+        detector: Detector,
+        level: f64,
+    ) -> Result<Option<SteadyState>, ApiError> {
+        check_series(self, &series)?;
+        detect(self.time(), &series.to_vec(), detector, level)
+    }
+
+    /// Steady state of the spatial average biomass concentration.
+    fn steady_state_biomass(
+        &self,
+        detector: Detector,
+        level: f64,
+    ) -> Result<Option<SteadyState>, ApiError> {
+        let x = self.get_spatial_average_biomass_concentration()?;
+        self.steady_state(x.view(), detector, level)
+    }
+
+    /// Steady state of the spatial average concentration of every species, in the order of
+    /// [`PostProcessReader::species_names`].
+    ///
+    /// The gas phase of a run without gas is a [`ApiError::RecordsError`].
+    fn steady_state_species(
+        &self,
+        phase: Phase,
+        detector: Detector,
+        level: f64,
+    ) -> Result<Vec<(String, Option<SteadyState>)>, ApiError> {
+        self.check_phase(phase)?;
+        self.species_names()
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let c = self.get_spatial_average_concentration(i, phase)?;
+                Ok((name, self.steady_state(c.view(), detector, level)?))
+            })
+            .collect()
+    }
+
+    /// Steady state of the population mean of a particle property.
+    fn steady_state_population_mean(
+        &self,
+        key: &str,
+        detector: Detector,
+        level: f64,
+    ) -> Result<Option<SteadyState>, ApiError> {
+        let mean = self.get_time_population_mean(key)?;
+        self.steady_state(mean.view(), detector, level)
+    }
+}
+
+impl<T: PostProcessReader> SteadyStateDetector for T {}
+
+/// Oscillations of the concentrations and of the population means, see [`crate::periodogram`]
+/// and [`crate::autocorrelation_time`].
+///
+/// Series exported on a non-uniform grid are first interpolated linearly onto a uniform grid with
+/// the median time step, means are removed before the analysis.
+///
+/// The dominant period of the concentration seen in the compartments estimates the circulation
+/// time, the autocorrelation time is the timescale of the fluctuations experienced by the cells.
+pub trait SpectralAnalyzer: PostProcessReader {
+    /// Periodogram of the concentration of a species in one compartment.
+    fn concentration_periodogram(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+        compartment: usize,
+    ) -> Result<Periodogram, ApiError> {
+        let c = compartment_series(self, species, phase)?;
+        if compartment >= c.ncols() {
+            return Err(ApiError::OutOfRange(compartment, c.ncols()));
+        }
+        periodogram(self.time(), &c.column(compartment).to_vec())
+    }
+
+    /// Dominant period of the concentration in each compartment, NaN without oscillation.
+    fn dominant_period_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let c = compartment_series(self, species, phase)?;
+        c.columns()
+            .into_iter()
+            .map(|c| {
+                let p = periodogram(self.time(), &c.to_vec())?;
+                Ok(p.dominant_period().unwrap_or(f64::NAN))
+            })
+            .collect()
+    }
+
+    /// Autocorrelation time of the concentration in each compartment, in s.
+    fn autocorrelation_time_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let c = compartment_series(self, species, phase)?;
+        c.columns()
+            .into_iter()
+            .map(|c| autocorrelation_time(self.time(), &c.to_vec()))
+            .collect()
+    }
+
+    /// Circulation time estimated as the median over the compartments of the dominant period
+    /// of the concentration, NaN if no compartment oscillates.
+    fn circulation_time(&self, species: impl SpeciesKey, phase: Phase) -> Result<f64, ApiError> {
+        let mut periods: Vec<f64> = self
+            .dominant_period_concentration(species, phase)?
+            .into_iter()
+            .filter(|p| p.is_finite())
+            .collect();
+        if periods.is_empty() {
+            return Ok(f64::NAN);
+        }
+        periods.sort_by(f64::total_cmp);
+        let n = periods.len();
+        Ok(if n % 2 == 1 {
+            periods[n / 2]
+        } else {
+            0.5 * (periods[n / 2 - 1] + periods[n / 2])
+        })
+    }
+
+    /// Periodogram of the population mean of a particle property.
+    fn population_mean_periodogram(&self, key: &str) -> Result<Periodogram, ApiError> {
+        let mean = self.get_time_population_mean(key)?;
+        periodogram(self.time(), &mean.to_vec())
+    }
+
+    /// Autocorrelation time of the population mean of a particle property, in s.
+    fn autocorrelation_time_population_mean(&self, key: &str) -> Result<f64, ApiError> {
+        let mean = self.get_time_population_mean(key)?;
+        autocorrelation_time(self.time(), &mean.to_vec())
+    }
+}
+
+impl<T: PostProcessReader> SpectralAnalyzer for T {}
+
+/// Concentration of a species in each compartment, shape (nt, n_compartment)
+fn compartment_series<R: PostProcessReader + ?Sized>(
+    reader: &R,
+    species: impl SpeciesKey,
+    phase: Phase,
+) -> Result<Array2<f64>, ApiError> {
+    let species = reader.species_index(species)?;
+    reader.check_phase(phase)?;
+    let c = reader.get_concentrations(phase);
+    if species >= c.len_of(Axis(2)) {
+        return Err(ApiError::OutOfRange(species, c.len_of(Axis(2))));
+    }
+    Ok(c.index_axis(Axis(2), species).to_owned())
 }
 
 pub trait ModelEstimator {
@@ -204,6 +953,18 @@ pub trait ModelEstimator {
     fn estimate(&self, etype: Estimator, key: &str, i_export: usize) -> Result<f64, ApiError>;
 
     fn estimate_time(&self, etype: Estimator, key: &str) -> Result<Array1<f64>, ApiError>;
+
+    /// Estimates a property over the particles selected by a filter.
+    ///
+    /// # Returns
+    /// * `Result<(f64, f64), ApiError>` - The estimate and the fraction of particles selected.
+    fn estimate_filtered(
+        &self,
+        etype: Estimator,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError>;
 }
 
 #[cfg(test)]
@@ -258,4 +1019,20 @@ mod tests {
         // With an empty array, the result should be zero
         assert_eq!(result, 0.0);
     }
+
+    #[test]
+    fn test_particle_table() {
+        let table = ParticleTable {
+            keys: vec!["mass".to_string(), "age".to_string()],
+            columns: vec![ndarray::array![1., 2., 3.], ndarray::array![10., 20., 30.]],
+            rank: ndarray::array![0, 0, 1],
+            index: ndarray::array![0, 1, 0],
+        };
+        assert_eq!(table.column("age"), Some(&ndarray::array![10., 20., 30.]));
+        assert!(table.column("length").is_none());
+        assert_eq!(
+            table.to_array(),
+            ndarray::array![[1., 10.], [2., 20.], [3., 30.]]
+        );
+    }
 }
@@ -0,0 +1,207 @@
+//! Comparison of two runs, e.g. before and after a change of the biological model.
+//!
+//! Both runs are interpolated onto a common time grid before their time series are compared and
+//! particle distributions are compared at export times shared by both runs.
+use crate::api::{Phase, PostProcessReader};
+use crate::error::ApiError;
+use crate::process::interpolate::{common_grid, linear};
+use crate::process::stats::{ks_statistic, wasserstein_distance};
+use ndarray::Array1;
+
+/// Tolerance under which two export times are considered equal
+const TIME_TOLERANCE: f64 = 1e-9;
+
+/// A time series of both runs on the common grid
+#[derive(Debug, Clone)]
+pub struct SeriesDiff {
+    pub name: String,
+    pub a: Array1<f64>,
+    pub b: Array1<f64>,
+}
+
+impl SeriesDiff {
+    /// `b - a`
+    pub fn abs_diff(&self) -> Array1<f64> {
+        &self.b - &self.a
+    }
+
+    /// `(b - a) / |a|`, NaN where `a` is zero
+    pub fn rel_diff(&self) -> Array1<f64> {
+        ndarray::Zip::from(&self.a)
+            .and(&self.b)
+            .map_collect(|a, b| {
+                if *a == 0. {
+                    f64::NAN
+                } else {
+                    (b - a) / a.abs()
+                }
+            })
+    }
+
+    /// Root mean square of `b - a`
+    pub fn rmse(&self) -> f64 {
+        self.abs_diff().powi(2).mean().unwrap_or(f64::NAN).sqrt()
+    }
+
+    /// Largest absolute difference
+    pub fn max_abs_diff(&self) -> f64 {
+        self.abs_diff().iter().fold(f64::NAN, |m, d| m.max(d.abs()))
+    }
+}
+
+/// Time series of two runs aligned on a common time grid
+#[derive(Debug, Clone)]
+pub struct RunComparison {
+    /// Union of the export times of both runs, restricted to their overlap
+    pub time: Vec<f64>,
+    pub series: Vec<SeriesDiff>,
+}
+
+impl RunComparison {
+    pub fn get(&self, name: &str) -> Option<&SeriesDiff> {
+        self.series.iter().find(|s| s.name == name)
+    }
+}
+
+/// Distances between the distributions of a property at an export time shared by both runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributionDistance {
+    pub time: f64,
+    pub i_export_a: usize,
+    pub i_export_b: usize,
+    pub wasserstein: f64,
+    pub ks: f64,
+}
+
+/// Compares the time series of two runs.
+///
+/// Series are the spatial average biomass concentration (`biomass`), the spatial average
+/// concentration of each species in the liquid (`liquid:<species>`) and, when both runs have
+/// them, in the gas (`gas:<species>`) and the mass transfer (`mtr:<species>`).
+/// Species are matched by name.
+///
+/// # Example
+/// ```ignore
+/// let c = compare(&before, &after)?;
+/// println!("{}", c.get("liquid:glucose").unwrap().rmse());
+/// ```
+pub fn compare<A, B>(a: &A, b: &B) -> Result<RunComparison, ApiError>
+where
+    A: PostProcessReader,
+    B: PostProcessReader,
+{
+    let time = common_grid(a.time(), b.time(), TIME_TOLERANCE);
+    if time.is_empty() {
+        return Err(ApiError::Default("Runs do not overlap in time".to_string()));
+    }
+    let on_grid = |name: String, sa: Array1<f64>, sb: Array1<f64>| SeriesDiff {
+        name,
+        a: linear(a.time(), &sa.view(), &time),
+        b: linear(b.time(), &sb.view(), &time),
+    };
+
+    let mut series = vec![on_grid(
+        "biomass".to_string(),
+        a.get_spatial_average_biomass_concentration()?,
+        b.get_spatial_average_biomass_concentration()?,
+    )];
+
+    let names_b = b.species_names();
+    let shared: Vec<(usize, usize, String)> = a
+        .species_names()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(ia, name)| {
+            names_b
+                .iter()
+                .position(|n| *n == name)
+                .map(|ib| (ia, ib, name))
+        })
+        .collect();
+
+    let has_gas = a.v_gas().is_some() && b.v_gas().is_some();
+    for (ia, ib, name) in &shared {
+        series.push(on_grid(
+            format!("liquid:{}", name),
+            a.get_spatial_average_concentration(*ia, Phase::Liquid)?,
+            b.get_spatial_average_concentration(*ib, Phase::Liquid)?,
+        ));
+        if has_gas {
+            series.push(on_grid(
+                format!("gas:{}", name),
+                a.get_spatial_average_concentration(*ia, Phase::Gas)?,
+                b.get_spatial_average_concentration(*ib, Phase::Gas)?,
+            ));
+        }
+        if let (Ok(ma), Ok(mb)) = (
+            a.get_spatial_average_mtr(*ia),
+            b.get_spatial_average_mtr(*ib),
+        ) {
+            series.push(on_grid(format!("mtr:{}", name), ma, mb));
+        }
+    }
+
+    Ok(RunComparison { time, series })
+}
+
+/// Compares the distributions of a property at every export time shared by both runs and for
+/// which both have particle data.
+pub fn compare_distributions<A, B>(
+    a: &A,
+    b: &B,
+    key: &str,
+) -> Result<Vec<DistributionDistance>, ApiError>
+where
+    A: PostProcessReader,
+    B: PostProcessReader,
+{
+    let (ta, tb) = (a.time(), b.time());
+    let n_a = a.get_max_n_export_bio().min(ta.len());
+    let n_b = b.get_max_n_export_bio().min(tb.len());
+
+    let mut distances = vec![];
+    let mut j = 0;
+    for (i, t) in ta.iter().enumerate().take(n_a) {
+        while j < n_b && tb[j] < t - TIME_TOLERANCE {
+            j += 1;
+        }
+        if j == n_b {
+            break;
+        }
+        if (tb[j] - t).abs() <= TIME_TOLERANCE {
+            let pa = a.get_properties(key, i)?;
+            let pb = b.get_properties(key, j)?;
+            let (pa, pb) = (pa.to_vec(), pb.to_vec());
+            distances.push(DistributionDistance {
+                time: *t,
+                i_export_a: i,
+                i_export_b: j,
+                wasserstein: wasserstein_distance(&pa, &pb),
+                ks: ks_statistic(&pa, &pb),
+            });
+        }
+    }
+    Ok(distances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_series_diff() {
+        let s = SeriesDiff {
+            name: "biomass".to_string(),
+            a: array![1., 2., 0.],
+            b: array![2., 1., 1.],
+        };
+        assert_eq!(s.abs_diff(), array![1., -1., 1.]);
+        let rel = s.rel_diff();
+        assert_eq!(rel[0], 1.);
+        assert_eq!(rel[1], -0.5);
+        assert!(rel[2].is_nan());
+        assert_eq!(s.rmse(), 1.);
+        assert_eq!(s.max_abs_diff(), 1.);
+    }
+}
@@ -1,12 +1,17 @@
+use crate::api::ParticleTable;
 use crate::error::ApiError;
+use crate::process::filter::select;
 use crate::process::Histogram;
 
 use super::main_file::{MainFInal, MainInitial, MainRecords, Misc};
 use super::tallies::Tallies;
-use super::{Dim, ResultGroup};
+use super::bio_exports::BioExportMap;
+use super::{Dim, Property, ResultGroup, Selection};
+use hdf5::types::{VarLenAscii, VarLenUnicode};
 use hdf5::Group;
-use ndarray::{s, Array1, Array2, ArrayView1};
+use ndarray::{s, Array1, Array2, ArrayView1, Ix1};
 use std::collections::HashMap;
+use std::ops::Range;
 
 macro_rules! read_scalar {
     // Match the types f64, usize, or u64 and provide the correct default behavior
@@ -62,22 +67,277 @@ pub fn read_number_particle(filename: &str) -> hdf5::Result<Vec<f64>> {
     Ok(v)
 }
 
+/// Opens a file that may still be written by a running simulation.
+///
+/// The file is opened in SWMR read mode when the library and the writer support it, which does
+/// not require file locking to be disabled, and in plain read mode otherwise.
+pub fn open_live(filename: &str) -> hdf5::Result<hdf5::File> {
+    if let Some(file) = swmr::open(filename) {
+        return Ok(file);
+    }
+    hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)
+}
+
+/// SWMR read mode, which the `hdf5` crate does not expose. `hdf5-sys` only defines its flag for
+/// HDF5 >= 1.10, `cfg(hdf5_1_10)` is set by the build script in that case.
+#[cfg(hdf5_1_10)]
+mod swmr {
+    use hdf5_sys::h5f::{H5Fopen, H5F_ACC_RDONLY, H5F_ACC_SWMR_READ};
+    use hdf5_sys::h5p::H5P_DEFAULT;
+    use std::ffi::CString;
+
+    /// `None` if the file cannot be opened in SWMR mode, e.g. when the writer has not enabled it
+    pub fn open(filename: &str) -> Option<hdf5::File> {
+        let name = CString::new(filename).ok()?;
+        // `hdf5::sync::sync` is the lock of the `hdf5` crate around every call into the library,
+        // it also initialises the library and silences its error stack. This is the only raw call
+        // made by the crate, it must hold the lock like the calls of `hdf5` do.
+        hdf5::sync::sync(|| {
+            let flags = H5F_ACC_RDONLY | H5F_ACC_SWMR_READ;
+            // SAFETY: `name` is a NUL-terminated string alive for the whole call and the default
+            // property list is a valid access list. `H5Fopen` only reads its arguments and returns
+            // a negative value on failure.
+            let id = unsafe { H5Fopen(name.as_ptr(), flags, H5P_DEFAULT) };
+            if id < 0 {
+                return None;
+            }
+            // SAFETY: `id` is a file identifier just returned by `H5Fopen` and not shared, the
+            // `File` takes ownership of it and closes it when dropped.
+            unsafe { hdf5::from_id::<hdf5::File>(id) }.ok()
+        })
+    }
+}
+
+/// HDF5 < 1.10 has no SWMR mode, files are always opened in plain read mode
+#[cfg(not(hdf5_1_10))]
+mod swmr {
+    pub fn open(_filename: &str) -> Option<hdf5::File> {
+        None
+    }
+}
+
+/// Exports `from..` of a dataset whose first axis is the export, flattened in row-major order.
+///
+/// 1D datasets are read as flattened rows of `row_size` values.
+fn read_rows(dataset: &hdf5::Dataset, from: usize, row_size: usize) -> hdf5::Result<Vec<f64>> {
+    let shape = dataset.shape();
+    match *shape.as_slice() {
+        [len] => {
+            let start = from * row_size;
+            if start >= len {
+                return Ok(vec![]);
+            }
+            Ok(dataset.read_slice_1d::<f64, _>(start..len)?.to_vec())
+        }
+        [nt, _] => {
+            if from >= nt {
+                return Ok(vec![]);
+            }
+            Ok(dataset
+                .read_slice_2d::<f64, _>((from..nt, ..))?
+                .iter()
+                .copied()
+                .collect())
+        }
+        [nt, n1, n2] => {
+            if from >= nt {
+                return Ok(vec![]);
+            }
+            // One plane of the last axis at a time, the reader has no 3D slices
+            let mut rows = vec![0.; (nt - from) * n1 * n2];
+            for k in 0..n2 {
+                let plane = dataset.read_slice_2d::<f64, _>((from..nt, .., k))?;
+                for ((i, j), value) in plane.indexed_iter() {
+                    rows[(i * n1 + j) * n2 + k] = *value;
+                }
+            }
+            Ok(rows)
+        }
+        _ => {
+            let mut rows = dataset.read_raw::<f64>()?;
+            let row: usize = shape.iter().skip(1).product();
+            rows.drain(..(from * row).min(rows.len()));
+            Ok(rows)
+        }
+    }
+}
+
+/// Particle counts of the exports `from..` of a partial file, flattened (nt - from, n_compartment)
+pub fn read_number_particle_from(
+    filename: &str,
+    from: usize,
+    n_compartment: usize,
+) -> hdf5::Result<Vec<f64>> {
+    let file = open_live(filename)?;
+    let rec = file.group("/records")?;
+    read_rows(&rec.dataset("number_particle")?, from, n_compartment)
+}
+
+/// Records of the main file from export `from` on, and its final results if already written.
+///
+/// Only the new rows are read. Tallies may lag behind the other records, they are read from row
+/// `tallies_from` with `tallies_n_column` columns when the number of columns is already known.
+pub fn read_records_from(
+    filename: &str,
+    from: usize,
+    dim: &Dim,
+    tallies_from: usize,
+    tallies_n_column: Option<usize>,
+) -> hdf5::Result<(MainRecords, Option<MainFInal>)> {
+    let file = open_live(filename)?;
+    let group = file.group("records")?;
+    let (n_c, n_cs) = (dim.0, dim.0 * dim.1);
+    let rows = |name: &str, row_size: usize| -> hdf5::Result<Option<Vec<f64>>> {
+        match group.dataset(name) {
+            Ok(dataset) => Ok(Some(read_rows(&dataset, from, row_size)?)),
+            Err(_) => Ok(None),
+        }
+    };
+
+    let concentration_liquid = rows("concentration_liquid", n_cs)?.unwrap_or_default();
+    let volume_liquid = rows("volume_liquid", n_c)?.unwrap_or_default();
+    let (concentration_gas, volume_gas) = match (
+        rows("concentration_gas", n_cs)?,
+        rows("volume_gas", n_c)?,
+    ) {
+        (Some(cg), Some(vg)) => (Some(cg), Some(vg)),
+        _ => (None, None),
+    };
+    let mtr = rows("mtr", n_cs)?;
+    let time = rows("time", 1)?.unwrap_or_default();
+
+    let tallies = match group.dataset("tallies") {
+        Ok(dataset) => {
+            let n_column = tallies_n_column
+                .unwrap_or_else(|| Tallies::detect_n_column(&dataset.shape(), from + time.len()));
+            Some(
+                Tallies::new(read_rows(&dataset, tallies_from, n_column)?, n_column)
+                    .with_cumulative(tallies_are_cumulative(&dataset)),
+            )
+        }
+        Err(_) => None,
+    };
+
+    let cfinal = match file.group("final_result") {
+        Ok(group) => Some(ResultGroup::<MainFInal>::read_g(&group)?),
+        Err(_) => None,
+    };
+
+    let records = MainRecords {
+        concentration_liquid,
+        volume_liquid,
+        concentration_gas,
+        volume_gas,
+        mtr,
+        tallies,
+        dim: Dim(dim.0, dim.1),
+        time,
+        species_names: vec![],
+    };
+    Ok((records, cfinal))
+}
+
+/// Layout of a tallies dataset from its [`Tallies::CUMULATIVE_ATTRIBUTE`], cumulative if missing
+fn tallies_are_cumulative(dataset: &hdf5::Dataset) -> bool {
+    let flag = dataset
+        .attr(Tallies::CUMULATIVE_ATTRIBUTE)
+        .and_then(|a| a.read_scalar::<i64>());
+    !matches!(flag, Ok(0))
+}
+
+/// Number of biological groups of a partial file and their `time` attribute, `None` if some
+/// group has no such attribute
+pub fn read_group_times(filename: &str) -> hdf5::Result<(usize, Option<Vec<f64>>)> {
+    let file = open_live(filename)?;
+    let group = match file.group("biological_model") {
+        Ok(group) => group,
+        Err(_) => return Ok((0, None)),
+    };
+    let n_group = group.len() as usize;
+    let times = (0..n_group)
+        .map(|i| {
+            group
+                .group(&i.to_string())
+                .and_then(|g| g.attr("time"))
+                .and_then(|a| a.read_scalar::<f64>())
+                .ok()
+        })
+        .collect();
+    Ok((n_group, times))
+}
+
+/// Storage size in bytes of the biological datasets of each export of a partial file
+pub fn read_export_sizes(filename: &str) -> hdf5::Result<Vec<u64>> {
+    let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
+    let group = match file.group("biological_model") {
+        Ok(group) => group,
+        Err(_) => return Ok(vec![]),
+    };
+    (0..group.len() as usize)
+        .map(|i_export| {
+            let export = group.group(&i_export.to_string())?;
+            Ok(export
+                .datasets()?
+                .iter()
+                .map(|dataset| dataset.storage_size())
+                .sum())
+        })
+        .collect()
+}
+
+/// Checks that the last biological export of a partial file holds every property with the same
+/// number of particles.
+///
+/// # Returns
+/// * `Option<usize>` - Index of the last export if it is incomplete
+pub fn last_incomplete_bio_export(
+    filename: &str,
+    property_name: &[String],
+) -> hdf5::Result<Option<usize>> {
+    let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
+    let group = match file.group("biological_model") {
+        Ok(group) => group,
+        Err(_) => return Ok(None),
+    };
+    let n_group = group.len() as usize;
+    if n_group == 0 {
+        return Ok(None);
+    }
+    let last = n_group - 1;
+    let mut size = None;
+    for key in property_name {
+        match group.dataset(&format!("{}/{}", last, key)) {
+            Ok(dataset) => match size {
+                None => size = Some(dataset.size()),
+                Some(s) if s != dataset.size() => return Ok(Some(last)),
+                _ => {}
+            },
+            Err(_) => return Ok(Some(last)),
+        }
+    }
+    Ok(None)
+}
+
+/// Sums the spatial property of every rank, row `i` of `cx` receives export `exports.start + i`
 pub fn read_spatial_model_properties(
     key: &str,
     files: &[String],
+    map: &BioExportMap,
     cx: &mut Array2<f64>,
-    n_export: usize,
+    exports: Range<usize>,
 ) -> Result<(), ApiError> {
-    for filename in files.iter() {
+    for (i_file, filename) in files.iter().enumerate() {
         // Open the HDF5 file in read mode
         let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
 
         // Access the "biological_model" group
         let group = file.group("biological_model")?;
-        //TODO: use group.len() instead of n_export
-        for i_e in 0..n_export {
+        for (row, i_e) in exports.clone().enumerate() {
+            let Some(i_group) = map.group(i_file, i_e) else {
+                continue;
+            };
             // Read the data for the current export index
-            let tmp: Vec<f64> = match group.dataset(&format!("{}/spatial/{}", i_e, key)) {
+            let tmp: Vec<f64> = match group.dataset(&format!("{}/spatial/{}", i_group, key)) {
                 Ok(dataset) => dataset.read_raw::<f64>()?, // Read the data directly as Vec<f64>
                 Err(_) => continue,                        // Skip if the dataset doesn't exist
             };
@@ -86,9 +346,9 @@ pub fn read_spatial_model_properties(
                 hdf5::Error::Internal("Shape mismatch while creating ArrayView1".to_string())
             })?;
 
-            let slice_shape = cx.slice(s![i_e, ..]).len();
+            let slice_shape = cx.slice(s![row, ..]).len();
             if tmp_array.len() == slice_shape {
-                cx.slice_mut(s![i_e, ..])
+                cx.slice_mut(s![row, ..])
                     .zip_mut_with(&tmp_array, |a, b| *a += b);
             } else {
                 eprintln!(
@@ -120,106 +380,200 @@ pub fn get_probe_size(files: &[String])-> Result<usize,ApiError>
     Ok(probe_size)
 }
 
+/// Reads a property of the particles of one rank from its group `i_group`
+fn read_property(group: &Group, i_group: usize, property: &Property) -> hdf5::Result<Vec<f64>> {
+    match property {
+        Property::Raw(key) => Ok(read_vec!(group, &format!("{}/{}", i_group, key), f64)),
+        Property::Derived(expr) => {
+            let mut vars = HashMap::new();
+            let mut n_particle = 0;
+            for key in expr.variables() {
+                let values = read_vec!(group, &format!("{}/{}", i_group, key), f64);
+                n_particle = values.len();
+                vars.insert(key, Array1::from_vec(values));
+            }
+            expr.eval(&vars, Ix1(n_particle))
+                .map(|values| values.to_vec())
+                .map_err(|e| hdf5::Error::Internal(e.to_string()))
+        }
+    }
+}
+
 pub fn read_model_properties(
-    key: &str,
+    property: &Property,
     files: &[String],
+    map: &BioExportMap,
     i_export: usize,
 ) -> hdf5::Result<Array1<f64>> {
-    let mut total_size = 0;
-    for filename in files.iter() {
+    let mut result = vec![];
+    for (i_file, filename) in files.iter().enumerate() {
+        // Ranks without particles at this export have not written it
+        let Some(i_group) = map.group(i_file, i_export) else {
+            continue;
+        };
         // Open the HDF5 file in read mode
         let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
 
         // Access the "biological_model" group
         let group = file.group("biological_model")?;
-        // Not all nodes have th=e same number of export so it happens that i_export is >
-        // number_export for the current file
-        if (group.len() as usize) >= i_export {
-            let dataset = group.dataset(&format!("{}/{}", i_export, key))?;
-            total_size += dataset.size();
-        }
+        result.extend(read_property(&group, i_group, property)?);
     }
 
-    let mut result = Array1::zeros(total_size);
-    let mut offset = 0;
-    for filename in files.iter() {
-        // Open the HDF5 file in read mode
-        let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
+    Ok(Array1::from_vec(result))
+}
 
-        // Access the "biological_model" group
+/// Reads the properties of `keys` at the given export, opening each partial file once.
+pub fn read_particle_table(
+    keys: &[&str],
+    properties: &[Property],
+    files: &[String],
+    map: &BioExportMap,
+    i_export: usize,
+) -> hdf5::Result<ParticleTable> {
+    let mut columns = vec![vec![]; properties.len()];
+    let mut rank = vec![];
+    let mut index = vec![];
+    for (i_file, filename) in files.iter().enumerate() {
+        let Some(i_group) = map.group(i_file, i_export) else {
+            continue;
+        };
+        let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
         let group = file.group("biological_model")?;
-        if (group.len() as usize) >= i_export {
-            let dataset = group.dataset(&format!("{}/{}", i_export, key))?;
-
-            // Read the dataset into a temporary array
-            let temp_array: Vec<f64> = dataset.read_raw::<f64>()?;
-            let tmp_array =
-                ArrayView1::from_shape(temp_array.len(), &temp_array).map_err(|_| {
-                    hdf5::Error::Internal("Shape mismatch while creating ArrayView1".to_string())
-                })?;
-
-            // Copy the data into the result array
-            result
-                .slice_mut(s![offset..offset + temp_array.len()])
-                .assign(&tmp_array);
-
-            offset += temp_array.len();
+        let mut n_particle = None;
+        for (column, property) in columns.iter_mut().zip(properties) {
+            let values = read_property(&group, i_group, property)?;
+            if *n_particle.get_or_insert(values.len()) != values.len() {
+                return Err(hdf5::Error::Internal(format!(
+                    "{}: properties have different lengths",
+                    filename
+                )));
+            }
+            column.extend(values);
         }
+        let n_particle = n_particle.unwrap_or(0);
+        rank.extend(std::iter::repeat_n(i_file, n_particle));
+        index.extend(0..n_particle);
     }
+    Ok(ParticleTable {
+        keys: keys.iter().map(|k| k.to_string()).collect(),
+        columns: columns.into_iter().map(Array1::from_vec).collect(),
+        rank: Array1::from_vec(rank),
+        index: Array1::from_vec(index),
+    })
+}
 
-    Ok(result)
+/// Evaluates a filter on the particles of one rank in its group `i_group`
+fn read_selection_mask(
+    group: &Group,
+    i_group: usize,
+    selection: &Selection,
+) -> hdf5::Result<Vec<bool>> {
+    let mut values = HashMap::new();
+    for (key, property) in &selection.properties {
+        let v = read_property(group, i_group, property)?;
+        values.insert(key.to_string(), Array1::from_vec(v));
+    }
+    selection
+        .filter
+        .mask(&values)
+        .map_err(|e| hdf5::Error::Internal(e.to_string()))
 }
 
-pub fn get_n_export_real(files: &[String]) -> hdf5::Result<usize> {
-    if files.is_empty() {
-        panic!("FIXME: not enough files")
+/// Reads a property of the particles selected by a filter.
+///
+/// # Returns
+/// * `(Array1<f64>, Vec<bool>)` - Values of the selected particles and the selection mask of
+///   every particle, ordered as in [`read_model_properties`]
+pub fn read_model_properties_filtered(
+    property: &Property,
+    selection: &Selection,
+    files: &[String],
+    map: &BioExportMap,
+    i_export: usize,
+) -> hdf5::Result<(Array1<f64>, Vec<bool>)> {
+    let mut result = vec![];
+    let mut mask = vec![];
+    for (i_file, filename) in files.iter().enumerate() {
+        let Some(i_group) = map.group(i_file, i_export) else {
+            continue;
+        };
+        let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
+        let group = file.group("biological_model")?;
+        let values = read_property(&group, i_group, property)?;
+        let rank_mask = read_selection_mask(&group, i_group, selection)?;
+        if rank_mask.len() != values.len() {
+            return Err(hdf5::Error::Internal(format!(
+                "{}: filter and property have different lengths",
+                filename
+            )));
+        }
+        result.extend(select(&values, &rank_mask));
+        mask.extend(rank_mask);
     }
-    let file = hdf5::File::open_as(&files[0], hdf5::file::OpenMode::Read)?;
-    let group = file.group("biological_model")?;
-    let group_size = group.len() as usize; //We export n_export times properties but if there is no
-                                           //particle we do not export. group_size <= n_export and for
-    Ok(group_size)
+
+    Ok((Array1::from_vec(result), mask))
 }
 
 pub fn make_histogram(
     files: &[String],
+    map: &BioExportMap,
     i_export: usize,
-    key: &str,
+    property: &Property,
     hist: &mut Histogram,
 ) -> hdf5::Result<()> {
-    for filename in files.iter() {
+    for (i_file, filename) in files.iter().enumerate() {
+        let Some(i_group) = map.group(i_file, i_export) else {
+            continue;
+        };
         // Open the HDF5 file in read mode
         let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
         let group = file.group("biological_model")?;
-        if (group.len() as usize) >= i_export {
-            let dataset = group.dataset(&format!("{}/{}", i_export, key))?;
-            let temp_array: Vec<f64> = dataset.read_raw::<f64>()?;
-            hist.add(temp_array);
-        }
+        let temp_array = read_property(&group, i_group, property)?;
+        hist.add(temp_array);
     }
 
     Ok(())
 }
 
+/// Same as [`make_histogram`] with only the particles selected by a filter.
+///
+/// # Returns
+/// * `(usize, usize)` - Number of selected particles and total number of particles
+pub fn make_histogram_filtered(
+    files: &[String],
+    map: &BioExportMap,
+    i_export: usize,
+    property: &Property,
+    selection: &Selection,
+    hist: &mut Histogram,
+) -> hdf5::Result<(usize, usize)> {
+    let (values, mask) =
+        read_model_properties_filtered(property, selection, files, map, i_export)?;
+    hist.add(values.to_vec());
+    Ok((values.len(), mask.len()))
+}
+
+/// Population mean of a property, element `i` of the result is export `exports.start + i`
 pub fn read_avg_model_properties(
-    key: &str,
+    property: &Property,
     files: &[String],
-    n_export: usize,
+    map: &BioExportMap,
+    exports: Range<usize>,
 ) -> hdf5::Result<Array1<f64>> {
-    let mut result = Array1::zeros(n_export);
-    let mut tot_particle: Array1<f64> = Array1::zeros(n_export);
+    let mut result = Array1::zeros(exports.len());
+    let mut tot_particle: Array1<f64> = Array1::zeros(exports.len());
 
-    for filename in files {
+    for (i_file, filename) in files.iter().enumerate() {
         let file = hdf5::File::open_as(filename, hdf5::file::OpenMode::Read)?;
         let group = file.group("biological_model")?;
-        let group_size = group.len() as usize; //We export n_export times properties but if there is no
-                                               //particle we do not export. group_size <= n_export and for
-                                               //all i > group_size , value is set to 0
-        for i_e in 0..group_size {
-            let dataset = group.dataset(&format!("{}/{}", i_e, key))?;
-            let temp_array: Vec<f64> = dataset.read_raw::<f64>()?;
-            result[i_e] += temp_array.iter().sum::<f64>();
-            tot_particle[i_e] += temp_array.len() as f64;
+        // Exports without particles on this rank have no group and do not contribute
+        for (row, i_e) in exports.clone().enumerate() {
+            let Some(i_group) = map.group(i_file, i_e) else {
+                continue;
+            };
+            let temp_array = read_property(&group, i_group, property)?;
+            result[row] += temp_array.iter().sum::<f64>();
+            tot_particle[row] += temp_array.len() as f64;
         }
     }
 
@@ -228,10 +582,11 @@ pub fn read_avg_model_properties(
 
 pub fn read_model_mass(
     files: &[String],
+    map: &BioExportMap,
     cx: &mut Array2<f64>,
-    n_export: usize,
+    exports: Range<usize>,
 ) -> Result<(), ApiError> {
-    read_spatial_model_properties("mass", files, cx, n_export)
+    read_spatial_model_properties("mass", files, map, cx, exports)
 }
 
 impl ResultGroup<MainInitial> for Group {
@@ -260,6 +615,46 @@ impl ResultGroup<MainInitial> for Group {
     }
 }
 
+/// Reads a list of names stored as a string attribute, either as an array of strings or as a
+/// single comma separated string.
+fn read_names_attr(location: &hdf5::Location, name: &str) -> Option<Vec<String>> {
+    let attr = location.attr(name).ok()?;
+    let raw: Vec<String> = if let Ok(v) = attr.read_raw::<VarLenUnicode>() {
+        v.iter().map(|s| s.as_str().to_string()).collect()
+    } else if let Ok(v) = attr.read_raw::<VarLenAscii>() {
+        v.iter().map(|s| s.as_str().to_string()).collect()
+    } else {
+        return None;
+    };
+
+    Some(
+        raw.iter()
+            .flat_map(|s| s.split(','))
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
+}
+
+/// Species names are looked for in the attributes of the concentration dataset, then of the
+/// records group. Names are ignored if their number does not match the number of species.
+fn read_species_names(records: &Group, n_species: usize) -> Vec<String> {
+    const ATTR_NAMES: [&str; 3] = ["species_names", "species", "names"];
+    let dataset = records.dataset("concentration_liquid").ok();
+    for attr_name in ATTR_NAMES {
+        let names = dataset
+            .as_ref()
+            .and_then(|d| read_names_attr(d, attr_name))
+            .or_else(|| read_names_attr(records, attr_name));
+        if let Some(names) = names {
+            if names.len() == n_species {
+                return names;
+            }
+        }
+    }
+    vec![]
+}
+
 impl ResultGroup<MainRecords> for Group {
     fn read_g(&self) -> hdf5::Result<MainRecords> {
         let concentration_liquid = read_vec!(self, "concentration_liquid", f64);
@@ -277,14 +672,22 @@ impl ResultGroup<MainRecords> for Group {
             _ => None,
         };
 
+        let time = read_vec!(self, "time", f64);
+
         let tallies = match self.dataset("tallies") {
-            Ok(_t) => Some(Tallies(_t.read_raw::<f64>()?)),
+            Ok(_t) => {
+                let n_column = Tallies::detect_n_column(&_t.shape(), time.len());
+                Some(
+                    Tallies::new(_t.read_raw::<f64>()?, n_column)
+                        .with_cumulative(tallies_are_cumulative(&_t)),
+                )
+            }
             _ => None,
         };
 
         let shape = self.dataset("concentration_liquid")?.shape();
         let dim = Dim(shape[1], shape[2]);
-        let time = read_vec!(self, "time", f64);
+        let species_names = read_species_names(self, dim.1);
         Ok(MainRecords {
             concentration_liquid,
             volume_liquid,
@@ -294,6 +697,7 @@ impl ResultGroup<MainRecords> for Group {
             tallies,
             dim,
             time,
+            species_names,
         })
     }
 }
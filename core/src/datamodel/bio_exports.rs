@@ -0,0 +1,187 @@
+//! Mapping between biological exports and the exports of the records.
+//!
+//! Each rank writes a group `biological_model/<k>` only for the exports at which it holds
+//! particles, so group `k` is not necessarily export `k` of `records/time`. The export of each
+//! group is read from its `time` attribute when BioMC writes one, otherwise it is reconstructed
+//! from the particle counts of the rank: the groups are the exports at which the rank is not
+//! empty, in order.
+//!
+//! Every export index of the API is an index of `records/time`; [`BioExportMap`] gives the group
+//! of each rank at that export, if any.
+
+/// Group of each rank at each export of the records
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BioExportMap {
+    /// Export of each group, per partial file
+    exports: Vec<Vec<usize>>,
+}
+
+impl BioExportMap {
+    pub fn new(exports: Vec<Vec<usize>>) -> Self {
+        Self { exports }
+    }
+
+    /// Group of the partial file `i_file` written at export `i_export`, `None` if the rank had no
+    /// particle at that export.
+    pub fn group(&self, i_file: usize, i_export: usize) -> Option<usize> {
+        self.exports
+            .get(i_file)
+            .and_then(|e| e.binary_search(&i_export).ok())
+    }
+
+    /// Export of each group of the partial file `i_file`
+    pub fn exports_of(&self, i_file: usize) -> &[usize] {
+        self.exports.get(i_file).map_or(&[], Vec::as_slice)
+    }
+
+    /// Exports at which at least one rank has written particles, in increasing order
+    pub fn exports(&self) -> Vec<usize> {
+        let mut exports: Vec<usize> = self.exports.iter().flatten().copied().collect();
+        exports.sort_unstable();
+        exports.dedup();
+        exports
+    }
+
+    /// One past the last export with particles
+    pub fn n_export(&self) -> usize {
+        self.exports
+            .iter()
+            .filter_map(|e| e.last())
+            .max()
+            .map_or(0, |last| last + 1)
+    }
+
+    /// Drops the groups written after the first `nt` exports
+    pub fn truncate(&mut self, nt: usize) {
+        for exports in &mut self.exports {
+            exports.retain(|i| *i < nt);
+        }
+    }
+}
+
+/// Exports at which a rank holds particles.
+///
+/// # Arguments
+/// * `number_particle` - Particle counts of the rank, shape (nt, n_compartment) flattened.
+/// * `offset` - Export of the first row of `number_particle`.
+pub fn non_empty_exports(
+    number_particle: &[f64],
+    n_compartment: usize,
+    offset: usize,
+) -> Vec<usize> {
+    number_particle
+        .chunks(n_compartment.max(1))
+        .enumerate()
+        .filter(|(_, row)| row.iter().sum::<f64>() > 0.)
+        .map(|(i, _)| offset + i)
+        .collect()
+}
+
+/// Export of each group of a rank.
+///
+/// # Arguments
+/// * `n_group` - Number of groups in `biological_model`.
+/// * `group_time` - `time` attribute of every group, if written by BioMC.
+/// * `time` - Export times of the records.
+/// * `non_empty` - Exports at which the rank holds particles, see [`non_empty_exports`].
+pub fn reconstruct(
+    n_group: usize,
+    group_time: Option<&[f64]>,
+    time: &[f64],
+    non_empty: &[usize],
+) -> Vec<usize> {
+    if let Some(group_time) = group_time.filter(|t| t.len() == n_group) {
+        if let Some(exports) = group_time
+            .iter()
+            .map(|t| nearest(time, *t))
+            .collect::<Option<Vec<usize>>>()
+        {
+            return exports;
+        }
+    }
+
+    let non_empty: Vec<usize> = non_empty
+        .iter()
+        .copied()
+        .take_while(|i| *i < time.len())
+        .collect();
+    if non_empty.len() >= n_group {
+        // The rank may lag behind the records of a running simulation
+        non_empty[..n_group].to_vec()
+    } else {
+        // Groups written for empty exports too
+        (0..n_group).collect()
+    }
+}
+
+/// Index of the value of `time` (increasing) nearest to `t`, the earlier one on ties
+pub fn nearest(time: &[f64], t: f64) -> Option<usize> {
+    if time.is_empty() || t.is_nan() {
+        return None;
+    }
+    let i = time.partition_point(|x| *x < t);
+    if i == 0 {
+        return Some(0);
+    }
+    if i == time.len() {
+        return Some(time.len() - 1);
+    }
+    if t - time[i - 1] <= time[i] - t {
+        Some(i - 1)
+    } else {
+        Some(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest() {
+        let time = [0., 1., 2., 4.];
+        assert_eq!(nearest(&time, -1.), Some(0));
+        assert_eq!(nearest(&time, 1.4), Some(1));
+        assert_eq!(nearest(&time, 1.5), Some(1));
+        assert_eq!(nearest(&time, 3.1), Some(3));
+        assert_eq!(nearest(&time, 10.), Some(3));
+        assert_eq!(nearest(&[], 1.), None);
+    }
+
+    #[test]
+    fn test_reconstruct_from_counts() {
+        let time = [0., 1., 2., 3.];
+        // One compartment, empty at export 1
+        let number = [5., 0., 3., 2.];
+        let non_empty = non_empty_exports(&number, 1, 0);
+        assert_eq!(non_empty, vec![0, 2, 3]);
+        assert_eq!(reconstruct(3, None, &time, &non_empty), vec![0, 2, 3]);
+        // Rank lagging behind the records
+        assert_eq!(reconstruct(2, None, &time, &non_empty), vec![0, 2]);
+        // Groups for every export
+        assert_eq!(reconstruct(4, None, &time, &non_empty), vec![0, 1, 2, 3]);
+        // Counts read after a refresh
+        assert_eq!(non_empty_exports(&[0., 1., 1., 0.], 2, 3), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_reconstruct_from_attributes() {
+        let time = [0., 1., 2., 3.];
+        let exports = reconstruct(2, Some(&[1.02, 2.97]), &time, &[0, 1, 2, 3]);
+        assert_eq!(exports, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map = BioExportMap::new(vec![vec![0, 2, 3], vec![0, 1]]);
+        assert_eq!(map.group(0, 2), Some(1));
+        assert_eq!(map.group(0, 1), None);
+        assert_eq!(map.group(1, 1), Some(1));
+        assert_eq!(map.exports(), vec![0, 1, 2, 3]);
+        assert_eq!(map.n_export(), 4);
+        assert_eq!(map.exports_of(1), &[0, 1]);
+        assert!(map.exports_of(2).is_empty());
+        map.truncate(2);
+        assert_eq!(map.n_export(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+//! Streaming of particle data by chunks.
+//!
+//! [`ParticleChunks`] reads the particles of one export rank after rank, at most `chunk_size`
+//! particles at a time, so that exports larger than the memory can be reduced with the fold-style
+//! reducers implementing [`crate::Reducer`].
+//!
+//! # Example
+//! ```ignore
+//! let chunks = pp.particle_chunks(i_export, &["mass", "age"], 1_000_000)?;
+//! let mass = chunks.reduce(0, Welford::default())?;
+//! println!("{} ± {}", mass.mean(), mass.std());
+//! ```
+use crate::error::ApiError;
+use crate::process::reduce::Reducer;
+use ndarray::{Array2, ArrayView1, Axis};
+
+/// Aligned values of several keys for consecutive particles of one rank
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleChunk {
+    /// Index of the partial file the particles come from
+    pub rank: usize,
+    /// Index of the first particle of the chunk within its rank
+    pub offset: usize,
+    /// Values of each key, shape (n_particle, n_key)
+    pub values: Array2<f64>,
+}
+
+impl ParticleChunk {
+    pub fn len(&self) -> usize {
+        self.values.nrows()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.nrows() == 0
+    }
+}
+
+/// Iterator over the particles of one export, see the [module documentation](self)
+pub struct ParticleChunks {
+    files: Vec<String>,
+    /// Biological group of each rank at the export, `None` if the rank has not written it
+    groups: Vec<Option<usize>>,
+    keys: Vec<String>,
+    chunk_size: usize,
+    rank: usize,
+    /// Datasets of the current rank, next particle to read and number of particles
+    current: Option<(Vec<hdf5::Dataset>, usize, usize)>,
+}
+
+impl ParticleChunks {
+    pub(crate) fn new(
+        files: &[String],
+        groups: Vec<Option<usize>>,
+        keys: &[&str],
+        chunk_size: usize,
+    ) -> Result<Self, ApiError> {
+        if chunk_size == 0 || keys.is_empty() {
+            return Err(ApiError::Default(
+                "Chunks need at least one key and one particle".to_string(),
+            ));
+        }
+        Ok(Self {
+            files: files.to_vec(),
+            groups,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            chunk_size,
+            rank: 0,
+            current: None,
+        })
+    }
+
+    /// Keys of the columns of each chunk
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Opens the datasets of the current rank, `None` if the rank has not written the export
+    fn open_rank(&self) -> Result<Option<(Vec<hdf5::Dataset>, usize, usize)>, ApiError> {
+        let Some(i_group) = self.groups.get(self.rank).copied().flatten() else {
+            return Ok(None);
+        };
+        let file = hdf5::File::open_as(&self.files[self.rank], hdf5::file::OpenMode::Read)?;
+        let group = file.group("biological_model")?;
+        let export = group.group(&i_group.to_string())?;
+        let datasets = self
+            .keys
+            .iter()
+            .map(|key| {
+                export
+                    .dataset(key)
+                    .map_err(|_| ApiError::KeyError(key.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let n_particle = datasets[0].size();
+        if datasets.iter().any(|d| d.size() != n_particle) {
+            return Err(ApiError::ShapeError);
+        }
+        Ok(Some((datasets, 0, n_particle)))
+    }
+
+    fn next_chunk(&mut self) -> Result<Option<ParticleChunk>, ApiError> {
+        loop {
+            if let Some((datasets, offset, n_particle)) = &mut self.current {
+                if *offset < *n_particle {
+                    let start = *offset;
+                    let end = (start + self.chunk_size).min(*n_particle);
+                    let mut values = Array2::zeros((end - start, datasets.len()));
+                    for (mut column, dataset) in values.axis_iter_mut(Axis(1)).zip(datasets.iter())
+                    {
+                        let data = dataset.read_slice_1d::<f64, _>(start..end)?.to_vec();
+                        column.assign(&ArrayView1::from(&data));
+                    }
+                    *offset = end;
+                    return Ok(Some(ParticleChunk {
+                        rank: self.rank,
+                        offset: start,
+                        values,
+                    }));
+                }
+                self.current = None;
+                self.rank += 1;
+            }
+            if self.rank >= self.files.len() {
+                return Ok(None);
+            }
+            match self.open_rank()? {
+                Some(current) => self.current = Some(current),
+                None => self.rank += 1,
+            }
+        }
+    }
+
+    /// Feeds the column `column` of every chunk to a reducer.
+    pub fn reduce<R: Reducer>(self, column: usize, mut reducer: R) -> Result<R, ApiError> {
+        if column >= self.keys.len() {
+            return Err(ApiError::OutOfRange(column, self.keys.len()));
+        }
+        for chunk in self {
+            reducer.update(chunk?.values.column(column));
+        }
+        Ok(reducer)
+    }
+}
+
+impl Iterator for ParticleChunks {
+    type Item = Result<ParticleChunk, ApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_chunk() {
+            Ok(chunk) => chunk.map(Ok),
+            Err(e) => {
+                // Stop after the first error
+                self.current = None;
+                self.rank = self.files.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -25,6 +25,99 @@ pub struct MainRecords {
     pub tallies: Option<Tallies>,
     pub dim: Dim,
     pub time: Vec<f64>,
+    pub species_names: Vec<String>, //Empty if names are not stored in the file
+}
+
+impl MainRecords {
+    /// Number of exports for which every record dataset holds a complete row
+    pub fn n_export_consistent(&self) -> usize {
+        let n_c = self.dim.0;
+        let n_cs = self.dim.0 * self.dim.1;
+        let mut rows = vec![
+            self.time.len(),
+            self.concentration_liquid.len() / n_cs,
+            self.volume_liquid.len() / n_c,
+        ];
+        if let Some(c) = &self.concentration_gas {
+            rows.push(c.len() / n_cs);
+        }
+        if let Some(v) = &self.volume_gas {
+            rows.push(v.len() / n_c);
+        }
+        if let Some(mtr) = &self.mtr {
+            rows.push(mtr.len() / n_cs);
+        }
+        rows.into_iter().min().unwrap_or(0)
+    }
+
+    /// Truncates every record dataset to `nt` exports.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - Names of the datasets that were actually truncated
+    pub fn truncate(&mut self, nt: usize) -> Vec<String> {
+        fn cut(name: &str, v: &mut Vec<f64>, len: usize, truncated: &mut Vec<String>) {
+            if v.len() > len {
+                v.truncate(len);
+                truncated.push(name.to_string());
+            }
+        }
+        let n_c = self.dim.0;
+        let n_cs = self.dim.0 * self.dim.1;
+        let mut truncated = vec![];
+        cut("time", &mut self.time, nt, &mut truncated);
+        cut(
+            "concentration_liquid",
+            &mut self.concentration_liquid,
+            nt * n_cs,
+            &mut truncated,
+        );
+        cut("volume_liquid", &mut self.volume_liquid, nt * n_c, &mut truncated);
+        if let Some(c) = &mut self.concentration_gas {
+            cut("concentration_gas", c, nt * n_cs, &mut truncated);
+        }
+        if let Some(v) = &mut self.volume_gas {
+            cut("volume_gas", v, nt * n_c, &mut truncated);
+        }
+        if let Some(mtr) = &mut self.mtr {
+            cut("mtr", mtr, nt * n_cs, &mut truncated);
+        }
+        if let Some(t) = &mut self.tallies {
+            cut("tallies", &mut t.data, nt * t.n_column, &mut truncated);
+        }
+        truncated
+    }
+}
+
+impl MainRecords {
+    /// Appends the exports of `other`, a read of the exports that follow the ones of `self`.
+    ///
+    /// Tallies are appended only if their number of columns matches.
+    pub fn append(&mut self, other: &MainRecords) {
+        self.time.extend_from_slice(&other.time);
+        self.concentration_liquid
+            .extend_from_slice(&other.concentration_liquid);
+        self.volume_liquid.extend_from_slice(&other.volume_liquid);
+        if let (Some(c), Some(oc)) = (&mut self.concentration_gas, &other.concentration_gas) {
+            c.extend_from_slice(oc);
+        }
+        if let (Some(v), Some(ov)) = (&mut self.volume_gas, &other.volume_gas) {
+            v.extend_from_slice(ov);
+        }
+        if let (Some(mtr), Some(omtr)) = (&mut self.mtr, &other.mtr) {
+            mtr.extend_from_slice(omtr);
+        }
+        match (&mut self.tallies, &other.tallies) {
+            (Some(t), Some(ot)) if t.n_column == ot.n_column && t.cumulative == ot.cumulative => {
+                t.data.extend_from_slice(&ot.data)
+            }
+            (None, Some(ot)) => {
+                self.tallies = Some(
+                    Tallies::new(ot.data.clone(), ot.n_column).with_cumulative(ot.cumulative),
+                )
+            }
+            _ => {}
+        }
+    }
 }
 
 ///Initial information
@@ -91,3 +184,52 @@ impl MainResult {
         &self.records.time
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(nt_time: usize, nt_c: usize) -> MainRecords {
+        let dim = Dim(2, 3);
+        MainRecords {
+            concentration_liquid: vec![1.; nt_c * 6],
+            volume_liquid: vec![1.; nt_c * 2],
+            concentration_gas: None,
+            volume_gas: None,
+            mtr: None,
+            tallies: Some(Tallies::new(vec![0.; nt_time * 6], 6)),
+            dim,
+            time: (0..nt_time).map(|i| i as f64).collect(),
+            species_names: vec![],
+        }
+    }
+
+    #[test]
+    fn test_consistent_export_truncated_time() {
+        let r = records(3, 5);
+        assert_eq!(r.n_export_consistent(), 3);
+    }
+
+    #[test]
+    fn test_truncate_records() {
+        let mut r = records(5, 4);
+        let nt = r.n_export_consistent();
+        assert_eq!(nt, 4);
+        let truncated = r.truncate(nt);
+        assert_eq!(truncated, vec!["time".to_string(), "tallies".to_string()]);
+        assert_eq!(r.time.len(), 4);
+        assert_eq!(r.concentration_liquid.len(), 4 * 6);
+        assert_eq!(r.tallies.unwrap().data.len(), 4 * 6);
+    }
+
+    #[test]
+    fn test_append_records() {
+        let mut r = records(2, 2);
+        let mut newer = records(3, 3);
+        newer.time = vec![2., 3., 4.];
+        r.append(&newer);
+        assert_eq!(r.time, vec![0., 1., 2., 3., 4.]);
+        assert_eq!(r.n_export_consistent(), 5);
+        assert_eq!(r.tallies.unwrap().data.len(), 5 * 6);
+    }
+}
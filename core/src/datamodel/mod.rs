@@ -1,14 +1,25 @@
 mod _impl;
-mod main_file;
+pub mod bio_exports;
+pub mod chunks;
+pub(crate) mod main_file;
+pub mod ranks;
+pub mod recovery;
 pub mod tallies;
+pub mod validate;
 use crate::error::ApiError;
+use crate::process::expr::Expr;
+use bio_exports::BioExportMap;
+use crate::process::filter::ParticleFilter;
 use _impl::get_probe_size;
 pub use _impl::{
-    get_n_export_real, make_histogram, read_avg_model_properties, read_model_mass,
-    read_model_properties, read_spatial_model_properties,
+    make_histogram, make_histogram_filtered, read_avg_model_properties,
+    read_model_mass, read_model_properties, read_model_properties_filtered, read_particle_table,
+    read_spatial_model_properties,
 };
 pub use main_file::MainResult;
-use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, ArrayView3};
+use recovery::{OpenMode, RecoveryReport};
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, ArrayView3, Axis};
+use std::ops::Range;
 use std::path::PathBuf;
 
 trait ResultGroup<T> {
@@ -24,40 +35,262 @@ pub enum Weight {
     Multiple(Vec<f64>), // Represents a vector of f64 values
 }
 
+/// A particle property read from the biological exports
+#[derive(Debug, Clone, Copy)]
+pub enum Property<'a> {
+    /// Dataset stored in the partial files
+    Raw(&'a str),
+    /// Expression over stored datasets, evaluated on each rank while reading
+    Derived(&'a Expr),
+}
+
+/// A particle filter whose keys have been resolved to properties
+#[derive(Debug)]
+pub struct Selection<'a> {
+    pub filter: &'a ParticleFilter,
+    pub properties: Vec<(&'a str, Property<'a>)>,
+}
+
 #[derive(Debug)]
 pub struct Results {
+    pub path: String,
     pub main: MainResult,
     pub files: Vec<String>,
     pub total_particle_repetition: Array2<f64>,
     pub property_name: Vec<String>,
+    pub recovery: Option<RecoveryReport>,
+    /// Export of the records written by each biological group of each partial file
+    pub bio_exports: BioExportMap,
+    /// Exports at which each partial file holds particles, kept to map the groups of new exports
+    non_empty_exports: Vec<Vec<usize>>,
 }
 
 impl Results {
-    pub fn new(fp: &str, root: &str, folder: &str) -> Result<Self, ApiError> {
+    pub fn open(fp: &str, root: &str, folder: &str, mode: OpenMode) -> Result<Self, ApiError> {
         match MainResult::read(fp) {
-            Ok(main) => {
-                let files: Vec<String> = (0..main.misc.n_rank)
-                    .map(|i| format!("{}/{}/{}_partial_{}.h5", root, folder, folder, i))
-                    .collect();
+            Ok(main) => match mode {
+                OpenMode::Strict => Self::open_strict(fp, main, root, folder),
+                OpenMode::Lenient => Self::open_lenient(fp, main, root, folder),
+            },
+            Err(hdf5_error) => Err(ApiError::Io(hdf5_error)),
+        }
+    }
+
+    fn open_strict(fp: &str, main: MainResult, root: &str, folder: &str) -> Result<Self, ApiError> {
+        let files: Vec<String> = (0..main.misc.n_rank)
+            .map(|i| partial_file_name(root, folder, i as usize))
+            .collect();
+
+        let nt = main.records.time.len();
+        let shape = (nt, main.records.dim.0);
+        let mut total_particle_repetition: Array2<f64> = Array2::zeros(shape);
+        let mut bio_exports = Vec::with_capacity(files.len());
+        let mut non_empty_exports = Vec::with_capacity(files.len());
+        for i_f in &files {
+            let n_p = _impl::read_number_particle(i_f)?;
+            let non_empty = bio_exports::non_empty_exports(&n_p, shape.1, 0);
+            bio_exports.push(Self::read_bio_exports(i_f, &main.records.time, &non_empty)?);
+            non_empty_exports.push(non_empty);
+            total_particle_repetition = total_particle_repetition
+                + Array2::from_shape_vec(shape, n_p).map_err(|_| ApiError::ShapeError)?;
+        }
+        let property_name = Self::get_property_name(&files);
+        Ok(Results {
+            path: fp.to_string(),
+            main,
+            files,
+            total_particle_repetition,
+            property_name,
+            recovery: None,
+            bio_exports: BioExportMap::new(bio_exports),
+            non_empty_exports,
+        })
+    }
 
-                let nt = main.records.time.len();
-                let shape = (nt, main.records.dim.0);
-                let mut total_particle_repetition: Array2<f64> = Array2::zeros(shape);
-                for i_f in &files {
-                    let n_p = _impl::read_number_particle(i_f)?;
-                    total_particle_repetition =
-                        total_particle_repetition + Array2::from_shape_vec(shape, n_p).unwrap();
+    fn open_lenient(
+        fp: &str,
+        mut main: MainResult,
+        root: &str,
+        folder: &str,
+    ) -> Result<Self, ApiError> {
+        let mut report = RecoveryReport {
+            n_export_found: main.records.time.len(),
+            missing_final: main.cfinal.is_none(),
+            ..Default::default()
+        };
+
+        let n_compartment = main.records.dim.0;
+        let mut ranks = vec![];
+        let mut files = vec![];
+        let mut number_particles = vec![];
+        for i in 0..main.misc.n_rank as usize {
+            let filename = partial_file_name(root, folder, i);
+            match _impl::read_number_particle(&filename) {
+                Ok(n_p) => {
+                    ranks.push(i);
+                    files.push(filename);
+                    number_particles.push(n_p);
                 }
-                let property_name = Self::get_property_name(&files);
-                Ok(Results {
-                    main,
-                    files,
-                    total_particle_repetition,
-                    property_name,
-                })
+                Err(_) => report.missing_ranks.push(i),
             }
-            Err(hdf5_error) => Err(ApiError::Io(hdf5_error)),
         }
+
+        if files.is_empty() {
+            return Err(ApiError::Default("No readable partial file".to_string()));
+        }
+
+        let property_name = Self::get_property_name(&files);
+
+        // The last biological export of a crashed rank may have been only partially written
+        let mut nt = main.records.n_export_consistent();
+        let mut bio_exports = Vec::with_capacity(files.len());
+        let mut non_empty_exports = Vec::with_capacity(files.len());
+        for (i_f, filename) in files.iter().enumerate() {
+            nt = nt.min(number_particles[i_f].len() / n_compartment);
+            let non_empty =
+                bio_exports::non_empty_exports(&number_particles[i_f], n_compartment, 0);
+            let mut exports = Self::read_bio_exports(filename, &main.records.time, &non_empty)?;
+            if let Some(last) = _impl::last_incomplete_bio_export(filename, &property_name)? {
+                // `last` is a group of the partial file, the records are cut at its export
+                report.incomplete_ranks.push(ranks[i_f]);
+                if let Some(export) = exports.get(last) {
+                    nt = nt.min(*export);
+                }
+                exports.truncate(last);
+            }
+            bio_exports.push(exports);
+            non_empty_exports.push(non_empty);
+        }
+        let mut bio_exports = BioExportMap::new(bio_exports);
+        bio_exports.truncate(nt);
+        for non_empty in &mut non_empty_exports {
+            non_empty.retain(|i| *i < nt);
+        }
+
+        report.truncated_datasets = main.records.truncate(nt);
+        report.n_export_kept = nt;
+
+        let shape = (nt, n_compartment);
+        let mut total_particle_repetition: Array2<f64> = Array2::zeros(shape);
+        for mut n_p in number_particles {
+            n_p.truncate(nt * n_compartment);
+            total_particle_repetition = total_particle_repetition
+                + Array2::from_shape_vec(shape, n_p).map_err(|_| ApiError::ShapeError)?;
+        }
+
+        Ok(Results {
+            path: fp.to_string(),
+            main,
+            files,
+            total_particle_repetition,
+            property_name,
+            recovery: Some(report),
+            bio_exports,
+            non_empty_exports,
+        })
+    }
+
+    /// Reads the exports appended to the files since the last read.
+    ///
+    /// Only the new rows of the records of the main file and of the particle counts of the partial
+    /// files are read, biological datasets are left on disk. Files are opened in SWMR read mode
+    /// when available, see [`_impl::open_live`]. Exports that are not yet complete in every file
+    /// are ignored until the next refresh.
+    ///
+    /// # Returns
+    /// * `Option<Range<usize>>` - Indices of the new exports, `None` if nothing new has been written
+    pub fn refresh(&mut self) -> Result<Option<Range<usize>>, ApiError> {
+        let old = self.main.records.time.len();
+        let n_compartment = self.main.records.dim.0;
+        let (tallies_from, tallies_n_column) = match &self.main.records.tallies {
+            Some(t) => (t.n_row(), Some(t.n_column)),
+            None => (0, None),
+        };
+        let (mut newer, cfinal) = _impl::read_records_from(
+            &self.path,
+            old,
+            &self.main.records.dim,
+            tallies_from,
+            tallies_n_column,
+        )?;
+
+        let mut number_particles = Vec::with_capacity(self.files.len());
+        let mut n_new = newer.n_export_consistent();
+        for filename in &self.files {
+            let n_p = _impl::read_number_particle_from(filename, old, n_compartment)?;
+            n_new = n_new.min(n_p.len() / n_compartment);
+            number_particles.push(n_p);
+        }
+
+        let n_found = old + newer.time.len();
+        self.main.cfinal = cfinal;
+        if n_new == 0 {
+            return Ok(None);
+        }
+        let nt = old + n_new;
+
+        // Tallies start at their own row, they are cut at the same export as the other records
+        let tallies = newer.tallies.take().map(|mut t| {
+            t.data.truncate(nt.saturating_sub(tallies_from) * t.n_column);
+            t
+        });
+        newer.truncate(n_new);
+        newer.tallies = tallies;
+        self.main.records.append(&newer);
+
+        let mut new_rows: Array2<f64> = Array2::zeros((n_new, n_compartment));
+        for n_p in &number_particles {
+            let rows = ArrayView2::from_shape((n_new, n_compartment), &n_p[..n_new * n_compartment])
+                .map_err(|_| ApiError::ShapeError)?;
+            new_rows += &rows;
+        }
+        self.total_particle_repetition
+            .append(Axis(0), new_rows.view())
+            .map_err(|_| ApiError::ShapeError)?;
+
+        let time = &self.main.records.time;
+        let mut bio_exports = Vec::with_capacity(self.files.len());
+        for ((filename, n_p), non_empty) in self
+            .files
+            .iter()
+            .zip(&number_particles)
+            .zip(&mut self.non_empty_exports)
+        {
+            non_empty.extend(bio_exports::non_empty_exports(
+                &n_p[..n_new * n_compartment],
+                n_compartment,
+                old,
+            ));
+            bio_exports.push(Self::read_bio_exports(filename, time, non_empty)?);
+        }
+        self.bio_exports = BioExportMap::new(bio_exports);
+        self.bio_exports.truncate(nt);
+
+        if self.property_name.is_empty() {
+            self.property_name = Self::get_property_name(&self.files);
+        }
+        if let Some(report) = &mut self.recovery {
+            report.n_export_found = n_found;
+            report.n_export_kept = nt;
+            report.missing_final = self.main.cfinal.is_none();
+        }
+
+        Ok(Some(old..nt))
+    }
+
+    /// Export of the records of each biological group of a partial file, see [`bio_exports`]
+    fn read_bio_exports(
+        filename: &str,
+        time: &[f64],
+        non_empty: &[usize],
+    ) -> Result<Vec<usize>, ApiError> {
+        let (n_group, group_time) = _impl::read_group_times(filename)?;
+        Ok(bio_exports::reconstruct(
+            n_group,
+            group_time.as_deref(),
+            time,
+            non_empty,
+        ))
     }
 
     fn get_property_name(files: &[String]) -> Vec<String> {
@@ -90,6 +323,16 @@ impl Results {
     }
 }
 
+/// Path of the main file of a run
+pub fn main_file_name(root: &str, folder: &str) -> String {
+    format!("{}/{}/{}.h5", root, folder, folder)
+}
+
+/// Path of the partial file written by `rank`
+pub fn partial_file_name(root: &str, folder: &str, rank: usize) -> String {
+    format!("{}/{}/{}_partial_{}.h5", root, folder, folder, rank)
+}
+
 pub fn f_get_probes(files: &[String]) -> Result<Array1<f64>, ApiError> {
     let total_size = get_probe_size(files)?;
     let mut probe = Array1::zeros(total_size);
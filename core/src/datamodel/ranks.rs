@@ -0,0 +1,173 @@
+//! Per-rank diagnostics of the MPI decomposition.
+//!
+//! Each rank of BioMC writes its own partial file, with its particle counts per compartment at
+//! every export and its biological datasets. The totals used by the rest of the API sum them over
+//! the ranks; a [`RankReport`] keeps the breakdown to assess the load balance.
+use super::{_impl, Results};
+use crate::error::ApiError;
+use ndarray::{s, Array1, Array2, Array3, ArrayView2, Axis};
+
+/// Particle counts and storage of every rank of a run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankReport {
+    /// Number of ranks of the simulation, see `misc/n_rank`
+    pub n_rank: u64,
+    /// Threads per node, see `misc/n_node_thread`
+    pub n_node_thread: u64,
+    /// Rank of each row, ranks without a readable partial file are left out
+    pub ranks: Vec<usize>,
+    /// Particles of each rank in each compartment, shape (n_rank, nt, n_compartment)
+    pub number_particle_compartment: Array3<f64>,
+    /// Storage size in bytes of the biological datasets of each rank and export of the records,
+    /// shape (n_rank, n_export_bio), NaN for exports that a rank has not written
+    pub export_size: Array2<f64>,
+}
+
+impl RankReport {
+    pub fn new(
+        n_rank: u64,
+        n_node_thread: u64,
+        ranks: Vec<usize>,
+        number_particle_compartment: Array3<f64>,
+        export_size: Array2<f64>,
+    ) -> Self {
+        Self {
+            n_rank,
+            n_node_thread,
+            ranks,
+            number_particle_compartment,
+            export_size,
+        }
+    }
+
+    /// Reads the partial files of an opened run.
+    pub(crate) fn read(results: &Results) -> Result<Self, ApiError> {
+        let misc = &results.main.misc;
+        let missing = results
+            .recovery
+            .as_ref()
+            .map(|r| r.missing_ranks.clone())
+            .unwrap_or_default();
+        let ranks: Vec<usize> = (0..misc.n_rank as usize)
+            .filter(|i| !missing.contains(i))
+            .collect();
+
+        let (nt, n_compartment) = results.total_particle_repetition.dim();
+        let mut number_particle = Array3::zeros((ranks.len(), nt, n_compartment));
+        let mut sizes = Vec::with_capacity(ranks.len());
+        // Partial files are listed in rank order, without the missing ranks
+        for (row, filename) in results.files.iter().enumerate() {
+            let n_p = _impl::read_number_particle(filename)?;
+            let n_p = n_p.get(..nt * n_compartment).ok_or(ApiError::ShapeError)?;
+            let n_p = ArrayView2::from_shape((nt, n_compartment), n_p)
+                .map_err(|_| ApiError::ShapeError)?;
+            number_particle.slice_mut(s![row, .., ..]).assign(&n_p);
+            sizes.push(_impl::read_export_sizes(filename)?);
+        }
+
+        let n_export_bio = results.bio_exports.n_export();
+        let mut export_size = Array2::from_elem((ranks.len(), n_export_bio), f64::NAN);
+        for (row, size) in sizes.iter().enumerate() {
+            // Groups are numbered per rank, the export of each one is given by the map
+            let exports = results.bio_exports.exports_of(row);
+            for (i_export, bytes) in exports.iter().zip(size) {
+                export_size[[row, *i_export]] = *bytes as f64;
+            }
+        }
+
+        Ok(Self::new(
+            misc.n_rank,
+            misc.n_node_thread,
+            ranks,
+            number_particle,
+            export_size,
+        ))
+    }
+
+    /// Particles of each rank over time, shape (n_rank, nt)
+    pub fn number_particle(&self) -> Array2<f64> {
+        self.number_particle_compartment.sum_axis(Axis(2))
+    }
+
+    /// Load imbalance at each export: the largest particle count of a rank divided by the mean
+    /// over the `n_rank` ranks of the simulation.
+    ///
+    /// 1 is a perfect balance, exports without particles are NaN.
+    pub fn imbalance(&self) -> Array1<f64> {
+        let number = self.number_particle();
+        let n_rank = (self.n_rank as f64).max(self.ranks.len() as f64);
+        number
+            .axis_iter(Axis(1))
+            .map(|counts| {
+                let max = counts.iter().cloned().fold(0., f64::max);
+                let mean = counts.sum() / n_rank;
+                if mean > 0. {
+                    max / mean
+                } else {
+                    f64::NAN
+                }
+            })
+            .collect()
+    }
+
+    /// Largest imbalance over the run
+    pub fn max_imbalance(&self) -> f64 {
+        self.imbalance()
+            .iter()
+            .cloned()
+            .filter(|x| !x.is_nan())
+            .fold(f64::NAN, f64::max)
+    }
+
+    /// Mean number of particles per thread at each export, over `n_rank * n_node_thread` threads
+    pub fn particles_per_thread(&self) -> Array1<f64> {
+        let n_thread = (self.n_rank * self.n_node_thread.max(1)) as f64;
+        self.number_particle().sum_axis(Axis(0)) / n_thread
+    }
+
+    /// Total storage size in bytes of each biological export over the ranks
+    pub fn total_export_size(&self) -> Array1<f64> {
+        self.export_size
+            .axis_iter(Axis(1))
+            .map(|sizes| sizes.iter().filter(|x| !x.is_nan()).sum())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn report() -> RankReport {
+        // 2 ranks, 2 exports, 2 compartments
+        let number = array![[[10., 10.], [20., 20.]], [[10., 10.], [5., 15.]]];
+        let sizes = array![[100., 200.], [100., f64::NAN]];
+        RankReport::new(2, 4, vec![0, 1], number, sizes)
+    }
+
+    #[test]
+    fn test_imbalance() {
+        let r = report();
+        assert_eq!(r.number_particle(), array![[20., 40.], [20., 20.]]);
+        let imbalance = r.imbalance();
+        assert_eq!(imbalance[0], 1.);
+        assert!((imbalance[1] - 40. / 30.).abs() < 1e-12);
+        assert!((r.max_imbalance() - 40. / 30.).abs() < 1e-12);
+        assert_eq!(r.particles_per_thread(), array![5., 7.5]);
+        assert_eq!(r.total_export_size(), array![200., 200.]);
+    }
+
+    #[test]
+    fn test_missing_rank() {
+        // A missing rank counts as an empty one in the mean
+        let r = RankReport::new(
+            3,
+            1,
+            vec![0, 2],
+            array![[[30.]], [[30.]]],
+            Array2::zeros((2, 0)),
+        );
+        assert_eq!(r.imbalance()[0], 1.5);
+    }
+}
@@ -0,0 +1,56 @@
+//! Lenient opening of incomplete runs.
+//!
+//! When a simulation crashes, the main file and the partial files are left in
+//! an intermediate state: `final_result` is missing, `records` datasets may not
+//! have the same number of exports and some ranks may not have written their
+//! partial file at all. In lenient mode, the run is truncated to the last export
+//! that is consistent across every file and what has been dropped is
+//! described in a [`RecoveryReport`].
+use serde::{Deserialize, Serialize};
+
+/// How result files are opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenMode {
+    /// Every file has to be present and consistent
+    #[default]
+    Strict,
+    /// Truncate to the last consistent export and skip missing ranks
+    Lenient,
+}
+
+/// Summary of what was dropped while opening a run in lenient mode
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// Number of exports listed in `records/time`
+    pub n_export_found: usize,
+    /// Number of exports kept after truncation
+    pub n_export_kept: usize,
+    /// Datasets that were longer than the kept number of exports and have been truncated
+    pub truncated_datasets: Vec<String>,
+    /// Ranks whose partial file is missing or unreadable, their particles are not accounted
+    pub missing_ranks: Vec<usize>,
+    /// Ranks whose last biological export is incomplete
+    pub incomplete_ranks: Vec<usize>,
+    /// True if `final_result` group is missing from the main file
+    pub missing_final: bool,
+}
+
+impl RecoveryReport {
+    /// Number of exports dropped by the truncation
+    pub fn n_export_dropped(&self) -> usize {
+        self.n_export_found.saturating_sub(self.n_export_kept)
+    }
+
+    /// Returns true if nothing had to be dropped
+    pub fn is_clean(&self) -> bool {
+        self.n_export_dropped() == 0
+            && self.truncated_datasets.is_empty()
+            && self.missing_ranks.is_empty()
+            && self.incomplete_ranks.is_empty()
+            && !self.missing_final
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
@@ -1,40 +1,153 @@
+//! Event tallies recorded by BioMC at each export.
+//!
+//! Tallies are stored as one row per export and one column per event type. The number of columns
+//! is read from the dataset so that event types added to BioMC are kept; the known ones are listed
+//! in [`TallyColumn`].
+//!
+//! A row holds either the events since the start of the simulation or the events since the
+//! previous export, as given by the `cumulative` integer attribute of the dataset. Datasets
+//! without the attribute are read as cumulative, see [`Tallies::CUMULATIVE_ATTRIBUTE`].
+use crate::error::ApiError;
+use crate::Weight;
 use csv::Writer;
-use ndarray::ArrayView2;
+use ndarray::{Array1, Array2, ArrayView2, Axis};
 use serde::{Deserialize, Serialize};
 use serde_json;
 use super::vec_to_array_view2;
+
+/// Event types known to the post-processing, in column order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TallyColumn {
+    NewParticle,
+    Death,
+    Move,
+    Exit,
+    Overflow,
+    ChangeWeight,
+}
+
+impl TallyColumn {
+    pub const ALL: [TallyColumn; 6] = [
+        TallyColumn::NewParticle,
+        TallyColumn::Death,
+        TallyColumn::Move,
+        TallyColumn::Exit,
+        TallyColumn::Overflow,
+        TallyColumn::ChangeWeight,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TallyColumn::NewParticle => "NewParticle",
+            TallyColumn::Death => "Death",
+            TallyColumn::Move => "Move",
+            TallyColumn::Exit => "Exit",
+            TallyColumn::Overflow => "Overflow",
+            TallyColumn::ChangeWeight => "ChangeWeight",
+        }
+    }
+}
+
+impl std::str::FromStr for TallyColumn {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TallyColumn::ALL
+            .into_iter()
+            .find(|c| c.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ApiError::KeyError(s.to_string()))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Tallies(pub Vec<f64>);
+pub struct Tallies {
+    pub data: Vec<f64>,
+    /// Number of event types, detected from the dataset shape
+    pub n_column: usize,
+    /// Whether rows count events since the start of the simulation rather than since the previous
+    /// export
+    pub cumulative: bool,
+}
 
 impl Tallies {
+    /// Number of event types written by the BioMC versions without a 2D tallies dataset
+    pub const DEFAULT_N_COLUMN: usize = TallyColumn::ALL.len();
+
+    /// Attribute of the tallies dataset, non-zero if rows are cumulative
+    pub const CUMULATIVE_ATTRIBUTE: &'static str = "cumulative";
+
+    /// Cumulative tallies, see [`Self::with_cumulative`] for the other layout
+    pub fn new(data: Vec<f64>, n_column: usize) -> Self {
+        Self {
+            data,
+            n_column,
+            cumulative: true,
+        }
+    }
+
+    pub fn with_cumulative(self, cumulative: bool) -> Self {
+        Self { cumulative, ..self }
+    }
+
+    /// Number of event types of a tallies dataset.
+    ///
+    /// 2D datasets give it with their shape. Flat ones use [`Self::DEFAULT_N_COLUMN`], unless their
+    /// length is not a multiple of it but splits exactly over the `n_export` rows, which is then
+    /// the only consistent layout.
+    pub fn detect_n_column(shape: &[usize], n_export: usize) -> usize {
+        if let [_, n_column] = shape {
+            return *n_column;
+        }
+        let len: usize = shape.iter().product();
+        let default_fits = len.is_multiple_of(Self::DEFAULT_N_COLUMN);
+        let splits = n_export != 0 && len >= n_export && len.is_multiple_of(n_export);
+        if !default_fits && splits {
+            len / n_export
+        } else {
+            Self::DEFAULT_N_COLUMN
+        }
+    }
+
     pub fn validate(&self) -> bool {
-        self.0.len() % 6 == 0
+        self.n_column != 0 && self.data.len().is_multiple_of(self.n_column)
+    }
+
+    pub fn n_row(&self) -> usize {
+        self.data.len() / self.n_column.max(1)
+    }
+
+    /// Names of the columns, event types unknown to the post-processing are named `Event<i>`
+    pub fn column_names(&self) -> Vec<String> {
+        (0..self.n_column)
+            .map(|i| match TallyColumn::ALL.get(i) {
+                Some(c) => c.name().to_string(),
+                None => format!("Event{}", i),
+            })
+            .collect()
     }
 
     pub fn to_json(&self) -> serde_json::Result<String> {
-        serde_json::to_string_pretty(&self.0)
+        serde_json::to_string_pretty(&self.data)
     }
 
     pub fn to_csv(&self) -> Result<String, String> {
         if !self.validate() {
-            return Err(
-                "Validation failed: The number of elements is not divisible by 6.".to_string(),
-            );
-        }
-
-        let headers = vec![
-            "NewParticle",
-            "Death",
-            "Move",
-            "Exit",
-            "Overflow",
-            "ChangeWeight",
-        ];
+            return Err(format!(
+                "Validation failed: The number of elements is not divisible by {}.",
+                self.n_column
+            ));
+        }
+
         let mut wtr = Writer::from_writer(vec![]);
 
-        wtr.write_record(&headers).map_err(|e| e.to_string())?;
+        wtr.write_record(self.column_names())
+            .map_err(|e| e.to_string())?;
 
-        for row in self.0.chunks(6) {
+        for row in self.data.chunks(self.n_column) {
             wtr.serialize(row).map_err(|e| e.to_string())?;
         }
         let data = String::from_utf8(wtr.into_inner().map_err(|e| e.to_string())?)
@@ -44,6 +157,155 @@ impl Tallies {
 
     pub fn to_array(&self)->ArrayView2<f64>
     {
-        vec_to_array_view2(&self.0, self.0.len()/6, 6)
+        let n_row = self.n_row();
+        vec_to_array_view2(&self.data[..n_row * self.n_column], n_row, self.n_column)
+    }
+
+    /// Rows of the exports in `range`, clamped to the recorded rows
+    pub fn slice(&self, range: std::ops::Range<usize>) -> Self {
+        let start = (range.start * self.n_column).min(self.data.len());
+        let end = (range.end * self.n_column).min(self.data.len());
+        Self::new(self.data[start..end].to_vec(), self.n_column).with_cumulative(self.cumulative)
+    }
+}
+
+/// Tallies with named columns and their time derivatives.
+///
+/// Counts are cumulative whatever the layout of the stored tallies (see [`Tallies::cumulative`]),
+/// increments are the differences between consecutive exports.
+///
+/// # Example
+/// ```ignore
+/// let table = pp.get_tally_table()?;
+/// let division_rate = table.rates().column(TallyColumn::NewParticle.index()).to_owned();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TallyTable {
+    pub columns: Vec<String>,
+    /// Export times in seconds
+    pub time: Array1<f64>,
+    /// Cumulative counts, shape (nt, n_column)
+    pub cumulative: Array2<f64>,
+}
+
+impl TallyTable {
+    /// Builds the table from the stored tallies, rows beyond `time` are dropped.
+    pub fn new(tallies: &Tallies, time: &[f64]) -> Result<Self, ApiError> {
+        if !tallies.validate() {
+            return Err(ApiError::ShapeError);
+        }
+        let nt = tallies.n_row().min(time.len());
+        let mut cumulative = tallies
+            .to_array()
+            .slice(ndarray::s![..nt, ..])
+            .to_owned();
+        if !tallies.cumulative {
+            cumulative.accumulate_axis_inplace(Axis(0), |&previous, x| *x += previous);
+        }
+        Ok(Self {
+            columns: tallies.column_names(),
+            time: Array1::from_vec(time[..nt].to_vec()),
+            cumulative,
+        })
+    }
+
+    pub fn n_column(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Cumulative counts of an event type, `None` if the run did not record it
+    pub fn get(&self, column: TallyColumn) -> Option<Array1<f64>> {
+        (column.index() < self.n_column())
+            .then(|| self.cumulative.column(column.index()).to_owned())
+    }
+
+    /// Events between consecutive exports, the first row counts events since the start.
+    pub fn increments(&self) -> Array2<f64> {
+        let mut increments = self.cumulative.clone();
+        for i in (1..self.cumulative.nrows()).rev() {
+            let previous = self.cumulative.row(i - 1);
+            increments.row_mut(i).zip_mut_with(&previous, |x, p| *x -= p);
+        }
+        increments
+    }
+
+    /// Events per second between consecutive exports, the first interval starts at time 0.
+    ///
+    /// Rates over an empty interval are NaN.
+    pub fn rates(&self) -> Array2<f64> {
+        let mut rates = self.increments();
+        for (i, mut row) in rates.axis_iter_mut(Axis(0)).enumerate() {
+            let dt = self.time[i] - if i == 0 { 0. } else { self.time[i - 1] };
+            row.mapv_inplace(|x| if dt > 0. { x / dt } else { f64::NAN });
+        }
+        rates
+    }
+
+    /// Table scaled by the particle weight, i.e. counts of cells instead of Monte Carlo particles
+    pub fn scaled(&self, weight: &Weight) -> Result<Self, ApiError> {
+        match weight {
+            Weight::Single(w) => Ok(Self {
+                cumulative: &self.cumulative * *w,
+                ..self.clone()
+            }),
+            Weight::Multiple(_) => Err(ApiError::Default(
+                "Tallies can not be scaled with a weight per particle".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn table() -> TallyTable {
+        // 3 exports, 7 columns as written by a BioMC with an extra event type
+        let data = (0..21).map(|i| (i / 7 * (i % 7 + 1)) as f64).collect();
+        TallyTable::new(&Tallies::new(data, 7), &[0., 2., 4.]).unwrap()
+    }
+
+    #[test]
+    fn test_columns() {
+        let t = table();
+        assert_eq!(t.columns[5], "ChangeWeight");
+        assert_eq!(t.columns[6], "Event6");
+        assert_eq!(t.get(TallyColumn::Death), Some(array![0., 2., 4.]));
+        assert_eq!("move".parse::<TallyColumn>().unwrap(), TallyColumn::Move);
+        assert!(Tallies::new(vec![0.; 7], 6).to_csv().is_err());
+    }
+
+    #[test]
+    fn test_detect_n_column() {
+        assert_eq!(Tallies::detect_n_column(&[3, 7], 5), 7);
+        // 12 values over 2 or 3 exports: the default layout is kept
+        assert_eq!(Tallies::detect_n_column(&[12], 2), 6);
+        assert_eq!(Tallies::detect_n_column(&[12], 3), 6);
+        assert_eq!(Tallies::detect_n_column(&[21], 3), 7);
+        assert_eq!(Tallies::detect_n_column(&[20], 3), 6);
+    }
+
+    #[test]
+    fn test_increments_rates() {
+        let t = table();
+        let new_particle = TallyColumn::NewParticle.index();
+        assert_eq!(t.increments().column(new_particle), array![0., 1., 1.]);
+        let rates = t.rates();
+        assert!(rates[[0, new_particle]].is_nan());
+        assert_eq!(rates.column(new_particle).slice(ndarray::s![1..]), array![0.5, 0.5]);
+        let scaled = t.scaled(&Weight::Single(10.)).unwrap();
+        assert_eq!(scaled.get(TallyColumn::NewParticle), Some(array![0., 10., 20.]));
+    }
+
+    #[test]
+    fn test_per_export_layout() {
+        // Same events as `table`, counted since the previous export
+        let data = (0..21).map(|i| (i / 7).min(1) as f64 * (i % 7 + 1) as f64).collect();
+        let tallies = Tallies::new(data, 7).with_cumulative(false);
+        let t = TallyTable::new(&tallies, &[0., 2., 4.]).unwrap();
+        assert_eq!(t, table());
+        assert_eq!(t.increments().column(TallyColumn::Death.index()), array![0., 2., 2.]);
+        assert!(!tallies.slice(1..3).cumulative);
     }
-}
\ No newline at end of file
+}
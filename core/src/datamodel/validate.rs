@@ -0,0 +1,419 @@
+//! Cross-file consistency checks.
+//!
+//! Checks are performed directly on the HDF5 files, so that a corrupted run can be diagnosed even
+//! when it cannot be opened with [`crate::PostProcess`].
+use super::tallies::Tallies;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Severity of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Severity {
+    /// Suspicious but the run can be analysed
+    Warning,
+    /// The run is corrupted and analysis results would be wrong or fail
+    Error,
+}
+
+/// A problem found while validating a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// File in which the problem has been found
+    pub file: String,
+    /// HDF5 path of the faulty object
+    pub object: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(file: &str, object: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            file: file.to_string(),
+            object: object.to_string(),
+            message,
+        }
+    }
+
+    fn warning(file: &str, object: &str, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            file: file.to_string(),
+            object: object.to_string(),
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:?}] {}:{}: {}",
+            self.severity, self.file, self.object, self.message
+        )
+    }
+}
+
+fn shape_of(group: &hdf5::Group, name: &str) -> Option<Vec<usize>> {
+    group.dataset(name).ok().map(|d| d.shape())
+}
+
+fn dataset_name(dataset: &hdf5::Dataset) -> String {
+    PathBuf::from(dataset.name())
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Checks the records of the main file.
+///
+/// # Returns
+/// * `Option<(usize, usize)>` - Number of exports and compartments if they can be determined
+fn validate_records(
+    path: &str,
+    records: &hdf5::Group,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<(usize, usize)> {
+    let nt = match shape_of(records, "time") {
+        Some(shape) => shape.iter().product::<usize>(),
+        None => {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "records/time",
+                "Missing dataset".to_string(),
+            ));
+            return None;
+        }
+    };
+
+    let (n_compartment, n_species) = match shape_of(records, "concentration_liquid") {
+        Some(shape) if shape.len() == 3 => {
+            if shape[0] != nt {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    "records/concentration_liquid",
+                    format!("{} exports while records/time has {}", shape[0], nt),
+                ));
+            }
+            (shape[1], shape[2])
+        }
+        Some(shape) => {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "records/concentration_liquid",
+                format!(
+                    "Expected shape (nt, n_compartment, n_species), got {:?}",
+                    shape
+                ),
+            ));
+            return None;
+        }
+        None => {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "records/concentration_liquid",
+                "Missing dataset".to_string(),
+            ));
+            return None;
+        }
+    };
+
+    let expected_volume = vec![nt, n_compartment];
+    match shape_of(records, "volume_liquid") {
+        Some(shape) if shape != expected_volume => diagnostics.push(Diagnostic::error(
+            path,
+            "records/volume_liquid",
+            format!("Expected shape {:?}, got {:?}", expected_volume, shape),
+        )),
+        None => diagnostics.push(Diagnostic::error(
+            path,
+            "records/volume_liquid",
+            "Missing dataset".to_string(),
+        )),
+        _ => {}
+    }
+
+    let expected_concentration = vec![nt, n_compartment, n_species];
+    match (
+        shape_of(records, "concentration_gas"),
+        shape_of(records, "volume_gas"),
+    ) {
+        (Some(c), Some(v)) => {
+            if c != expected_concentration {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    "records/concentration_gas",
+                    format!("Expected shape {:?}, got {:?}", expected_concentration, c),
+                ));
+            }
+            if v != expected_volume {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    "records/volume_gas",
+                    format!("Expected shape {:?}, got {:?}", expected_volume, v),
+                ));
+            }
+        }
+        (Some(_), None) | (None, Some(_)) => diagnostics.push(Diagnostic::warning(
+            path,
+            "records",
+            "Only one of concentration_gas and volume_gas is present, gas phase is ignored"
+                .to_string(),
+        )),
+        (None, None) => {}
+    }
+
+    if let Some(shape) = shape_of(records, "mtr") {
+        if shape != expected_concentration {
+            diagnostics.push(Diagnostic::error(
+                path,
+                "records/mtr",
+                format!(
+                    "Expected shape {:?}, got {:?}",
+                    expected_concentration, shape
+                ),
+            ));
+        }
+    }
+
+    if let Ok(dataset) = records.dataset("tallies") {
+        match dataset.read_raw::<f64>() {
+            Ok(raw) => {
+                let n_column = Tallies::detect_n_column(&dataset.shape(), nt);
+                if dataset.shape().len() != 2 && n_column != Tallies::DEFAULT_N_COLUMN {
+                    diagnostics.push(Diagnostic::warning(
+                        path,
+                        "records/tallies",
+                        format!(
+                            "Flat tallies split into {} columns from the number of exports",
+                            n_column
+                        ),
+                    ));
+                }
+                if !Tallies::new(raw, n_column).validate() {
+                    diagnostics.push(Diagnostic::error(
+                        path,
+                        "records/tallies",
+                        "Number of elements is not divisible by the number of events".to_string(),
+                    ));
+                }
+            }
+            Err(e) => diagnostics.push(Diagnostic::error(path, "records/tallies", e.to_string())),
+        }
+    }
+
+    Some((nt, n_compartment))
+}
+
+/// Checks the biological exports of a partial file against the key set of the first export found.
+fn validate_biological_model(
+    path: &str,
+    file: &hdf5::File,
+    reference_keys: &mut Option<BTreeSet<String>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let bio = match file.group("biological_model") {
+        Ok(bio) => bio,
+        Err(_) => {
+            diagnostics.push(Diagnostic::warning(
+                path,
+                "biological_model",
+                "Missing group, no particle has been exported".to_string(),
+            ));
+            return;
+        }
+    };
+
+    for i_e in 0..bio.len() as usize {
+        let object = format!("biological_model/{}", i_e);
+        let group = match bio.group(&i_e.to_string()) {
+            Ok(group) => group,
+            Err(_) => {
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    &object,
+                    "Missing group".to_string(),
+                ));
+                continue;
+            }
+        };
+        let datasets = match group.datasets() {
+            Ok(datasets) => datasets,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(path, &object, e.to_string()));
+                continue;
+            }
+        };
+
+        let keys: BTreeSet<String> = datasets.iter().map(dataset_name).collect();
+        match reference_keys {
+            None => *reference_keys = Some(keys),
+            Some(reference) if *reference != keys => {
+                let missing: Vec<&String> = reference.difference(&keys).collect();
+                let extra: Vec<&String> = keys.difference(reference).collect();
+                diagnostics.push(Diagnostic::error(
+                    path,
+                    &object,
+                    format!(
+                        "Key set differs: missing {:?}, unexpected {:?}",
+                        missing, extra
+                    ),
+                ));
+            }
+            _ => {}
+        }
+
+        let sizes: BTreeSet<usize> = datasets.iter().map(|d| d.size()).collect();
+        if sizes.len() > 1 {
+            diagnostics.push(Diagnostic::error(
+                path,
+                &object,
+                format!("Properties have different lengths: {:?}", sizes),
+            ));
+        }
+    }
+}
+
+/// Checks the consistency of a run made of a main file and one partial file per rank.
+///
+/// # Arguments
+/// * `main_path` - Path of the main file.
+/// * `partial_path` - Returns the path of the partial file written by a rank.
+///
+/// # Returns
+/// * `Vec<Diagnostic>` - Every problem found, empty if the run is consistent.
+pub fn validate_run<F>(main_path: &str, partial_path: F) -> Vec<Diagnostic>
+where
+    F: Fn(usize) -> String,
+{
+    let mut diagnostics = vec![];
+
+    let main = match hdf5::File::open_as(main_path, hdf5::file::OpenMode::Read) {
+        Ok(file) => file,
+        Err(e) => {
+            diagnostics.push(Diagnostic::error(main_path, "/", e.to_string()));
+            return diagnostics;
+        }
+    };
+
+    let dims = match main.group("records") {
+        Ok(records) => validate_records(main_path, &records, &mut diagnostics),
+        Err(_) => {
+            diagnostics.push(Diagnostic::error(
+                main_path,
+                "records",
+                "Missing group".to_string(),
+            ));
+            None
+        }
+    };
+
+    let n_rank = match main
+        .dataset("misc/n_rank")
+        .and_then(|d| d.read_scalar::<u64>())
+    {
+        Ok(n) => n as usize,
+        Err(_) => {
+            diagnostics.push(Diagnostic::error(
+                main_path,
+                "misc/n_rank",
+                "Missing dataset, partial files cannot be checked".to_string(),
+            ));
+            return diagnostics;
+        }
+    };
+
+    let final_number = main
+        .dataset("final_result/number_particles")
+        .and_then(|d| d.read_scalar::<u64>())
+        .ok();
+    if final_number.is_none() {
+        diagnostics.push(Diagnostic::warning(
+            main_path,
+            "final_result",
+            "Missing final results, the run may not have completed".to_string(),
+        ));
+    }
+
+    let mut reference_keys = None;
+    let mut last_number_particle = 0.;
+    let mut all_ranks_read = true;
+    for rank in 0..n_rank {
+        let path = partial_path(rank);
+        let file = match hdf5::File::open_as(&path, hdf5::file::OpenMode::Read) {
+            Ok(file) => file,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(&path, "/", e.to_string()));
+                all_ranks_read = false;
+                continue;
+            }
+        };
+
+        match file.dataset("records/number_particle") {
+            Ok(dataset) => {
+                let shape = dataset.shape();
+                if let Some((nt, n_compartment)) = dims {
+                    if shape != vec![nt, n_compartment] {
+                        diagnostics.push(Diagnostic::error(
+                            &path,
+                            "records/number_particle",
+                            format!("Expected shape {:?}, got {:?}", (nt, n_compartment), shape),
+                        ));
+                    }
+                }
+                match dataset.read_raw::<f64>() {
+                    Ok(n_p) if shape.len() == 2 && shape[0] > 0 => {
+                        last_number_particle +=
+                            n_p[(shape[0] - 1) * shape[1]..].iter().sum::<f64>();
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        diagnostics.push(Diagnostic::error(
+                            &path,
+                            "records/number_particle",
+                            e.to_string(),
+                        ));
+                        all_ranks_read = false;
+                    }
+                }
+            }
+            Err(_) => {
+                diagnostics.push(Diagnostic::error(
+                    &path,
+                    "records/number_particle",
+                    "Missing dataset".to_string(),
+                ));
+                all_ranks_read = false;
+            }
+        }
+
+        if file.dataset("probes").is_err() {
+            diagnostics.push(Diagnostic::warning(
+                &path,
+                "probes",
+                "Missing dataset".to_string(),
+            ));
+        }
+
+        validate_biological_model(&path, &file, &mut reference_keys, &mut diagnostics);
+    }
+
+    if let Some(final_number) = final_number {
+        if all_ranks_read && final_number as f64 != last_number_particle {
+            diagnostics.push(Diagnostic::error(
+                main_path,
+                "final_result/number_particles",
+                format!(
+                    "{} particles while the last export counts {}",
+                    final_number, last_number_particle
+                ),
+            ));
+        }
+    }
+
+    diagnostics
+}
@@ -0,0 +1,208 @@
+//! Statistics across replicate runs of the same simulation.
+//!
+//! Monte Carlo runs are stochastic, an [`Ensemble`] gathers replicates sharing the same time grid
+//! (typically different seeds) and returns the mean, standard deviation and confidence band of
+//! their time series.
+use crate::api::{Estimator, ModelEstimator, Phase, PostProcessReader, SpeciesKey};
+use crate::error::ApiError;
+use crate::process::stats::{band, Band};
+use crate::process::Histogram;
+use crate::PostProcess;
+use ndarray::{Array, Array1, Array2, ArrayView1, Axis, Dimension, Ix1, Ix2};
+
+/// Relative tolerance used to compare the time grids of the replicates
+const TIME_TOLERANCE: f64 = 1e-9;
+
+/// Confidence level used when none is given
+pub const DEFAULT_LEVEL: f64 = 0.95;
+
+/// Replicate runs sharing the same time grid.
+///
+/// # Example
+/// ```ignore
+/// let ensemble = Ensemble::open(&["run_1", "run_2", "run_3"], None)?;
+/// let x = ensemble.spatial_average_biomass_concentration(0.95)?;
+/// println!("{} ± {}", x.mean[10], x.upper[10] - x.mean[10]);
+/// ```
+#[derive(Debug)]
+pub struct Ensemble {
+    runs: Vec<PostProcess>,
+}
+
+impl Ensemble {
+    /// Creates an ensemble, all runs must have the same time grid.
+    pub fn new(runs: Vec<PostProcess>) -> Result<Self, ApiError> {
+        let first = runs
+            .first()
+            .ok_or_else(|| ApiError::Default("An ensemble needs at least one run".to_string()))?;
+        let time = first.time();
+        for (i, run) in runs.iter().enumerate().skip(1) {
+            let same_grid =
+                run.time().len() == time.len()
+                    && run.time().iter().zip(time).all(|(a, b)| {
+                        (a - b).abs() <= TIME_TOLERANCE * a.abs().max(b.abs()).max(1.)
+                    });
+            if !same_grid {
+                return Err(ApiError::Default(format!(
+                    "Run {} does not share the time grid of run 0",
+                    i
+                )));
+            }
+        }
+        Ok(Self { runs })
+    }
+
+    /// Opens every folder with [`PostProcess::new`] and gathers them into an ensemble
+    pub fn open<S: AsRef<str>>(folders: &[S], root: Option<String>) -> Result<Self, ApiError> {
+        let runs = folders
+            .iter()
+            .map(|f| PostProcess::new(f.as_ref(), root.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(runs)
+    }
+
+    pub fn runs(&self) -> &[PostProcess] {
+        &self.runs
+    }
+
+    pub fn runs_mut(&mut self) -> &mut [PostProcess] {
+        &mut self.runs
+    }
+
+    pub fn n_run(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Time grid shared by the runs
+    pub fn time(&self) -> &[f64] {
+        self.runs[0].time()
+    }
+
+    /// Statistics of any quantity computed on each run.
+    ///
+    /// # Arguments
+    /// * `level` - Confidence level of the band, e.g. 0.95.
+    /// * `f` - Quantity of a run, all runs must return the same shape.
+    pub fn band<D, F>(&self, level: f64, f: F) -> Result<Band<D>, ApiError>
+    where
+        D: Dimension,
+        F: Fn(&PostProcess) -> Result<Array<f64, D>, ApiError>,
+    {
+        let samples = self.runs.iter().map(f).collect::<Result<Vec<_>, _>>()?;
+        band(&samples, level)
+    }
+
+    /// Biomass concentration per compartment, shape (nt, n_compartment)
+    pub fn biomass_concentration(&self, level: f64) -> Result<Band<Ix2>, ApiError> {
+        self.band(level, |r| r.get_biomass_concentration())
+    }
+
+    pub fn spatial_average_biomass_concentration(&self, level: f64) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| r.get_spatial_average_biomass_concentration())
+    }
+
+    pub fn spatial_average_concentration(
+        &self,
+        species: impl SpeciesKey + Copy,
+        phase: Phase,
+        level: f64,
+    ) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| {
+            let i = r.species_index(species)?;
+            r.get_spatial_average_concentration(i, phase)
+        })
+    }
+
+    /// Concentrations per compartment, shape (nt, n_compartment) for one species
+    pub fn concentration(
+        &self,
+        species: impl SpeciesKey + Copy,
+        phase: Phase,
+        level: f64,
+    ) -> Result<Band<Ix2>, ApiError> {
+        self.band(level, |r| {
+            let i = r.species_index(species)?;
+            r.check_phase(phase)?;
+            Ok(r.get_concentrations(phase)
+                .index_axis(Axis(2), i)
+                .to_owned())
+        })
+    }
+
+    pub fn time_population_mean(&self, key: &str, level: f64) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| r.get_time_population_mean(key))
+    }
+
+    pub fn estimate_time(
+        &self,
+        etype: Estimator,
+        key: &str,
+        level: f64,
+    ) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| r.estimate_time(etype, key))
+    }
+
+    pub fn mu_direct(&self, level: f64) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| r.mu_direct())
+    }
+
+    pub fn growth_in_number(&self, level: f64) -> Result<Band<Ix1>, ApiError> {
+        self.band(level, |r| Ok(r.get_growth_in_number()))
+    }
+
+    /// Histogram of a property over the particles of all runs at an export.
+    ///
+    /// Bins span the range of the pooled particles.
+    ///
+    /// # Returns
+    /// * `(Vec<f64>, Vec<f64>)` - Lower edges of the bins and pooled counts.
+    pub fn pooled_histogram(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> Result<(Vec<f64>, Vec<f64>), ApiError> {
+        let (bins, counts) = self.histograms(n_bins, i_export, key)?;
+        Ok((bins.to_vec(), counts.sum_axis(Axis(0)).to_vec()))
+    }
+
+    /// Histogram of a property for each run at an export, on the bins of the pooled histogram so
+    /// that counts of different runs are comparable.
+    ///
+    /// # Returns
+    /// * `(Array1<f64>, Array2<f64>)` - Lower edges of the bins and counts of shape
+    ///   (n_run, n_bins), both empty if no run has particles.
+    pub fn histograms(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> Result<(Array1<f64>, Array2<f64>), ApiError> {
+        if n_bins == 0 {
+            return Err(ApiError::Default("Histogram needs at least one bin".to_string()));
+        }
+        let values = self
+            .runs
+            .iter()
+            .map(|r| r.get_properties(key, i_export))
+            .collect::<Result<Vec<_>, _>>()?;
+        let all = values.iter().flat_map(|v| v.iter()).cloned();
+        let min = all.clone().fold(f64::INFINITY, f64::min);
+        let max = all.fold(f64::NEG_INFINITY, f64::max);
+        if !min.is_finite() || !max.is_finite() {
+            return Ok((Array1::zeros(0), Array2::zeros((self.n_run(), 0))));
+        }
+
+        let mut counts = Array2::zeros((self.n_run(), n_bins));
+        let mut bins = vec![];
+        for (i, v) in values.into_iter().enumerate() {
+            let mut hist = Histogram::with_range(n_bins, min, max);
+            hist.add(v.to_vec());
+            counts
+                .row_mut(i)
+                .assign(&ArrayView1::from(hist.get_counts()));
+            bins = hist.get_bins().to_vec();
+        }
+        Ok((Array1::from_vec(bins), counts))
+    }
+}
@@ -10,6 +10,15 @@ pub enum ApiError {
     #[error("Records quantity '{0}' does not exist in the selected dataset")]
     RecordsError(String),
 
+    #[error("Species '{0}' does not exist, available species are {1:?}")]
+    SpeciesError(String, Vec<String>),
+
+    #[error("Cannot convert '{0}' to '{1}'")]
+    UnitError(String, String),
+
+    #[error("Invalid expression: {0}")]
+    ExpressionError(String),
+
     #[error("Datasetd shape mismatch")]
     ShapeError,
 
@@ -20,4 +29,11 @@ pub enum ApiError {
     Default(String),
 }
 
+impl ApiError {
+    /// Error of a gas phase quantity requested from a run without gas phase
+    pub fn missing_gas() -> Self {
+        ApiError::RecordsError("concentration_gas".to_string())
+    }
+}
+
 
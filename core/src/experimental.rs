@@ -0,0 +1,353 @@
+//! Experimental time series and their agreement with a run.
+//!
+//! Measurements are read from CSV with units in the headers, e.g.
+//!
+//! ```text
+//! time [h],biomass [g/L],biomass_std [g/L],glucose [g/L],glucose_std [g/L]
+//! 0,0.5,0.02,20,0.5
+//! 1.5,0.9,0.03,,
+//! ```
+//!
+//! The first column is the sampling time, `<name>_std` columns hold the standard deviation of
+//! `<name>` and empty cells are missing samples. Rows may be in any order, they are sorted by
+//! time. Values in a known unit are converted on import to the unit of the API for that dimension
+//! (e.g. seconds, kg/m3 or 1/s), see [`crate::units::Quantity`]. Other units such as `%` or `OD`
+//! are kept as written and their variables are not compared to a run. The time column must be in
+//! a time unit known to [`crate::units::Unit`].
+use crate::api::{Phase, PostProcessReader};
+use crate::error::ApiError;
+use crate::process::interpolate::linear;
+use crate::units::{parse_label, split_label, Dimension, Quantity, Unit};
+use ndarray::Array1;
+use std::path::Path;
+
+/// Name of the measured variable compared to the spatial average biomass concentration
+pub const BIOMASS: &str = "biomass";
+
+/// Samples of one measured variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    /// Unit of the CSV column, values are stored in API units.
+    ///
+    /// `None` without unit or for units unknown to [`Unit`] (e.g. `%`, `OD`), whose values are
+    /// kept as written.
+    pub unit: Option<Unit>,
+    /// `false` for a unit unknown to [`Unit`], the values cannot be compared to a run
+    pub comparable: bool,
+    /// Sampling times in seconds
+    pub time: Vec<f64>,
+    pub values: Vec<f64>,
+    pub std: Option<Vec<f64>>,
+}
+
+impl Measurement {
+    /// Whether the samples are concentrations in API units, the only variables compared to a run.
+    ///
+    /// Samples without unit are assumed to be in API units.
+    pub fn is_concentration(&self) -> bool {
+        match self.unit {
+            Some(unit) => unit.dimension() == Dimension::Concentration,
+            None => self.comparable,
+        }
+    }
+
+    /// Interpolates the samples onto a time grid, e.g. [`PostProcessReader::time_array`].
+    ///
+    /// Values outside the sampled time range are NaN.
+    pub fn on_grid(&self, time: &[f64]) -> Array1<f64> {
+        linear(
+            &self.time,
+            &Array1::from_vec(self.values.clone()).view(),
+            time,
+        )
+    }
+}
+
+/// Measured time series, typically offline samples of a bioreactor
+///
+/// # Example
+/// ```ignore
+/// let data = ExperimentalData::from_file("batch_1.csv")?;
+/// for a in data.agreement(&pp)? {
+///     println!("{}: RMSE {} R² {}", a.name, a.rmse, a.r2);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExperimentalData {
+    pub measurements: Vec<Measurement>,
+}
+
+/// Name, unit and comparability of a header, see [`Measurement::comparable`].
+///
+/// The unit is `None` if missing or unknown to [`Unit`], only the latter is not comparable.
+fn parse_header(header: &str) -> (String, Option<Unit>, bool) {
+    match parse_label(header) {
+        Ok((name, unit)) => (name, unit, true),
+        Err(_) => (split_label(header).0.to_string(), None, false),
+    }
+}
+
+/// Quantity returned by the API in the dimension of `unit`
+fn api_quantity(unit: Unit) -> Option<Quantity> {
+    match unit.dimension() {
+        Dimension::Time => Some(Quantity::Time),
+        Dimension::Volume => Some(Quantity::Volume),
+        Dimension::Mass => Some(Quantity::Mass),
+        Dimension::Concentration => Some(Quantity::Concentration),
+        Dimension::Rate => Some(Quantity::GrowthRate),
+        Dimension::ConcentrationRate => Some(Quantity::MassTransferRate),
+        Dimension::Dimensionless => None,
+    }
+}
+
+/// Converts values to the unit in which the API returns their dimension
+fn to_api_unit(values: &mut [f64], unit: Option<Unit>) -> Result<(), ApiError> {
+    if let Some((unit, quantity)) = unit.and_then(|u| api_quantity(u).map(|q| (u, q))) {
+        let factor = unit.conversion_factor(quantity.unit())?;
+        values.iter_mut().for_each(|v| *v *= factor);
+    }
+    Ok(())
+}
+
+impl ExperimentalData {
+    pub fn from_csv(csv: &str) -> Result<Self, ApiError> {
+        let to_api_error = |e: csv::Error| ApiError::Default(format!("Invalid CSV: {}", e));
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv.as_bytes());
+        let headers = reader.headers().map_err(to_api_error)?.clone();
+        let Some(time_header) = headers.get(0) else {
+            return Err(ApiError::Default("Missing time column".to_string()));
+        };
+        // Unlike other columns, the time column can't be kept in an unknown unit
+        if let (_, Some(unit)) = parse_label(time_header)? {
+            unit.conversion_factor(Quantity::Time.unit())?;
+        }
+        let headers = headers.iter().map(parse_header).collect::<Vec<_>>();
+
+        // Raw columns, NaN for missing cells
+        let mut columns: Vec<Vec<f64>> = vec![vec![]; headers.len()];
+        for record in reader.records() {
+            let record = record.map_err(to_api_error)?;
+            for (column, cell) in columns.iter_mut().zip(record.iter()) {
+                let value = if cell.is_empty() {
+                    f64::NAN
+                } else {
+                    cell.parse::<f64>()
+                        .map_err(|_| ApiError::Default(format!("Invalid number '{}'", cell)))?
+                };
+                column.push(value);
+            }
+        }
+        for (column, (_, unit, _)) in columns.iter_mut().zip(&headers) {
+            to_api_unit(column, *unit)?;
+        }
+
+        // Rows sorted by time, interpolation needs increasing sampling times
+        let mut rows: Vec<usize> = (0..columns[0].len()).collect();
+        rows.sort_by(|a, b| columns[0][*a].total_cmp(&columns[0][*b]));
+        let time = &columns[0];
+        let mut measurements = vec![];
+        for (i, (name, unit, comparable)) in headers.iter().enumerate().skip(1) {
+            if name.ends_with("_std") {
+                continue;
+            }
+            let std_column = headers
+                .iter()
+                .position(|(n, _, _)| *n == format!("{}_std", name));
+            let (mut t, mut values, mut std) = (vec![], vec![], vec![]);
+            for &row in &rows {
+                let value = columns[i][row];
+                if value.is_nan() || time[row].is_nan() {
+                    continue;
+                }
+                t.push(time[row]);
+                values.push(value);
+                if let Some(j) = std_column {
+                    std.push(columns[j][row]);
+                }
+            }
+            measurements.push(Measurement {
+                name: name.clone(),
+                unit: *unit,
+                comparable: *comparable,
+                time: t,
+                values,
+                std: std_column.map(|_| std),
+            });
+        }
+        Ok(Self { measurements })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ApiError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ApiError::Default(e.to_string()))?;
+        Self::from_csv(&content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Measurement> {
+        self.measurements.iter().find(|m| m.name == name)
+    }
+
+    /// Renames a measured variable, e.g. to match a species name of the run
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), ApiError> {
+        let m = self
+            .measurements
+            .iter_mut()
+            .find(|m| m.name == from)
+            .ok_or_else(|| ApiError::KeyError(from.to_string()))?;
+        m.name = to.to_string();
+        Ok(())
+    }
+
+    /// Compares every measured variable to the run.
+    ///
+    /// `biomass` is compared to the spatial average biomass concentration and any other variable to
+    /// the spatial average liquid concentration of the species of the same name.
+    /// The run is interpolated at the sampling times, samples outside the run are ignored.
+    ///
+    /// Variables that are not concentrations (see [`Measurement::is_concentration`]), e.g.
+    /// `od [OD]` or `mu [1/h]`, and variables that are neither `biomass` nor a species of the run
+    /// are skipped.
+    pub fn agreement<R: PostProcessReader>(&self, reader: &R) -> Result<Vec<Agreement>, ApiError> {
+        let run_time = reader.time();
+        let species = reader.species_names();
+        let mut result = vec![];
+        for m in self.measurements.iter().filter(|m| m.is_concentration()) {
+            let simulated = if m.name == BIOMASS {
+                reader.get_spatial_average_biomass_concentration()?
+            } else if species.contains(&m.name) {
+                reader.get_spatial_average_concentration(m.name.as_str(), Phase::Liquid)?
+            } else {
+                continue;
+            };
+            let simulated = linear(run_time, &simulated.view(), &m.time);
+            result.push(Agreement::new(m, simulated.to_vec()));
+        }
+        Ok(result)
+    }
+}
+
+/// Agreement between a measured variable and a run at the sampling times
+#[derive(Debug, Clone, PartialEq)]
+pub struct Agreement {
+    pub name: String,
+    /// Sampling times inside the run, in seconds
+    pub time: Array1<f64>,
+    pub measured: Array1<f64>,
+    pub simulated: Array1<f64>,
+    /// `measured - simulated`
+    pub residuals: Array1<f64>,
+    pub rmse: f64,
+    /// RMSE divided by the range of the measured values
+    pub nrmse: f64,
+    /// Coefficient of determination of the measured values by the run
+    pub r2: f64,
+    /// Sum of squared residuals weighted by the measured variance, NaN without standard deviation
+    pub chi2: f64,
+}
+
+impl Agreement {
+    /// Builds the metrics from the samples and the run interpolated at the sampling times.
+    ///
+    /// Samples for which the run is NaN (outside the run) are dropped.
+    pub fn new(measurement: &Measurement, simulated: Vec<f64>) -> Self {
+        let keep: Vec<usize> = (0..measurement.values.len())
+            .filter(|i| simulated.get(*i).is_some_and(|s| !s.is_nan()))
+            .collect();
+        let pick = |v: &[f64]| -> Array1<f64> { keep.iter().map(|i| v[*i]).collect() };
+        let time = pick(&measurement.time);
+        let measured = pick(&measurement.values);
+        let simulated = pick(&simulated);
+        let residuals = &measured - &simulated;
+
+        let n = residuals.len() as f64;
+        let ss_res = residuals.powi(2).sum();
+        let rmse = (ss_res / n).sqrt();
+        let mean = measured.mean().unwrap_or(f64::NAN);
+        let ss_tot = measured.mapv(|x| (x - mean).powi(2)).sum();
+        let range = measured.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+            - measured.iter().cloned().fold(f64::INFINITY, f64::min);
+        let chi2 = match &measurement.std {
+            Some(std) => residuals
+                .iter()
+                .zip(pick(std).iter())
+                .map(|(r, s)| (r / s).powi(2))
+                .sum(),
+            None => f64::NAN,
+        };
+
+        Self {
+            name: measurement.name.clone(),
+            time,
+            measured,
+            simulated,
+            residuals,
+            rmse,
+            nrmse: rmse / range,
+            r2: 1. - ss_res / ss_tot,
+            chi2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    const CSV: &str = "time [h],biomass [g/L],biomass_std [g/L],glucose [g/L]\n\
+                       0,0.5,0.1,20\n\
+                       1,1.0,0.1,\n\
+                       2,2.0,0.2,10\n";
+
+    #[test]
+    fn test_parse() {
+        let data = ExperimentalData::from_csv(CSV).unwrap();
+        assert_eq!(data.measurements.len(), 2);
+        let x = data.get("biomass").unwrap();
+        assert_eq!(x.time, vec![0., 3600., 7200.]);
+        assert_eq!(x.values, vec![0.5, 1., 2.]);
+        assert_eq!(x.std, Some(vec![0.1, 0.1, 0.2]));
+        let s = data.get("glucose").unwrap();
+        assert_eq!(s.time, vec![0., 7200.]);
+        assert_eq!(s.std, None);
+        assert_eq!(s.on_grid(&[3600.])[0], 15.);
+    }
+
+    #[test]
+    fn test_agreement() {
+        let data = ExperimentalData::from_csv(CSV).unwrap();
+        let x = data.get("biomass").unwrap();
+        let a = Agreement::new(x, vec![0.6, 1.0, f64::NAN]);
+        assert_eq!(a.time, array![0., 3600.]);
+        assert!((a.residuals[0] + 0.1).abs() < 1e-12);
+        assert!((a.rmse - (0.01f64 / 2.).sqrt()).abs() < 1e-12);
+        assert!((a.chi2 - 1.).abs() < 1e-12);
+        assert!((a.r2 - (1. - 0.01 / 0.125)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_units() {
+        assert!(ExperimentalData::from_csv("time [g/L],x\n0,1\n").is_err());
+        assert!(ExperimentalData::from_csv("time [d],x\n0,1\n").is_err());
+        let csv = "time [min],od [OD],viability [%],mu [1/h]\n2,0.4,90,0.36\n1,0.2,95,\n";
+        let data = ExperimentalData::from_csv(csv).unwrap();
+        let od = data.get("od").unwrap();
+        assert_eq!(od.time, vec![60., 120.]);
+        assert_eq!(od.values, vec![0.2, 0.4]);
+        assert_eq!(od.unit, None);
+        assert!(!od.comparable && !od.is_concentration());
+        assert_eq!(data.get("viability").unwrap().values, vec![95., 90.]);
+        let mu = data.get("mu").unwrap();
+        assert!((mu.values[0] - 1e-4).abs() < 1e-15);
+        assert_eq!(mu.unit, Some(Unit::PerHour));
+        assert!(mu.comparable && !mu.is_concentration());
+
+        let csv = "time,glucose [mmol/L],lactate\n0,1,2\n";
+        let data = ExperimentalData::from_csv(csv).unwrap();
+        assert!(!data.get("glucose").unwrap().is_concentration());
+        assert!(data.get("lactate").unwrap().is_concentration());
+    }
+}
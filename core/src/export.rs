@@ -0,0 +1,87 @@
+//! Labelled exports of time series.
+//!
+//! Columns are named after the species returned by [`PostProcessReader::species_names`] and
+//! headers carry the unit of each column, e.g. `glucose [g/L]`.
+use crate::api::{Phase, PostProcessReader};
+use crate::error::ApiError;
+use crate::units::{label, Quantity, Unit};
+use csv::Writer;
+use ndarray::Array1;
+use serde_json::{Map, Value};
+
+/// Units used for exported columns
+#[derive(Debug, Clone, Copy)]
+pub struct ExportUnits {
+    pub time: Unit,
+    pub concentration: Unit,
+}
+
+impl Default for ExportUnits {
+    fn default() -> Self {
+        Self {
+            time: Quantity::Time.unit(),
+            concentration: Quantity::Concentration.unit(),
+        }
+    }
+}
+
+/// Collects time and the spatial average concentration of every species, labelled with units
+fn spatial_average_concentrations<R: PostProcessReader>(
+    reader: &R,
+    phase: Phase,
+    units: ExportUnits,
+) -> Result<Vec<(String, Array1<f64>)>, ApiError> {
+    let mut columns = vec![(label("time", units.time), reader.time_in(units.time)?)];
+    let from = reader.unit(Quantity::Concentration);
+    for (i, name) in reader.species_names().into_iter().enumerate() {
+        let c = reader.get_spatial_average_concentration(i, phase)?;
+        columns.push((
+            label(&name, units.concentration),
+            from.convert_array(&c, units.concentration)?,
+        ));
+    }
+    Ok(columns)
+}
+
+/// Exports the spatial average concentration of every species as CSV.
+///
+/// # Returns
+/// * `Result<String, ApiError>` - CSV with a time column followed by one column per species.
+pub fn concentrations_to_csv<R: PostProcessReader>(
+    reader: &R,
+    phase: Phase,
+    units: ExportUnits,
+) -> Result<String, ApiError> {
+    let columns = spatial_average_concentrations(reader, phase, units)?;
+    let mut wtr = Writer::from_writer(vec![]);
+
+    let to_api_error = |e: csv::Error| ApiError::Default(e.to_string());
+    wtr.write_record(columns.iter().map(|(name, _)| name.as_str()))
+        .map_err(to_api_error)?;
+    for i in 0..reader.n_export() {
+        wtr.serialize(columns.iter().map(|(_, c)| c[i]).collect::<Vec<f64>>())
+            .map_err(to_api_error)?;
+    }
+
+    let data = wtr
+        .into_inner()
+        .map_err(|e| ApiError::Default(e.to_string()))?;
+    String::from_utf8(data).map_err(|e| ApiError::Default(e.to_string()))
+}
+
+/// Exports the spatial average concentration of every species as JSON.
+///
+/// # Returns
+/// * `Result<String, ApiError>` - JSON object mapping each labelled column to its series.
+pub fn concentrations_to_json<R: PostProcessReader>(
+    reader: &R,
+    phase: Phase,
+    units: ExportUnits,
+) -> Result<String, ApiError> {
+    let columns = spatial_average_concentrations(reader, phase, units)?;
+    let object: Map<String, Value> = columns
+        .into_iter()
+        .map(|(name, c)| (name, Value::from(c.to_vec())))
+        .collect();
+    serde_json::to_string_pretty(&object).map_err(|e| ApiError::Default(e.to_string()))
+}
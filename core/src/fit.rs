@@ -0,0 +1,392 @@
+//! Fitting of kinetic models to the time series of a run.
+//!
+//! Growth models (exponential, logistic, Gompertz) are fitted to the spatial average biomass
+//! concentration and the Monod model to the growth rate as a function of a substrate
+//! concentration, which reduces a Monte Carlo run to a few interpretable parameters.
+//! Nonlinear least squares are solved with Levenberg-Marquardt.
+use crate::api::{ModelEstimator, Phase, PostProcessReader, SpeciesKey};
+use crate::error::ApiError;
+use crate::process::linalg::{invert, solve};
+use ndarray::{Array1, Array2};
+use std::fmt;
+use std::str::FromStr;
+
+const MAX_ITERATIONS: usize = 500;
+const TOLERANCE: f64 = 1e-12;
+/// Damping above which no step is expected to decrease the residuals
+const MAX_LAMBDA: f64 = 1e12;
+
+/// Kinetic model `y = f(x, p)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// `x0 exp(mu t)`
+    Exponential,
+    /// `K / (1 + (K / x0 - 1) exp(-mu t))`
+    Logistic,
+    /// `K exp(ln(x0 / K) exp(-mu t))`
+    Gompertz,
+    /// `mu_max S / (Ks + S)`
+    Monod,
+}
+
+impl Model {
+    /// Names of the parameters, in the order of [`FitResult::parameters`]
+    pub fn parameter_names(&self) -> &'static [&'static str] {
+        match self {
+            Model::Exponential => &["x0", "mu"],
+            Model::Logistic | Model::Gompertz => &["x0", "mu", "K"],
+            Model::Monod => &["mu_max", "Ks"],
+        }
+    }
+
+    pub fn eval(&self, p: &[f64], x: f64) -> f64 {
+        match self {
+            Model::Exponential => p[0] * (p[1] * x).exp(),
+            Model::Logistic => p[2] / (1. + (p[2] / p[0] - 1.) * (-p[1] * x).exp()),
+            Model::Gompertz => p[2] * ((p[0] / p[2]).ln() * (-p[1] * x).exp()).exp(),
+            Model::Monod => p[0] * x / (p[1] + x),
+        }
+    }
+
+    /// Starting point of the optimisation from the data
+    fn initial_guess(&self, x: &[f64], y: &[f64]) -> Vec<f64> {
+        let y_max = y.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        match self {
+            Model::Exponential | Model::Logistic | Model::Gompertz => {
+                // Log-linear regression on the positive values
+                let (lx, ly): (Vec<f64>, Vec<f64>) = x
+                    .iter()
+                    .zip(y)
+                    .filter(|(_, y)| **y > 0.)
+                    .map(|(x, y)| (*x, y.ln()))
+                    .unzip();
+                let (intercept, slope) = linear_regression(&lx, &ly);
+                let x0 = if intercept.is_finite() {
+                    intercept.exp()
+                } else {
+                    y[0]
+                };
+                let mu = if slope.is_finite() { slope } else { 0. };
+                match self {
+                    Model::Exponential => vec![x0, mu],
+                    _ => vec![y[0].max(f64::MIN_POSITIVE), 2. * mu.abs(), 1.05 * y_max],
+                }
+            }
+            Model::Monod => {
+                // Ks is the substrate concentration at which the rate is half of its maximum
+                let ks = x
+                    .iter()
+                    .zip(y)
+                    .min_by(|a, b| {
+                        (a.1 - y_max / 2.)
+                            .abs()
+                            .total_cmp(&(b.1 - y_max / 2.).abs())
+                    })
+                    .map(|(x, _)| *x)
+                    .unwrap_or(1.);
+                vec![y_max, ks.max(f64::MIN_POSITIVE)]
+            }
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Model::Exponential => "exponential",
+            Model::Logistic => "logistic",
+            Model::Gompertz => "gompertz",
+            Model::Monod => "monod",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Model {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "exponential" => Ok(Model::Exponential),
+            "logistic" => Ok(Model::Logistic),
+            "gompertz" => Ok(Model::Gompertz),
+            "monod" => Ok(Model::Monod),
+            _ => Err(ApiError::Default(format!("Unknown model '{}'", s))),
+        }
+    }
+}
+
+/// Least squares line `y = intercept + slope x`, NaN if x is constant
+fn linear_regression(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let n = x.len() as f64;
+    let mx = x.iter().sum::<f64>() / n;
+    let my = y.iter().sum::<f64>() / n;
+    let sxy: f64 = x.iter().zip(y).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let sxx: f64 = x.iter().map(|x| (x - mx).powi(2)).sum();
+    let slope = sxy / sxx;
+    (my - slope * mx, slope)
+}
+
+/// Estimated parameters of a model and goodness of fit
+#[derive(Debug, Clone)]
+pub struct FitResult {
+    pub model: Model,
+    pub parameters: Vec<f64>,
+    /// Covariance of the parameters, scaled by the residual variance
+    pub covariance: Array2<f64>,
+    /// Residual sum of squares (weighted if weights are given)
+    pub rss: f64,
+    pub rmse: f64,
+    pub r2: f64,
+    /// Number of points used
+    pub n: usize,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+impl FitResult {
+    pub fn parameter(&self, name: &str) -> Option<f64> {
+        self.model
+            .parameter_names()
+            .iter()
+            .position(|n| *n == name)
+            .map(|i| self.parameters[i])
+    }
+
+    /// Standard error of each parameter
+    pub fn std_errors(&self) -> Vec<f64> {
+        self.covariance.diag().iter().map(|v| v.sqrt()).collect()
+    }
+
+    pub fn predict(&self, x: &[f64]) -> Array1<f64> {
+        x.iter()
+            .map(|x| self.model.eval(&self.parameters, *x))
+            .collect()
+    }
+}
+
+/// Fits a model to `y(x)` by weighted nonlinear least squares.
+///
+/// # Arguments
+/// * `x`, `y` - Data, points where either is not finite are ignored.
+/// * `weights` - Optional weight of each point, typically `1 / std²`.
+pub fn fit(
+    model: Model,
+    x: &[f64],
+    y: &[f64],
+    weights: Option<&[f64]>,
+) -> Result<FitResult, ApiError> {
+    if x.len() != y.len() || weights.is_some_and(|w| w.len() != x.len()) {
+        return Err(ApiError::ShapeError);
+    }
+    let keep: Vec<usize> = (0..x.len())
+        .filter(|i| x[*i].is_finite() && y[*i].is_finite())
+        .collect();
+    let x: Vec<f64> = keep.iter().map(|i| x[*i]).collect();
+    let y: Vec<f64> = keep.iter().map(|i| y[*i]).collect();
+    let w: Vec<f64> = keep.iter().map(|i| weights.map_or(1., |w| w[*i])).collect();
+    let n_param = model.parameter_names().len();
+    if x.len() <= n_param {
+        return Err(ApiError::Default(format!(
+            "Not enough points to fit the {} model: {}",
+            model,
+            x.len()
+        )));
+    }
+
+    let p0 = model.initial_guess(&x, &y);
+    let (parameters, jtj, rss, iterations, converged) = levenberg_marquardt(model, &x, &y, &w, p0);
+
+    let dof = (x.len() - n_param) as f64;
+    let covariance = invert(&jtj)
+        .map(|inv| inv * (rss / dof))
+        .unwrap_or_else(|| Array2::from_elem((n_param, n_param), f64::NAN));
+
+    let mean = y.iter().zip(&w).map(|(y, w)| w * y).sum::<f64>() / w.iter().sum::<f64>();
+    let ss_tot: f64 = y.iter().zip(&w).map(|(y, w)| w * (y - mean).powi(2)).sum();
+
+    Ok(FitResult {
+        model,
+        parameters,
+        covariance,
+        rss,
+        rmse: (rss / x.len() as f64).sqrt(),
+        r2: 1. - rss / ss_tot,
+        n: x.len(),
+        iterations,
+        converged,
+    })
+}
+
+/// Weighted residual sum of squares
+fn rss(model: Model, p: &[f64], x: &[f64], y: &[f64], w: &[f64]) -> f64 {
+    x.iter()
+        .zip(y)
+        .zip(w)
+        .map(|((x, y), w)| w * (y - model.eval(p, *x)).powi(2))
+        .sum()
+}
+
+/// Minimises the weighted residual sum of squares from `p`.
+///
+/// # Returns
+/// * Parameters, `JᵀWJ` at the optimum, residual sum of squares, iterations and convergence.
+fn levenberg_marquardt(
+    model: Model,
+    x: &[f64],
+    y: &[f64],
+    w: &[f64],
+    mut p: Vec<f64>,
+) -> (Vec<f64>, Array2<f64>, f64, usize, bool) {
+    let n_param = p.len();
+    let mut lambda = 1e-3;
+    let mut current = rss(model, &p, x, y, w);
+    let mut converged = false;
+    let mut iterations = 0;
+
+    // Normal equations with a forward difference Jacobian
+    let normal_equations = |p: &[f64]| -> (Array2<f64>, Array1<f64>) {
+        let mut jtj = Array2::zeros((n_param, n_param));
+        let mut jtr = Array1::zeros(n_param);
+        for ((x, y), w) in x.iter().zip(y).zip(w) {
+            let f = model.eval(p, *x);
+            let grad: Vec<f64> = (0..n_param)
+                .map(|k| {
+                    let h = 1e-7 * p[k].abs().max(1e-12);
+                    let mut ph = p.to_vec();
+                    ph[k] += h;
+                    (model.eval(&ph, *x) - f) / h
+                })
+                .collect();
+            for i in 0..n_param {
+                jtr[i] += w * grad[i] * (y - f);
+                for j in 0..n_param {
+                    jtj[[i, j]] += w * grad[i] * grad[j];
+                }
+            }
+        }
+        (jtj, jtr)
+    };
+
+    let (mut jtj, mut jtr) = normal_equations(&p);
+    while iterations < MAX_ITERATIONS {
+        iterations += 1;
+        let mut damped = jtj.clone();
+        for i in 0..n_param {
+            damped[[i, i]] += lambda * jtj[[i, i]].max(f64::MIN_POSITIVE);
+        }
+        let Some(step) = solve(&damped, &jtr) else {
+            lambda *= 10.;
+            if lambda > MAX_LAMBDA {
+                break;
+            }
+            continue;
+        };
+        let candidate: Vec<f64> = p.iter().zip(&step).map(|(p, s)| p + s).collect();
+        let next = rss(model, &candidate, x, y, w);
+        if next.is_finite() && next <= current {
+            let small_step = step
+                .iter()
+                .zip(&candidate)
+                .all(|(s, p)| s.abs() <= TOLERANCE.sqrt() * p.abs().max(TOLERANCE));
+            let small_decrease = current - next <= TOLERANCE * current;
+            p = candidate;
+            current = next;
+            (jtj, jtr) = normal_equations(&p);
+            lambda = (lambda / 10.).max(1e-12);
+            if small_step || small_decrease {
+                converged = true;
+                break;
+            }
+        } else {
+            lambda *= 10.;
+            // No step decreases the residuals, the fit is stuck and not converged
+            if lambda > MAX_LAMBDA {
+                converged = false;
+                break;
+            }
+        }
+    }
+    (p, jtj, current, iterations, converged)
+}
+
+/// Fits a growth model to the spatial average biomass concentration, time in seconds
+pub fn fit_biomass<R: PostProcessReader>(reader: &R, model: Model) -> Result<FitResult, ApiError> {
+    let x = reader.get_spatial_average_biomass_concentration()?;
+    fit(model, reader.time(), &x.to_vec(), None)
+}
+
+/// Fits the Monod model to [`ModelEstimator::mu_direct`] as a function of the spatial average
+/// liquid concentration of a substrate.
+pub fn fit_monod<R: PostProcessReader + ModelEstimator>(
+    reader: &R,
+    substrate: impl SpeciesKey,
+) -> Result<FitResult, ApiError> {
+    let mu = reader.mu_direct()?;
+    let s = reader.get_spatial_average_concentration(substrate, Phase::Liquid)?;
+    fit(Model::Monod, &s.to_vec(), &mu.to_vec(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(model: Model, p: &[f64], x: &[f64]) -> Vec<f64> {
+        x.iter().map(|x| model.eval(p, *x)).collect()
+    }
+
+    #[test]
+    fn test_exponential() {
+        let t: Vec<f64> = (0..20).map(|i| i as f64 * 600.).collect();
+        let y = data(Model::Exponential, &[0.5, 1e-4], &t);
+        let r = fit(Model::Exponential, &t, &y, None).unwrap();
+        assert!(r.converged);
+        assert!((r.parameter("mu").unwrap() - 1e-4).abs() < 1e-10);
+        assert!((r.r2 - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_logistic_gompertz() {
+        let t: Vec<f64> = (0..60).map(|i| i as f64 * 1000.).collect();
+        for model in [Model::Logistic, Model::Gompertz] {
+            let y = data(model, &[0.1, 2e-4, 5.], &t);
+            let r = fit(model, &t, &y, None).unwrap();
+            assert!((r.parameter("K").unwrap() - 5.).abs() < 1e-4, "{}", model);
+            assert!(
+                (r.parameter("mu").unwrap() - 2e-4).abs() < 1e-8,
+                "{}",
+                model
+            );
+        }
+    }
+
+    #[test]
+    fn test_monod() {
+        let s: Vec<f64> = (1..30).map(|i| i as f64 * 0.1).collect();
+        let noise: Vec<f64> = (0..s.len()).map(|i| 1e-6 * ((i % 3) as f64 - 1.)).collect();
+        let mu: Vec<f64> = data(Model::Monod, &[3e-4, 0.5], &s)
+            .iter()
+            .zip(&noise)
+            .map(|(m, e)| m + e)
+            .collect();
+        let r = fit(Model::Monod, &s, &mu, None).unwrap();
+        assert!((r.parameter("Ks").unwrap() - 0.5).abs() < 0.05);
+        assert!(r.std_errors().iter().all(|e| e.is_finite() && *e > 0.));
+    }
+
+    #[test]
+    fn test_stuck() {
+        // Overflowing residuals: no step is accepted and the damping keeps growing
+        let (x, y) = ([1e6, 2e6, 3e6], [1., 2., 3.]);
+        let (_, _, _, iterations, converged) =
+            levenberg_marquardt(Model::Exponential, &x, &y, &[1.; 3], vec![1., 1.]);
+        assert!(!converged);
+        assert!(iterations < MAX_ITERATIONS);
+    }
+
+    #[test]
+    fn test_not_enough_points() {
+        assert!(fit(Model::Logistic, &[0., 1.], &[1., 2.], None).is_err());
+        assert!(fit(Model::Monod, &[0., 1.], &[1.], None).is_err());
+    }
+}
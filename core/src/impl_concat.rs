@@ -1,12 +1,19 @@
-use crate::api::PostProcessReader;
+use crate::api::{ParticleTable, PostProcessReader, SpeciesKey};
 use crate::datamodel::{Weight,tallies::Tallies};
+use crate::metadata::{FinalSummary, InitialParameters, RunMetadata};
+use crate::process::filter::ParticleFilter;
+use crate::process::integrate::running_average;
 
 use crate::{api::Phase, error::ApiError, PostProcess};
-use ndarray::{Array1, Array2, ArrayView2, ArrayView3, Axis};
+use ndarray::{s, Array1, Array2, ArrayView1, ArrayView2, ArrayView3, Axis};
 
 #[derive(Debug)]
 pub struct ConcatPostPrcess {
     dataset: Vec<PostProcess>,
+    /// Export times of all runs
+    time: Vec<f64>,
+    /// Gas volumes of all runs, `None` if any run has no gas phase
+    v_gas: Option<Array2<f64>>,
 }
 
 impl ConcatPostPrcess {
@@ -19,7 +26,13 @@ impl ConcatPostPrcess {
             if dataset.is_empty() {
                 return Err(ApiError::Default("Need at least one file".to_string()));
             }
-            Ok(Self { dataset })
+            let time = dataset.iter().flat_map(|ds| ds.time().to_vec()).collect();
+            let v_gas = concatenate_v_gas(&dataset)?;
+            Ok(Self {
+                dataset,
+                time,
+                v_gas,
+            })
         } else {
             Err(ApiError::Default("Need at least one file".to_string()))
         }
@@ -40,11 +53,39 @@ impl ConcatPostPrcess {
             })
             .collect()
     }
+
+    /// Run holding the export `i_export` of the concatenation and the index of that export
+    /// within the run.
+    fn locate(&self, i_export: usize) -> Result<(&PostProcess, usize), ApiError> {
+        let mut offset = 0;
+        for postprocess in &self.dataset {
+            let n_export = postprocess.n_export();
+            if i_export < offset + n_export {
+                return Ok((postprocess, i_export - offset));
+            }
+            offset += n_export;
+        }
+        Err(ApiError::OutOfRange(i_export, offset))
+    }
+}
+
+/// Gas volumes of the runs stacked along the time axis
+fn concatenate_v_gas(dataset: &[PostProcess]) -> Result<Option<Array2<f64>>, ApiError> {
+    let mut views = Vec::with_capacity(dataset.len());
+    for postprocess in dataset {
+        match postprocess.v_gas() {
+            Some(v) => views.push(v),
+            None => return Ok(None),
+        }
+    }
+    ndarray::concatenate(Axis(0), &views)
+        .map(Some)
+        .map_err(|e| ApiError::Default(e.to_string()))
 }
 
 impl PostProcessReader for ConcatPostPrcess {
     fn time(&self) -> &[f64] {
-        todo!()
+        &self.time
     }
 
     fn v_liquid(&self) -> ArrayView2<'_, f64>
@@ -52,6 +93,10 @@ impl PostProcessReader for ConcatPostPrcess {
         todo!()
     }
 
+    fn v_gas(&self) -> Option<ArrayView2<'_, f64>> {
+        self.v_gas.as_ref().map(|v| v.view())
+    }
+
     fn get_spatial_average_property(&self, key:&str) ->  Result<Array2<f64>, ApiError>
     {
         todo!()
@@ -61,7 +106,7 @@ impl PostProcessReader for ConcatPostPrcess {
         todo!()
     }
 
-    fn get_variance_concentration(&self,species:usize,phase:Phase)-> Result<Array1<f64>, ApiError>
+    fn get_variance_concentration(&self,species:impl SpeciesKey,phase:Phase)-> Result<Array1<f64>, ApiError>
     {
         todo!()
     }
@@ -88,6 +133,10 @@ impl PostProcessReader for ConcatPostPrcess {
         self.dataset[0].get_property_names() //Names SHOULD be the same
     }
 
+    fn species_names(&self) -> Vec<String> {
+        self.dataset[0].species_names() //Names SHOULD be the same
+    }
+
     /// Concatenates the time arrays from all datasets into a single array view.
     ///
     /// # Returns
@@ -101,11 +150,19 @@ impl PostProcessReader for ConcatPostPrcess {
         Array1::from_vec(concatenated)
     }
 
+    /// One past the last export with particles, exports being numbered across the runs
     fn get_max_n_export_bio(&self) -> usize {
-        self.dataset
-            .iter()
-            .map(|postprocess| postprocess.get_max_n_export_bio())
-            .sum()
+        self.get_bio_exports().last().map_or(0, |last| last + 1)
+    }
+
+    fn get_bio_exports(&self) -> Vec<usize> {
+        let mut offset = 0;
+        let mut exports = vec![];
+        for postprocess in &self.dataset {
+            exports.extend(postprocess.get_bio_exports().iter().map(|i| i + offset));
+            offset += postprocess.n_export();
+        }
+        exports
     }
 
     fn n_export(&self) -> usize {
@@ -115,32 +172,43 @@ impl PostProcessReader for ConcatPostPrcess {
             .sum()
     }
 
-    fn get_spatial_average_concentration(&self, species: usize, phase: Phase) -> Array1<f64> {
+    fn get_spatial_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
         let mut concatenated = Array1::<f64>::default(0);
         for postprocess in &self.dataset {
-            let data = postprocess.get_spatial_average_concentration(species, phase);
+            let data = postprocess.get_spatial_average_concentration(species, phase)?;
             concatenated.append(Axis(0), data.view()).unwrap();
         }
-        concatenated
+        Ok(concatenated)
     }
 
+    /// Running average over the export times of all runs, from the first export of the first run
     fn get_time_average_concentration(
         &self,
-        species: usize,
+        species: impl SpeciesKey,
         position: usize,
         phase: Phase,
     ) -> Result<Array1<f64>, ApiError> {
-        let mut concatenated = Array1::<f64>::default(0);
+        let species = self.species_index(species)?;
+        let mut series = Vec::with_capacity(self.time.len());
         for postprocess in &self.dataset {
-            match postprocess.get_time_average_concentration(species, position, phase) {
-                Ok(data) => concatenated.append(Axis(0), data.view()).unwrap(),
-                Err(e) => return Err(e),
+            postprocess.check_phase(phase)?;
+            let concentrations = postprocess.get_concentrations(phase);
+            let n_compartment = concentrations.dim().1;
+            if position >= n_compartment {
+                return Err(ApiError::OutOfRange(position, n_compartment));
             }
+            series.extend(concentrations.slice(s![.., position, species]).iter());
         }
-        Ok(concatenated)
+        Ok(running_average(&self.time, &ArrayView1::from(&series)))
     }
 
-    fn get_spatial_average_mtr(&self, species: usize) -> Result<Array1<f64>, ApiError> {
+    fn get_spatial_average_mtr(&self, species: impl SpeciesKey) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
         let mut concatenated = Array1::<f64>::default(0);
         for postprocess in &self.dataset {
             match postprocess.get_spatial_average_mtr(species) {
@@ -189,7 +257,13 @@ impl PostProcessReader for ConcatPostPrcess {
     }
 
     fn get_properties(&self, key: &str, i_export: usize) -> Result<Array1<f64>, ApiError> {
-        todo!()
+        let (postprocess, i_export) = self.locate(i_export)?;
+        postprocess.get_properties(key, i_export)
+    }
+
+    fn get_particle_table(&self, keys: &[&str], i_export: usize) -> Result<ParticleTable, ApiError> {
+        let (postprocess, i_export) = self.locate(i_export)?;
+        postprocess.get_particle_table(keys, i_export)
     }
 
     fn get_time_population_mean(&self, key: &str) -> Result<Array1<f64>, ApiError> {
@@ -220,4 +294,51 @@ impl PostProcessReader for ConcatPostPrcess {
     fn tallies(&self) -> Option<&Tallies> {
         todo!()
     }
+    /// Initial parameters of the first run
+    fn get_initial_parameters(&self) -> InitialParameters {
+        self.dataset[0].get_initial_parameters()
+    }
+
+    /// Final summary of the last run
+    fn get_final_summary(&self) -> Option<FinalSummary> {
+        self.dataset.last().and_then(|ds| ds.get_final_summary())
+    }
+
+    /// Metadata of the last run with the exports of all runs
+    fn get_run_metadata(&self) -> RunMetadata {
+        let mut metadata = self.dataset[self.dataset.len() - 1].get_run_metadata();
+        metadata.n_export = self.n_export();
+        metadata
+    }
+
+    fn get_properties_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(Array1<f64>, f64), ApiError> {
+        let (postprocess, i_export) = self.locate(i_export)?;
+        postprocess.get_properties_filtered(key, i_export, filter)
+    }
+
+    fn get_histogram_filtered(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+        filter: &ParticleFilter,
+    ) -> Result<(Vec<f64>, Vec<f64>, f64), ApiError> {
+        let (postprocess, i_export) = self.locate(i_export)?;
+        postprocess.get_histogram_filtered(n_bins, i_export, key, filter)
+    }
+
+    fn get_population_mean_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError> {
+        let (postprocess, i_export) = self.locate(i_export)?;
+        postprocess.get_population_mean_filtered(key, i_export, filter)
+    }
 }
@@ -1,14 +1,59 @@
-use crate::api::{ModelEstimator, PostProcessReader};
-use crate::datamodel::{f_get_probes, make_histogram, read_spatial_model_properties, Results};
+use crate::api::{ModelEstimator, ParticleTable, PostProcessReader, SpeciesKey};
+use crate::datamodel::chunks::ParticleChunks;
+use crate::datamodel::ranks::RankReport;
+use crate::datamodel::recovery::{OpenMode, RecoveryReport};
+use crate::datamodel::validate::{validate_run, Diagnostic};
 use crate::datamodel::{
-    get_n_export_real, read_avg_model_properties, read_model_mass, read_model_properties,
+    f_get_probes, make_histogram, make_histogram_filtered, read_model_properties_filtered,
+    read_particle_table, read_spatial_model_properties, Property, Results, Selection,
+};
+use crate::datamodel::{
+    read_avg_model_properties, read_model_mass, read_model_properties,
     tallies::Tallies, vec_to_array_view2, vec_to_array_view3, Dim, Weight,
 };
+use crate::datamodel::{main_file_name, partial_file_name};
+use crate::metadata::{FinalSummary, InitialParameters, RunMetadata};
+use crate::process::expr::Expr;
+use crate::process::filter::{select, selected_fraction, ParticleFilter};
+use crate::process::integrate::running_average;
 use crate::process::{
     spatial_average_concentration, variance_concentration, Histogram,
 };
+use crate::view::PostProcessView;
 use crate::{api::Estimator, api::Phase, error::ApiError};
 use ndarray::{s, Array1, Array2, ArrayView2, ArrayView3, Axis};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Exports appended to a running simulation since the last refresh
+#[derive(Debug, Clone)]
+pub struct ExportUpdate {
+    /// Indices of the new exports
+    pub exports: Range<usize>,
+    /// Time of the new exports
+    pub time: Vec<f64>,
+}
+
+const DEFAULT_ROOT: &str = "./results/";
+
+/// Checks the consistency of the files of a run without opening it.
+///
+/// Shapes of records, particle counts of every rank, key sets of biological exports, tallies,
+/// probes and final particle count are checked.
+///
+/// # Arguments
+/// * `folder` - The name of the folder containing the simulation results.
+/// * `root` - Optional root directory. Defaults to "./results/" if not provided.
+///
+/// # Returns
+/// * `Vec<Diagnostic>` - Every problem found, empty if the run is consistent.
+pub fn validate(folder: &str, root: Option<String>) -> Vec<Diagnostic> {
+    let root = root.unwrap_or_else(|| DEFAULT_ROOT.to_string());
+    validate_run(&main_file_name(&root, folder), |rank| {
+        partial_file_name(&root, folder, rank)
+    })
+}
 
 /// The `PostProcess` struct handles post-processing of simulation results.
 ///
@@ -18,6 +63,8 @@ use ndarray::{s, Array1, Array2, ArrayView2, ArrayView3, Axis};
 #[derive(Debug)]
 pub struct PostProcess {
     results: Results, // The results of the simulation, which will be accessed for time and other data.
+    species_names: Vec<String>, // User-provided names, used when names are not stored in the file
+    derived: Vec<(String, Expr)>, // Derived properties, expressed over stored properties only
 }
 
 impl PostProcess {
@@ -30,10 +77,409 @@ impl PostProcess {
     /// # Returns
     /// * `Result<Self, String>` - Returns the `PostProcess` instance or an error message if initialization fails.
     pub fn new(folder: &str, root: Option<String>) -> Result<Self, ApiError> {
-        let _root = root.unwrap_or_else(|| "./results/".to_string());
-        let result_path = format!("{}/{}/{}.h5", _root, folder, folder);
-        let main = Results::new(&result_path, &_root, folder)?;
-        Ok(Self { results: main })
+        Self::open(folder, root, OpenMode::Strict)
+    }
+
+    /// Creates a new instance of `PostProcess` with the given opening mode.
+    ///
+    /// In `OpenMode::Lenient`, an incomplete run (crashed or still running) is truncated to its
+    /// last consistent export and missing partial files are skipped.
+    /// What has been dropped is available through [`PostProcess::recovery_report`].
+    ///
+    /// # Arguments
+    /// * `folder` - The name of the folder containing the simulation results.
+    /// * `root` - Optional root directory. Defaults to "./results/" if not provided.
+    /// * `mode` - Strict or lenient opening.
+    pub fn open(folder: &str, root: Option<String>, mode: OpenMode) -> Result<Self, ApiError> {
+        let _root = root.unwrap_or_else(|| DEFAULT_ROOT.to_string());
+        let result_path = main_file_name(&_root, folder);
+        let main = Results::open(&result_path, &_root, folder, mode)?;
+        Ok(Self {
+            results: main,
+            species_names: vec![],
+            derived: vec![],
+        })
+    }
+
+    /// Checks the consistency of the files of the run, see [`validate`]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let base = self.results.path.trim_end_matches(".h5");
+        validate_run(&self.results.path, |rank| {
+            format!("{}_partial_{}.h5", base, rank)
+        })
+    }
+
+    /// Sets the names of the species, used when the result file does not store them.
+    ///
+    /// # Arguments
+    /// * `names` - One name per species, ordered as the last axis of concentrations.
+    pub fn set_species_names(&mut self, names: Vec<String>) -> Result<(), ApiError> {
+        let n_species = self.results.main.records.dim.1;
+        if names.len() != n_species {
+            return Err(ApiError::Default(format!(
+                "Expected {} species names, got {}",
+                n_species,
+                names.len()
+            )));
+        }
+        self.species_names = names;
+        Ok(())
+    }
+
+    /// Registers a property derived from stored properties.
+    ///
+    /// The expression may use `+ - * / ^`, `log`, `exp`, `sqrt`, `abs` and the names of stored or
+    /// previously derived properties, e.g. `mass * nu_eff` or `log(age)`.
+    /// The derived property can then be used wherever a property key is accepted, it is evaluated
+    /// on each rank while reading.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the new property, must not already exist.
+    /// * `expression` - Expression defining the property.
+    pub fn register_property(&mut self, name: &str, expression: &str) -> Result<(), ApiError> {
+        if self.get_property_names().iter().any(|x| x == name) {
+            return Err(ApiError::Default(format!(
+                "Property '{}' already exists",
+                name
+            )));
+        }
+
+        let mut expr = Expr::parse(expression)?;
+        // Inline derived properties so that only stored datasets are read
+        for (derived_name, derived_expr) in &self.derived {
+            expr = expr.substitute(derived_name, derived_expr);
+        }
+
+        let variables = expr.variables();
+        if variables.is_empty() {
+            return Err(ApiError::ExpressionError(format!(
+                "'{}' does not depend on any property",
+                expression
+            )));
+        }
+        if let Some(unknown) = variables
+            .iter()
+            .find(|v| !self.results.property_name.contains(v))
+        {
+            return Err(ApiError::KeyError(unknown.clone()));
+        }
+
+        self.derived.push((name.to_string(), expr));
+        Ok(())
+    }
+
+    /// Resolves a property key, either stored or derived
+    fn property<'a>(&'a self, key: &'a str) -> Result<Property<'a>, ApiError> {
+        if self.results.property_name.iter().any(|x| x == key) {
+            return Ok(Property::Raw(key));
+        }
+        self.derived
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, expr)| Property::Derived(expr))
+            .ok_or_else(|| ApiError::KeyError(key.to_string()))
+    }
+
+    /// Resolves the keys of a filter
+    fn selection<'a>(&'a self, filter: &'a ParticleFilter) -> Result<Selection<'a>, ApiError> {
+        let properties = filter
+            .keys()
+            .into_iter()
+            .map(|key| Ok((key, self.property(key)?)))
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok(Selection { filter, properties })
+    }
+
+    /// Reads a property of the particles selected by a filter along with the selection mask
+    fn read_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(Array1<f64>, Vec<bool>), ApiError> {
+        if i_export >= self.results.main.records.time.len() {
+            return Err(ApiError::OutOfRange(
+                i_export,
+                self.results.main.records.time.len(),
+            ));
+        }
+        let property = self.property(key)?;
+        let selection = self.selection(filter)?;
+
+        read_model_properties_filtered(
+            &property,
+            &selection,
+            self.results.get_files(),
+            &self.results.bio_exports,
+            i_export,
+        )
+        .map_err(ApiError::Io)
+    }
+
+    /// Restricts the run to a range of exports, see [`PostProcessView`].
+    ///
+    /// # Arguments
+    /// * `exports` - Indices of the exports to keep.
+    pub fn exports(&self, exports: Range<usize>) -> Result<PostProcessView<'_>, ApiError> {
+        PostProcessView::new(self, exports)
+    }
+
+    /// Restricts the run to the exports whose time lies in `time`, start included and end
+    /// excluded, see [`PostProcessView`].
+    ///
+    /// # Arguments
+    /// * `time` - Time window in seconds.
+    pub fn view(&self, time: Range<f64>) -> Result<PostProcessView<'_>, ApiError> {
+        let t = self.time();
+        let start = t.iter().position(|x| *x >= time.start).unwrap_or(t.len());
+        let end = t
+            .iter()
+            .position(|x| *x >= time.end)
+            .unwrap_or(t.len())
+            .max(start);
+        PostProcessView::new(self, start..end)
+    }
+
+    /// Returns the recovery report if the run has been opened in lenient mode
+    pub fn recovery_report(&self) -> Option<&RecoveryReport> {
+        self.results.recovery.as_ref()
+    }
+
+    /// Streams the particles of an export by chunks of at most `chunk_size` particles, with one
+    /// column per key, see [`ParticleChunks`].
+    pub fn particle_chunks(
+        &self,
+        i_export: usize,
+        keys: &[&str],
+        chunk_size: usize,
+    ) -> Result<ParticleChunks, ApiError> {
+        let n_export_bio = self.get_max_n_export_bio();
+        if i_export >= n_export_bio {
+            return Err(ApiError::OutOfRange(i_export, n_export_bio));
+        }
+        if let Some(key) = keys
+            .iter()
+            .find(|k| !self.results.property_name.iter().any(|x| x == *k))
+        {
+            return Err(ApiError::KeyError(key.to_string()));
+        }
+        let groups = (0..self.results.files.len())
+            .map(|i_file| self.results.bio_exports.group(i_file, i_export))
+            .collect();
+        ParticleChunks::new(self.results.get_files(), groups, keys, chunk_size)
+    }
+
+    /// Reads the particle counts and storage of each rank from the partial files, see
+    /// [`RankReport`].
+    pub fn rank_report(&self) -> Result<RankReport, ApiError> {
+        RankReport::read(&self.results)
+    }
+
+    /// Initial parameters of the run by name, e.g. `delta_time` or `number_particles`, see
+    /// [`PostProcessReader::get_initial_parameters`]
+    pub fn initial_parameters(&self) -> Vec<(&'static str, f64)> {
+        self.get_initial_parameters().parameters()
+    }
+
+    /// Returns true once the simulation has written its final results
+    pub fn is_finished(&self) -> bool {
+        self.results.main.cfinal.is_some()
+    }
+
+    /// Reads the exports written since the last read of a running simulation.
+    ///
+    /// Only the new rows of the records and particle counts are read and appended in place.
+    /// Files are opened in SWMR read mode when BioMC writes them in SWMR mode (HDF5 >= 1.10).
+    /// Otherwise they are opened read-only: BioMC has to flush its files after each export and
+    /// file locking has to be disabled on the reader side (`HDF5_USE_FILE_LOCKING=FALSE`).
+    ///
+    /// # Returns
+    /// * `Result<Option<ExportUpdate>, ApiError>` - The new exports, `None` if there is nothing new.
+    pub fn refresh(&mut self) -> Result<Option<ExportUpdate>, ApiError> {
+        match self.results.refresh()? {
+            Some(exports) => {
+                let time = self.time()[exports.clone()].to_vec();
+                Ok(Some(ExportUpdate { exports, time }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Polls a running simulation and calls `on_export` each time new exports are available.
+    ///
+    /// Watching stops when the simulation has finished or when `on_export` returns `false`.
+    ///
+    /// # Arguments
+    /// * `poll` - Time to wait between two refreshes.
+    /// * `on_export` - Callback receiving the up-to-date object and the new exports.
+    pub fn watch<F>(&mut self, poll: Duration, mut on_export: F) -> Result<(), ApiError>
+    where
+        F: FnMut(&PostProcess, &ExportUpdate) -> bool,
+    {
+        loop {
+            if let Some(update) = self.refresh()? {
+                if !on_export(self, &update) {
+                    return Ok(());
+                }
+            }
+            if self.is_finished() {
+                return Ok(());
+            }
+            std::thread::sleep(poll);
+        }
+    }
+}
+
+/// Readers restricted to a range of exports, shared by [`PostProcess`] and [`PostProcessView`]
+impl PostProcess {
+    pub(crate) fn spatial_average_biomass_concentration_in(
+        &self,
+        exports: Range<usize>,
+    ) -> Result<Array1<f64>, ApiError> {
+        let vtot = self
+            .v_liquid()
+            .slice(s![exports.clone(), ..])
+            .sum_axis(Axis(1));
+
+        let mut biomass_matrix = Array1::zeros(exports.len());
+
+        for (i, i_export) in exports.enumerate() {
+            let m = self.get_properties("mass", i_export)?;
+            biomass_matrix[i] = m.sum() / vtot[i];
+        }
+
+        Ok(self.results.main.initial.initial_weight * biomass_matrix)
+    }
+
+    pub(crate) fn time_average_concentration_in(
+        &self,
+        species: usize,
+        position: usize,
+        phase: Phase,
+        exports: Range<usize>,
+    ) -> Result<Array1<f64>, ApiError> {
+        let r = &self.results.main.records;
+        let nt = r.time.len();
+        let dim = &r.dim;
+        if position >= dim.0 {
+            return Err(ApiError::OutOfRange(position, dim.0));
+        }
+
+        let callback = |c: &Vec<f64>| {
+            let cl = vec_to_array_view3(c, dim, nt);
+            running_average(
+                &r.time[exports.clone()],
+                &cl.slice(s![exports.clone(), position, species]),
+            )
+        };
+
+        match phase {
+            Phase::Liquid => Ok(callback(&r.concentration_liquid)),
+            Phase::Gas => {
+                if let Some(c) = &r.concentration_gas {
+                    return Ok(callback(c));
+                }
+
+                Err(ApiError::missing_gas())
+            }
+        }
+    }
+
+    //Actually compute spatial average property cannot be use to follow distribution
+    /// Only compartment sums are stored, a derived property is computed from the compartment means
+    /// of the stored properties. This is exact for affine expressions only, the others are
+    /// rejected with `ExpressionError`.
+    pub(crate) fn spatial_average_property_in(
+        &self,
+        key: &str,
+        exports: Range<usize>,
+    ) -> Result<Array2<f64>, ApiError> {
+        let nt: usize = exports.len(); // Number of time steps
+        let num_dimensions = self.results.main.records.dim.0; // Dimensionality
+        let number_particle = self
+            .results
+            .total_particle_repetition
+            .slice(s![exports.clone(), ..]);
+
+        let compartment_mean = |key: &str| -> Result<Array2<f64>, ApiError> {
+            // Initialize the biomass matrix
+            let mut biomass_matrix = Array2::zeros((nt, num_dimensions));
+            read_spatial_model_properties(
+                key,
+                self.results.get_files(),
+                &self.results.bio_exports,
+                &mut biomass_matrix,
+                exports.clone(),
+            )?;
+
+            // Calculate biomass concentration
+            biomass_matrix /= &number_particle;
+            Ok(biomass_matrix)
+        };
+
+        match self.property(key)? {
+            Property::Raw(key) => compartment_mean(key),
+            Property::Derived(expr) => {
+                if !expr.is_linear() {
+                    return Err(ApiError::ExpressionError(format!(
+                        "'{}' is not linear in the stored properties, its compartment mean cannot \
+                         be computed from their compartment means",
+                        key
+                    )));
+                }
+                let mut vars = HashMap::new();
+                for variable in expr.variables() {
+                    let mean = compartment_mean(&variable)?;
+                    vars.insert(variable, mean);
+                }
+                expr.eval(&vars, ndarray::Ix2(nt, num_dimensions))
+            }
+        }
+    }
+
+    pub(crate) fn biomass_concentration_in(
+        &self,
+        exports: Range<usize>,
+    ) -> Result<Array2<f64>, ApiError> {
+        let nt: usize = exports.len(); // Number of time steps
+        let num_dimensions = self.results.main.records.dim.0; // Dimensionality
+
+        // Initialize the biomass matrix
+        let mut biomass_matrix = Array2::zeros((nt, num_dimensions));
+
+        if !self.results.property_name.iter().any(|x| x == "mass") {
+            return Err(ApiError::KeyError("mass".to_string()));
+        }
+
+        // Attempt to read model mass
+        read_model_mass(
+            self.results.get_files(),
+            &self.results.bio_exports,
+            &mut biomass_matrix,
+            exports.clone(),
+        )?;
+
+        let volume = self.v_liquid();
+        let volume = volume.slice(s![exports, ..]);
+
+        // Calculate biomass concentration
+        biomass_matrix = self.results.main.initial.initial_weight * (biomass_matrix / volume);
+
+        Ok(biomass_matrix)
+    }
+
+    pub(crate) fn time_population_mean_in(
+        &self,
+        key: &str,
+        exports: Range<usize>,
+    ) -> Result<Array1<f64>, ApiError> {
+        let property = self.property(key)?;
+
+        let files = self.results.get_files();
+        match read_avg_model_properties(&property, files, &self.results.bio_exports, exports) {
+            Ok(res) => Ok(res),
+            Err(e) => Err(ApiError::Io(e)),
+        }
     }
 }
 
@@ -47,10 +493,27 @@ impl PostProcessReader for PostProcess {
     }
 
     fn get_property_names(&self) -> Vec<String> {
-        self.results.property_name.clone()
+        self.results
+            .property_name
+            .iter()
+            .chain(self.derived.iter().map(|(name, _)| name))
+            .cloned()
+            .collect()
+    }
+
+    fn species_names(&self) -> Vec<String> {
+        let records = &self.results.main.records;
+        if !records.species_names.is_empty() {
+            records.species_names.clone()
+        } else if !self.species_names.is_empty() {
+            self.species_names.clone()
+        } else {
+            (0..records.dim.1).map(|i| format!("species_{}", i)).collect()
+        }
     }
 
-    fn get_spatial_average_mtr(&self, species: usize) -> Result<Array1<f64>, ApiError> {
+    fn get_spatial_average_mtr(&self, species: impl SpeciesKey) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
         let r = &self.results.main.records;
         let nt = r.time.len();
         let dim = &r.dim;
@@ -75,11 +538,21 @@ impl PostProcessReader for PostProcess {
         vec_to_array_view2(&self.results.main.records.volume_liquid, nt, dim.0)
     }
 
+    fn v_gas(&self) -> Option<ArrayView2<'_, f64>> {
+        let records = &self.results.main.records;
+        let nt = records.time.len();
+        match (&records.concentration_gas, &records.volume_gas) {
+            (Some(_), Some(v)) => Some(vec_to_array_view2(v, nt, records.dim.0)),
+            _ => None,
+        }
+    }
+
     fn get_variance_concentration(
         &self,
-        species: usize,
+        species: impl SpeciesKey,
         phase: Phase,
     ) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
         // Helper
         fn process_phase(
             concentration: &Vec<f64>,
@@ -105,8 +578,7 @@ impl PostProcessReader for PostProcess {
                 if let (Some(c), Some(v)) = (&records.concentration_gas, &records.volume_gas) {
                     return Ok(process_phase(c, v, nt, dim, species));
                 }
-
-                panic!("Gas is not present");
+                Err(ApiError::missing_gas())
             }
             Phase::Liquid => Ok(process_phase(
                 &records.concentration_liquid,
@@ -128,12 +600,17 @@ impl PostProcessReader for PostProcess {
     }
 
     /// Retrieves the maximum number of export events specifically related to biological dumps from the simulation results.
-    /// This value represents the real number of biological export events, which may not always align with the total number of exports.
+    /// Exports are indices of the records: this is one past the last export at which a rank has
+    /// written particles, see [`crate::datamodel::bio_exports`].
     ///
     /// # Returns
-    /// * `usize` - The maximum number of biological export events, or `0` if no events are found or if an error occurs.
+    /// * `usize` - The maximum number of biological export events, or `0` if no events are found.
     fn get_max_n_export_bio(&self) -> usize {
-        get_n_export_real(self.results.get_files()).unwrap_or(0)
+        self.results.bio_exports.n_export()
+    }
+
+    fn get_bio_exports(&self) -> Vec<usize> {
+        self.results.bio_exports.exports()
     }
 
     /// Returns the total number of export events from the simulation results.
@@ -162,7 +639,12 @@ impl PostProcessReader for PostProcess {
         }
     }
 
-    fn get_spatial_average_concentration(&self, species: usize, phase: Phase) -> Array1<f64> {
+    fn get_spatial_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
         // Helper
         fn process_phase(
             concentration: &Vec<f64>,
@@ -185,83 +667,38 @@ impl PostProcessReader for PostProcess {
         match phase {
             Phase::Gas => {
                 if let (Some(c), Some(v)) = (&records.concentration_gas, &records.volume_gas) {
-                    return process_phase(c, v, nt, dim, species);
+                    return Ok(process_phase(c, v, nt, dim, species));
                 }
-
-                panic!("Gas is not present");
+                Err(ApiError::missing_gas())
             }
-            Phase::Liquid => process_phase(
+            Phase::Liquid => Ok(process_phase(
                 &records.concentration_liquid,
                 &records.volume_liquid,
                 nt,
                 dim,
                 species,
-            ),
+            )),
         }
     }
 
     fn get_spatial_average_biomass_concentration(&self) -> Result<Array1<f64>, ApiError> {
-        let num_dimensions = self.results.main.records.dim.0;
-        let nt = self.results.main.records.time.len();
-        let volume =
-            vec_to_array_view2(&self.results.main.records.volume_liquid, nt, num_dimensions);
-        let vtot = volume.sum_axis(Axis(1));
-
-        let mut biomass_matrix = Array1::zeros(nt);
-
-        for i in 0..nt {
-            let m = self.get_properties("mass", i)?;
-            biomass_matrix[i] = m.sum() / vtot[i];
-        }
-
-        Ok(self.results.main.initial.initial_weight * biomass_matrix)
+        self.spatial_average_biomass_concentration_in(0..self.n_export())
     }
 
     fn get_time_average_concentration(
         &self,
-        species: usize,
+        species: impl SpeciesKey,
         position: usize,
         phase: Phase,
     ) -> Result<Array1<f64>, ApiError> {
-        let r = &self.results.main.records;
-        let nt = r.time.len();
-        let dim = &r.dim;
-
-        let callback = |c: &Vec<f64>| {
-            let cl = vec_to_array_view3(c, dim, nt);
-            cl.slice(s![.., .., species]).mean_axis(Axis(0)).unwrap()
-        };
-
-        match phase {
-            Phase::Liquid => Ok(callback(&r.concentration_liquid)),
-            Phase::Gas => {
-                if let Some(c) = &r.concentration_gas {
-                    return Ok(callback(c));
-                }
-
-                Err(ApiError::RecordsError("Gas".to_string()))
-            }
-        }
+        let species = self.species_index(species)?;
+        self.time_average_concentration_in(species, position, phase, 0..self.n_export())
     }
 
     //Actually compute spatial average property cannot be use to follow distribution
+    /// Derived properties have to be affine in the stored properties, e.g. `2 * mass`.
     fn get_spatial_average_property(&self, key: &str) -> Result<Array2<f64>, ApiError> {
-        let nt: usize = self.results.main.records.time.len(); // Number of time steps
-        let num_dimensions = self.results.main.records.dim.0; // Dimensionality
-
-        // Initialize the biomass matrix
-        let mut biomass_matrix = Array2::zeros((nt, num_dimensions));
-
-        if !self.results.property_name.iter().any(|x| x == key) {
-            return Err(ApiError::KeyError(key.to_string()));
-        }
-
-        read_spatial_model_properties(key, self.results.get_files(), &mut biomass_matrix, nt)?;
-
-        // Calculate biomass concentration
-        biomass_matrix /= self.get_number_particle();
-
-        Ok(biomass_matrix)
+        self.spatial_average_property_in(key, 0..self.n_export())
     }
 
     fn get_probes(&self) -> Result<Array1<f64>, ApiError> {
@@ -273,27 +710,7 @@ impl PostProcessReader for PostProcess {
     /// # Returns
     /// * `Result<Array2<f64>, String>` - A 2D array with biomass concentrations or an error message.
     fn get_biomass_concentration(&self) -> Result<Array2<f64>, ApiError> {
-        let nt: usize = self.results.main.records.time.len(); // Number of time steps
-        let num_dimensions = self.results.main.records.dim.0; // Dimensionality
-
-        // Initialize the biomass matrix
-        let mut biomass_matrix = Array2::zeros((nt, num_dimensions));
-
-        if !self.results.property_name.iter().any(|x| x == "mass") {
-            return Err(ApiError::KeyError("mass".to_string()));
-        }
-
-        // Attempt to read model mass
-        read_model_mass(self.results.get_files(), &mut biomass_matrix, nt)?;
-
-        // Convert volume to an array view
-        let volume =
-            vec_to_array_view2(&self.results.main.records.volume_liquid, nt, num_dimensions);
-
-        // Calculate biomass concentration
-        biomass_matrix = self.results.main.initial.initial_weight * (biomass_matrix / volume);
-
-        Ok(biomass_matrix)
+        self.biomass_concentration_in(0..self.n_export())
     }
 
     /// Calculates the total growth in number
@@ -328,17 +745,50 @@ impl PostProcessReader for PostProcess {
             ));
         }
 
-        if !self.results.property_name.iter().any(|x| x == key) {
-            return Err(ApiError::KeyError(key.to_string()));
-        }
+        let property = self.property(key)?;
 
-        match read_model_properties(key, self.results.get_files(), i_export) {
+        let files = self.results.get_files();
+        match read_model_properties(&property, files, &self.results.bio_exports, i_export) {
             Ok(res) => Ok(res),
             // Err(e) => Err(format!("Failed to read model properties: {:?}", e)),
             Err(e) => Err(ApiError::Io(e)),
         }
     }
 
+    /// Reads several properties of the particles at a given export, each partial file being
+    /// opened once.
+    ///
+    /// # Arguments
+    /// * `keys` - The property keys to fetch, one column each.
+    /// * `i_export` - The index of the export to retrieve.
+    ///
+    /// # Returns
+    /// * `Result<ParticleTable, ApiError>` - The columns with the rank and local index of each
+    ///   particle.
+    fn get_particle_table(
+        &self,
+        keys: &[&str],
+        i_export: usize,
+    ) -> Result<ParticleTable, ApiError> {
+        if i_export >= self.results.main.records.time.len() {
+            return Err(ApiError::OutOfRange(
+                i_export,
+                self.results.main.records.time.len(),
+            ));
+        }
+        let properties = keys
+            .iter()
+            .map(|key| self.property(key))
+            .collect::<Result<Vec<_>, ApiError>>()?;
+        Ok(read_particle_table(
+            keys,
+            &properties,
+            self.results.get_files(),
+            &self.results.bio_exports,
+            i_export,
+        )?)
+    }
+
     /// Calculates the population mean over time for a given property key.
     ///
     /// # Arguments
@@ -347,14 +797,7 @@ impl PostProcessReader for PostProcess {
     /// # Returns
     /// * `Result<Array1<f64>, String>` - A 1D array of mean values over time or an error message.
     fn get_time_population_mean(&self, key: &str) -> Result<Array1<f64>, ApiError> {
-        if !self.results.property_name.iter().any(|x| x == key) {
-            return Err(ApiError::KeyError(key.to_string()));
-        }
-
-        match read_avg_model_properties(key, self.results.get_files(), self.n_export()) {
-            Ok(res) => Ok(res),
-            Err(e) => Err(ApiError::Io(e)),
-        }
+        self.time_population_mean_in(key, 0..self.n_export())
     }
 
     fn get_histogram_array(
@@ -375,16 +818,20 @@ impl PostProcessReader for PostProcess {
         i_export: usize,
         key: &str,
     ) -> Result<(Vec<f64>, Vec<f64>), ApiError> {
-        if i_export > self.n_export() {
+        if i_export >= self.n_export() {
             return Err(ApiError::OutOfRange(i_export, self.n_export()));
         }
-        if !self.results.property_name.iter().any(|x| x == key) {
-            return Err(ApiError::KeyError(key.to_string()));
-        }
+        let property = self.property(key)?;
         // let np = n_bins;//*self.results.total_particle_repetition.sum_axis(Axis(1)).last().unwrap() as usize;
         let mut hist = Histogram::new(n_bins);
 
-        if let Err(e) = make_histogram(self.results.get_files(), i_export, key, &mut hist) {
+        if let Err(e) = make_histogram(
+            self.results.get_files(),
+            &self.results.bio_exports,
+            i_export,
+            &property,
+            &mut hist,
+        ) {
             return Err(ApiError::Io(e));
         }
 
@@ -401,11 +848,10 @@ impl PostProcessReader for PostProcess {
                 self.results.main.records.time.len(),
             ));
         }
-        if !self.results.property_name.iter().any(|x| x == key) {
-            return Err(ApiError::KeyError(key.to_string()));
-        }
+        let property = self.property(key)?;
 
-        match read_model_properties(key, self.results.get_files(), i_export) {
+        let files = self.results.get_files();
+        match read_model_properties(&property, files, &self.results.bio_exports, i_export) {
             Ok(res) => res
                 .mean()
                 .ok_or(ApiError::Default("get_population_mean".to_string())),
@@ -415,37 +861,82 @@ impl PostProcessReader for PostProcess {
     fn tallies(&self) -> Option<&Tallies> {
         self.results.main.records.tallies.as_ref()
     }
+
+    fn get_initial_parameters(&self) -> InitialParameters {
+        InitialParameters::from(&self.results.main.initial)
+    }
+
+    fn get_final_summary(&self) -> Option<FinalSummary> {
+        self.results.main.cfinal.as_ref().map(FinalSummary::from)
+    }
+
+    fn get_run_metadata(&self) -> RunMetadata {
+        let main = &self.results.main;
+        RunMetadata {
+            n_node_thread: main.misc.n_node_thread,
+            n_rank: main.misc.n_rank,
+            n_export: self.n_export(),
+            n_compartment: main.records.dim.0,
+            species_names: self.species_names(),
+            finished: self.is_finished(),
+        }
+    }
+
+    fn get_properties_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(Array1<f64>, f64), ApiError> {
+        let (values, mask) = self.read_filtered(key, i_export, filter)?;
+        Ok((values, selected_fraction(&mask)))
+    }
+
+    fn get_histogram_filtered(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+        filter: &ParticleFilter,
+    ) -> Result<(Vec<f64>, Vec<f64>, f64), ApiError> {
+        if i_export >= self.n_export() {
+            return Err(ApiError::OutOfRange(i_export, self.n_export()));
+        }
+        let property = self.property(key)?;
+        let selection = self.selection(filter)?;
+        let mut hist = Histogram::new(n_bins);
+
+        let (n_selected, n_total) = make_histogram_filtered(
+            self.results.get_files(),
+            &self.results.bio_exports,
+            i_export,
+            &property,
+            &selection,
+            &mut hist,
+        )?;
+
+        let fraction = if n_total == 0 {
+            0.
+        } else {
+            n_selected as f64 / n_total as f64
+        };
+        Ok((hist.get_bins().to_vec(), hist.get_counts().to_vec(), fraction))
+    }
+
+    fn get_population_mean_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError> {
+        let (values, mask) = self.read_filtered(key, i_export, filter)?;
+        Ok((values.mean().unwrap_or(f64::NAN), selected_fraction(&mask)))
+    }
 }
 
 impl ModelEstimator for PostProcess {
     fn mu_direct(&self) -> Result<Array1<f64>, ApiError> {
-        match self.weight() {
-            Weight::Single(_) => {
-                let nt = self.results.main.records.time.len();
-                let time = self.time();
-
-                let mu_functor = |i: usize, j: usize, im: usize| -> Result<f64, ApiError> {
-                    let mass_i = self.get_properties("mass", i)?;
-                    let mass_mi = self.get_properties("mass", j)?;
-                    let mass_tot_i = self.get_properties("mass", im)?.sum();
-                    let dm: f64 = mass_i.sum() - mass_mi.sum();
-                    let dt = time[i] - time[j];
-                    Ok(dm / dt / mass_tot_i)
-                };
-
-                let mut mu = Array1::zeros(nt);
-
-                mu[0] = mu_functor(1, 0, 0)?; //Forward
-
-                for i in 1..nt - 1 {
-                    mu[i] = mu_functor(i + 1, i - 1, i)?; //Center
-                }
-                mu[nt - 1] = mu_functor(nt - 1, nt - 2, nt - 1)?; //Backward
-
-                Ok(mu)
-            }
-            Weight::Multiple(_) => todo!("Mu with different weight"),
-        }
+        mu_direct(self)
     }
 
     fn estimate(&self, etype: Estimator, key: &str, i_export: usize) -> Result<f64, ApiError> {
@@ -460,4 +951,69 @@ impl ModelEstimator for PostProcess {
         }
         Ok(estimator)
     }
+
+    fn estimate_filtered(
+        &self,
+        etype: Estimator,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError> {
+        let (values, mask) = self.read_filtered(key, i_export, filter)?;
+        let weight = match self.weight() {
+            Weight::Single(sw) => Weight::Single(*sw),
+            Weight::Multiple(mw) => {
+                if mw.len() != mask.len() {
+                    return Err(ApiError::ShapeError);
+                }
+                Weight::Multiple(select(mw, &mask))
+            }
+        };
+        let estimate = crate::process::estimate(etype, &weight, &values)?;
+        Ok((estimate, selected_fraction(&mask)))
+    }
+}
+
+/// Total mass represented by the particles at each export, `Σ mass·weight`, each export being
+/// read once.
+///
+/// With one weight per particle, an export whose number of particles differs from the number of
+/// weights is a [`ApiError::ShapeError`].
+pub(crate) fn total_mass<R: PostProcessReader + ?Sized>(
+    reader: &R,
+) -> Result<Array1<f64>, ApiError> {
+    (0..reader.n_export())
+        .map(|i| {
+            let mass = reader.get_properties("mass", i)?;
+            match reader.weight() {
+                Weight::Single(sw) => Ok(mass.sum() * sw),
+                Weight::Multiple(mw) => {
+                    if mw.len() != mass.len() {
+                        return Err(ApiError::ShapeError);
+                    }
+                    Ok(mass.iter().zip(mw).map(|(m, w)| m * w).sum())
+                }
+            }
+        })
+        .collect()
+}
+
+/// Growth rate from the variation of the total particle mass between exports, with centered
+/// differences inside the range and one-sided ones at its bounds.
+pub(crate) fn mu_direct<R: PostProcessReader>(reader: &R) -> Result<Array1<f64>, ApiError> {
+    let nt = reader.n_export();
+    if nt < 2 {
+        return Err(ApiError::OutOfRange(1, nt));
+    }
+    let time = reader.time();
+    let mass = total_mass(reader)?;
+    let rate = |i: usize, j: usize, im: usize| (mass[i] - mass[j]) / (time[i] - time[j]) / mass[im];
+
+    let mut mu = Array1::zeros(nt);
+    mu[0] = rate(1, 0, 0); //Forward
+    for i in 1..nt - 1 {
+        mu[i] = rate(i + 1, i - 1, i); //Center
+    }
+    mu[nt - 1] = rate(nt - 1, nt - 2, nt - 1); //Backward
+    Ok(mu)
 }
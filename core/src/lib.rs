@@ -1,13 +1,41 @@
 pub mod error;
 pub mod api;
+pub mod compare;
 mod datamodel;
+pub mod ensemble;
+pub mod experimental;
+pub mod export;
+pub mod fit;
+pub mod metadata;
 mod impl_concat;
 mod impl_unique;
 mod process;
+pub mod study;
+pub mod units;
+mod view;
+pub mod zoning;
 
 pub use api::PostProcessReader;
+pub use datamodel::chunks::{ParticleChunk, ParticleChunks};
+pub use datamodel::ranks::RankReport;
+pub use datamodel::recovery::{OpenMode, RecoveryReport};
+pub use datamodel::validate::{Diagnostic, Severity};
+pub use datamodel::tallies::{TallyColumn, TallyTable};
 pub use datamodel::Weight;
+pub use ensemble::Ensemble;
 pub use impl_concat::ConcatPostPrcess;
-pub use impl_unique::PostProcess;
+pub use impl_unique::{validate, ExportUpdate, PostProcess};
+pub use metadata::{FinalSummary, InitialParameters, RunMetadata};
+pub use process::filter::{Comparison, ParticleFilter};
+pub use process::growth::GrowthRate;
+pub use process::integrate::Rule;
+pub use process::interpolate::{uniform_grid, Method, Resampled};
+pub use process::reduce::{Binned, MinMax, Reducer, Sum, Welford};
+pub use process::spectral::{autocorrelation, autocorrelation_time, periodogram, Periodogram};
+pub use process::stats::Band;
+pub use process::steady::{Detector, SteadyState};
+pub use process::Histogram;
+pub use study::{Kpi, Study};
+pub use view::PostProcessView;
 
 
@@ -0,0 +1,135 @@
+//! Run configuration and final results read from the main file.
+//!
+//! These are plain serde structures so that scripts can store or compare the configuration of
+//! runs without opening the HDF5 files themselves.
+//!
+//! # Example
+//! ```ignore
+//! let initial = pp.get_initial_parameters();
+//! println!("{} particles, dt = {} s", initial.number_particles, initial.delta_time);
+//! println!("{}", pp.get_run_metadata().to_json()?);
+//! ```
+use crate::datamodel::main_file::{MainFInal, MainInitial};
+use crate::error::ApiError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Parameters of the simulation at its start, group `initial_parameters` of the main file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitialParameters {
+    /// Time step in seconds
+    pub delta_time: f64,
+    /// Simulated time in seconds
+    pub final_time: f64,
+    /// Initial biomass concentration in kg/m3
+    pub initial_biomass_concentration: f64,
+    /// Weight of the particles
+    pub initial_weight: f64,
+    /// Number of flow maps of the hydrodynamic cycle
+    pub n_map: usize,
+    pub number_compartment: usize,
+    pub number_particles: u64,
+    /// Duration of each flow map in seconds
+    pub t_per_flow_map: f64,
+}
+
+impl InitialParameters {
+    /// Scalar parameters by name, in declaration order
+    pub fn parameters(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("delta_time", self.delta_time),
+            ("final_time", self.final_time),
+            (
+                "initial_biomass_concentration",
+                self.initial_biomass_concentration,
+            ),
+            ("initial_weight", self.initial_weight),
+            ("n_map", self.n_map as f64),
+            ("number_compartment", self.number_compartment as f64),
+            ("number_particles", self.number_particles as f64),
+            ("t_per_flow_map", self.t_per_flow_map),
+        ]
+    }
+}
+
+impl From<&MainInitial> for InitialParameters {
+    fn from(initial: &MainInitial) -> Self {
+        Self {
+            delta_time: initial.delta_time,
+            final_time: initial.final_time,
+            initial_biomass_concentration: initial.initial_biomass_concentration,
+            initial_weight: initial.initial_weight,
+            n_map: initial.n_map,
+            number_compartment: initial.number_compartment,
+            number_particles: initial.number_particles,
+            t_per_flow_map: initial.t_per_flow_map,
+        }
+    }
+}
+
+/// Results written once the simulation has ended, group `final_result` of the main file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinalSummary {
+    /// Total count of each event type, empty if the file holds no event
+    pub events: BTreeMap<String, u64>,
+    /// Number of particles at the end of the simulation
+    pub number_particles: u64,
+}
+
+impl From<&MainFInal> for FinalSummary {
+    fn from(cfinal: &MainFInal) -> Self {
+        Self {
+            events: cfinal
+                .events
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            number_particles: cfinal.number_particles,
+        }
+    }
+}
+
+/// Description of a run: parallel layout, shapes of the records and state of the simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// Threads per node
+    pub n_node_thread: u64,
+    /// Number of MPI ranks, i.e. of partial files
+    pub n_rank: u64,
+    pub n_export: usize,
+    pub n_compartment: usize,
+    pub species_names: Vec<String>,
+    /// True once the simulation has written its final results
+    pub finished: bool,
+}
+
+impl RunMetadata {
+    pub fn to_json(&self) -> Result<String, ApiError> {
+        serde_json::to_string_pretty(self).map_err(|e| ApiError::Default(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_final_summary() {
+        let cfinal = MainFInal {
+            events: Some(HashMap::from([
+                ("new_particle".to_string(), 12),
+                ("death".to_string(), 3),
+            ])),
+            number_particles: 109,
+        };
+        let summary = FinalSummary::from(&cfinal);
+        assert_eq!(
+            summary.events.keys().collect::<Vec<_>>(),
+            vec!["death", "new_particle"]
+        );
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(serde_json::from_str::<FinalSummary>(&json).unwrap(), summary);
+    }
+}
@@ -0,0 +1,437 @@
+//! Arithmetic expressions over particle properties.
+//!
+//! Grammar, from lowest to highest precedence:
+//! ```text
+//! expr   := term (('+' | '-') term)*
+//! term   := unary (('*' | '/') unary)*
+//! unary  := '-' unary | power
+//! power  := atom ('^' unary)?
+//! atom   := number | name | func '(' expr ')' | '(' expr ')'
+//! func   := log | exp | sqrt | abs
+//! ```
+//! Names refer to properties and are evaluated element-wise, e.g. `mass * nu_eff` or `log(age)`.
+use crate::error::ApiError;
+use ndarray::{Array, Dimension, Zip};
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Func {
+    Log,
+    Exp,
+    Sqrt,
+    Abs,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "log" | "ln" => Some(Func::Log),
+            "exp" => Some(Func::Exp),
+            "sqrt" => Some(Func::Sqrt),
+            "abs" => Some(Func::Abs),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Func::Log => x.ln(),
+            Func::Exp => x.exp(),
+            Func::Sqrt => x.sqrt(),
+            Func::Abs => x.abs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Func, Box<Expr>),
+}
+
+impl Expr {
+    pub fn parse(input: &str) -> Result<Self, ApiError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(t) => Err(ApiError::ExpressionError(format!(
+                "unexpected '{}' in '{}'",
+                t, input
+            ))),
+        }
+    }
+
+    /// Names of the properties the expression depends on
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables(&self, names: &mut BTreeSet<String>) {
+        match self {
+            Expr::Number(_) => {}
+            Expr::Var(name) => {
+                names.insert(name.clone());
+            }
+            Expr::Neg(e) | Expr::Call(_, e) => e.collect_variables(names),
+            Expr::Binary(_, l, r) => {
+                l.collect_variables(names);
+                r.collect_variables(names);
+            }
+        }
+    }
+
+    /// True if the expression is affine in its variables, e.g. `2 * mass + 1`.
+    ///
+    /// Means commute with affine expressions only: the mean of `mass * nu_eff` is not the product
+    /// of the means.
+    pub fn is_linear(&self) -> bool {
+        let constant = |e: &Expr| e.variables().is_empty();
+        match self {
+            Expr::Number(_) | Expr::Var(_) => true,
+            Expr::Neg(e) => e.is_linear(),
+            Expr::Call(_, e) => constant(e),
+            Expr::Binary(op, l, r) => match op {
+                BinaryOp::Add | BinaryOp::Sub => l.is_linear() && r.is_linear(),
+                BinaryOp::Mul => {
+                    (constant(l) && r.is_linear()) || (l.is_linear() && constant(r))
+                }
+                BinaryOp::Div => l.is_linear() && constant(r),
+                BinaryOp::Pow => constant(l) && constant(r),
+            },
+        }
+    }
+
+    /// Replaces every occurrence of the variable `name` by `value`
+    pub fn substitute(&self, name: &str, value: &Expr) -> Expr {
+        match self {
+            Expr::Var(v) if v == name => value.clone(),
+            Expr::Number(_) | Expr::Var(_) => self.clone(),
+            Expr::Neg(e) => Expr::Neg(Box::new(e.substitute(name, value))),
+            Expr::Call(f, e) => Expr::Call(*f, Box::new(e.substitute(name, value))),
+            Expr::Binary(op, l, r) => Expr::Binary(
+                *op,
+                Box::new(l.substitute(name, value)),
+                Box::new(r.substitute(name, value)),
+            ),
+        }
+    }
+
+    /// Evaluates the expression element-wise.
+    ///
+    /// # Arguments
+    /// * `vars` - Value of each variable, all arrays must have the shape `shape`.
+    /// * `shape` - Shape of the result, used to broadcast constants.
+    pub fn eval<D: Dimension>(
+        &self,
+        vars: &HashMap<String, Array<f64, D>>,
+        shape: D,
+    ) -> Result<Array<f64, D>, ApiError> {
+        match self {
+            Expr::Number(v) => Ok(Array::from_elem(shape, *v)),
+            Expr::Var(name) => {
+                let v = vars
+                    .get(name)
+                    .ok_or_else(|| ApiError::KeyError(name.clone()))?;
+                if v.raw_dim() != shape {
+                    return Err(ApiError::ShapeError);
+                }
+                Ok(v.clone())
+            }
+            Expr::Neg(e) => Ok(-e.eval(vars, shape)?),
+            Expr::Call(f, e) => Ok(e.eval(vars, shape)?.mapv_into(|x| f.apply(x))),
+            Expr::Binary(op, l, r) => {
+                let mut l = l.eval(vars, shape.clone())?;
+                let r = r.eval(vars, shape)?;
+                Zip::from(&mut l).and(&r).for_each(|a, &b| {
+                    *a = match op {
+                        BinaryOp::Add => *a + b,
+                        BinaryOp::Sub => *a - b,
+                        BinaryOp::Mul => *a * b,
+                        BinaryOp::Div => *a / b,
+                        BinaryOp::Pow => a.powf(b),
+                    }
+                });
+                Ok(l)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(v) => write!(f, "{}", v),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Op(c) => write!(f, "{}", c),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ApiError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Exponent, e.g. 1e-3
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let literal: String = chars[start..i].iter().collect();
+            let value = literal
+                .parse::<f64>()
+                .map_err(|_| ApiError::ExpressionError(format!("invalid number '{}'", literal)))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if "+-*/^".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else {
+            return Err(ApiError::ExpressionError(format!(
+                "unexpected character '{}' in '{}'",
+                c, input
+            )));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn eat_op(&mut self, ops: &str) -> Option<char> {
+        match self.peek() {
+            Some(Token::Op(c)) if ops.contains(*c) => {
+                let c = *c;
+                self.pos += 1;
+                Some(c)
+            }
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ApiError> {
+        let mut lhs = self.term()?;
+        while let Some(op) = self.eat_op("+-") {
+            let rhs = self.term()?;
+            let op = if op == '+' {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, ApiError> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.eat_op("*/") {
+            let rhs = self.unary()?;
+            let op = if op == '*' {
+                BinaryOp::Mul
+            } else {
+                BinaryOp::Div
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ApiError> {
+        if self.eat_op("-").is_some() {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, ApiError> {
+        let base = self.atom()?;
+        if self.eat_op("^").is_some() {
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, ApiError> {
+        match self.next() {
+            Some(Token::Number(v)) => Ok(Expr::Number(v)),
+            Some(Token::Ident(name)) => {
+                if self.peek() == Some(&Token::LParen) {
+                    let func = Func::from_name(&name).ok_or_else(|| {
+                        ApiError::ExpressionError(format!("unknown function '{}'", name))
+                    })?;
+                    self.pos += 1;
+                    let arg = self.expr()?;
+                    self.close_paren()?;
+                    Ok(Expr::Call(func, Box::new(arg)))
+                } else {
+                    Ok(Expr::Var(name))
+                }
+            }
+            Some(Token::LParen) => {
+                let e = self.expr()?;
+                self.close_paren()?;
+                Ok(e)
+            }
+            Some(t) => Err(ApiError::ExpressionError(format!("unexpected '{}'", t))),
+            None => Err(ApiError::ExpressionError(
+                "unexpected end of expression".to_string(),
+            )),
+        }
+    }
+
+    fn close_paren(&mut self) -> Result<(), ApiError> {
+        match self.next() {
+            Some(Token::RParen) => Ok(()),
+            _ => Err(ApiError::ExpressionError("missing ')'".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{array, Array1};
+
+    fn vars() -> HashMap<String, Array1<f64>> {
+        HashMap::from([
+            ("mass".to_string(), array![1., 2., 4.]),
+            ("nu_eff".to_string(), array![0.5, 1., 2.]),
+        ])
+    }
+
+    fn eval(input: &str) -> Array1<f64> {
+        Expr::parse(input)
+            .unwrap()
+            .eval(&vars(), ndarray::Dim([3]))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), array![7., 7., 7.]);
+        assert_eq!(eval("(1 + 2) * 3"), array![9., 9., 9.]);
+        assert_eq!(eval("-2^2"), array![-4., -4., -4.]);
+        assert_eq!(eval("2^3^2"), array![512., 512., 512.]);
+        assert_eq!(eval("1e-1 * 10"), array![1., 1., 1.]);
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(eval("mass * nu_eff"), array![0.5, 2., 8.]);
+        assert_eq!(eval("sqrt(mass)"), array![1., 2f64.sqrt(), 2.]);
+        let names = Expr::parse("log(mass) / nu_eff + mass")
+            .unwrap()
+            .variables();
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            vec!["mass", "nu_eff"]
+        );
+    }
+
+    #[test]
+    fn test_linear() {
+        let linear = |input: &str| Expr::parse(input).unwrap().is_linear();
+        assert!(linear("mass"));
+        assert!(linear("2 * mass - nu_eff / 3 + log(2)"));
+        assert!(linear("-(mass + 1) * 2"));
+        assert!(!linear("mass * nu_eff"));
+        assert!(!linear("log(age)"));
+        assert!(!linear("1 / mass"));
+        assert!(!linear("mass^2"));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let e = Expr::parse("2 * x")
+            .unwrap()
+            .substitute("x", &Expr::parse("mass + 1").unwrap());
+        assert_eq!(
+            e.eval(&vars(), ndarray::Dim([3])).unwrap(),
+            array![4., 6., 10.]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Expr::parse("mass *").is_err());
+        assert!(Expr::parse("(mass").is_err());
+        assert!(Expr::parse("foo(mass)").is_err());
+        assert!(Expr::parse("mass $ 2").is_err());
+        let missing = Expr::parse("age").unwrap().eval(&vars(), ndarray::Dim([3]));
+        assert!(matches!(missing, Err(ApiError::KeyError(_))));
+    }
+}
@@ -0,0 +1,225 @@
+//! Selection of particles by predicates on their properties.
+use crate::error::ApiError;
+use ndarray::Array1;
+use std::collections::{BTreeSet, HashMap};
+
+/// Comparison between a particle property and a threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn test(&self, x: f64, value: f64) -> bool {
+        match self {
+            Comparison::Lt => x < value,
+            Comparison::Le => x <= value,
+            Comparison::Gt => x > value,
+            Comparison::Ge => x >= value,
+            Comparison::Eq => x == value,
+            Comparison::Ne => x != value,
+        }
+    }
+}
+
+impl std::str::FromStr for Comparison {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "<" => Ok(Comparison::Lt),
+            "<=" => Ok(Comparison::Le),
+            ">" => Ok(Comparison::Gt),
+            ">=" => Ok(Comparison::Ge),
+            "==" => Ok(Comparison::Eq),
+            "!=" => Ok(Comparison::Ne),
+            _ => Err(ApiError::Default(format!("Unknown comparison '{}'", s))),
+        }
+    }
+}
+
+/// Predicate selecting particles from their properties.
+///
+/// Predicates are evaluated on the properties of each particle at the same export, properties
+/// being aligned datasets of a rank.
+///
+/// # Example
+/// ```ignore
+/// let old = ParticleFilter::gt("age", 3600.);
+/// let slow_old = old.and(ParticleFilter::lt("nu_meta", 0.5));
+/// let (mean, fraction) = pp.get_population_mean_filtered("nu_eff", 10, &slow_old)?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParticleFilter {
+    Compare {
+        key: String,
+        op: Comparison,
+        value: f64,
+    },
+    And(Box<ParticleFilter>, Box<ParticleFilter>),
+    Or(Box<ParticleFilter>, Box<ParticleFilter>),
+    Not(Box<ParticleFilter>),
+}
+
+impl ParticleFilter {
+    pub fn compare(key: &str, op: Comparison, value: f64) -> Self {
+        ParticleFilter::Compare {
+            key: key.to_string(),
+            op,
+            value,
+        }
+    }
+
+    pub fn lt(key: &str, value: f64) -> Self {
+        Self::compare(key, Comparison::Lt, value)
+    }
+
+    pub fn le(key: &str, value: f64) -> Self {
+        Self::compare(key, Comparison::Le, value)
+    }
+
+    pub fn gt(key: &str, value: f64) -> Self {
+        Self::compare(key, Comparison::Gt, value)
+    }
+
+    pub fn ge(key: &str, value: f64) -> Self {
+        Self::compare(key, Comparison::Ge, value)
+    }
+
+    pub fn and(self, other: ParticleFilter) -> Self {
+        ParticleFilter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: ParticleFilter) -> Self {
+        ParticleFilter::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        ParticleFilter::Not(Box::new(self))
+    }
+
+    /// Names of the properties the filter depends on
+    pub fn keys(&self) -> BTreeSet<&str> {
+        let mut keys = BTreeSet::new();
+        self.collect_keys(&mut keys);
+        keys
+    }
+
+    fn collect_keys<'a>(&'a self, keys: &mut BTreeSet<&'a str>) {
+        match self {
+            ParticleFilter::Compare { key, .. } => {
+                keys.insert(key);
+            }
+            ParticleFilter::And(l, r) | ParticleFilter::Or(l, r) => {
+                l.collect_keys(keys);
+                r.collect_keys(keys);
+            }
+            ParticleFilter::Not(f) => f.collect_keys(keys),
+        }
+    }
+
+    /// Evaluates the filter on every particle.
+    ///
+    /// # Arguments
+    /// * `values` - Properties of the particles, every key of the filter must be present and all
+    ///   arrays must have the same length.
+    ///
+    /// # Returns
+    /// * `Result<Vec<bool>, ApiError>` - `true` for selected particles.
+    pub fn mask(&self, values: &HashMap<String, Array1<f64>>) -> Result<Vec<bool>, ApiError> {
+        match self {
+            ParticleFilter::Compare { key, op, value } => {
+                let x = values
+                    .get(key)
+                    .ok_or_else(|| ApiError::KeyError(key.clone()))?;
+                Ok(x.iter().map(|x| op.test(*x, *value)).collect())
+            }
+            ParticleFilter::And(l, r) | ParticleFilter::Or(l, r) => {
+                let l = l.mask(values)?;
+                let r = r.mask(values)?;
+                if l.len() != r.len() {
+                    return Err(ApiError::ShapeError);
+                }
+                let is_and = matches!(self, ParticleFilter::And(..));
+                Ok(l.iter()
+                    .zip(&r)
+                    .map(|(a, b)| if is_and { *a && *b } else { *a || *b })
+                    .collect())
+            }
+            ParticleFilter::Not(f) => Ok(f.mask(values)?.iter().map(|m| !m).collect()),
+        }
+    }
+}
+
+/// Keeps the values selected by `mask`
+pub fn select(values: &[f64], mask: &[bool]) -> Vec<f64> {
+    values
+        .iter()
+        .zip(mask)
+        .filter(|(_, m)| **m)
+        .map(|(v, _)| *v)
+        .collect()
+}
+
+/// Fraction of selected particles, 0 if there is no particle
+pub fn selected_fraction(mask: &[bool]) -> f64 {
+    if mask.is_empty() {
+        return 0.;
+    }
+    mask.iter().filter(|m| **m).count() as f64 / mask.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn values() -> HashMap<String, Array1<f64>> {
+        HashMap::from([
+            ("age".to_string(), array![100., 4000., 5000., 10.]),
+            ("nu_meta".to_string(), array![0.1, 0.9, 0.2, 0.3]),
+        ])
+    }
+
+    #[test]
+    fn test_compare() {
+        let mask = ParticleFilter::gt("age", 3600.).mask(&values()).unwrap();
+        assert_eq!(mask, vec![false, true, true, false]);
+        assert_eq!(selected_fraction(&mask), 0.5);
+    }
+
+    #[test]
+    fn test_combine() {
+        let old = ParticleFilter::gt("age", 3600.);
+        let slow = ParticleFilter::lt("nu_meta", 0.5);
+        let v = values();
+        assert_eq!(
+            old.clone().and(slow.clone()).mask(&v).unwrap(),
+            vec![false, false, true, false]
+        );
+        assert_eq!(
+            old.clone().or(slow).mask(&v).unwrap(),
+            vec![true, true, true, true]
+        );
+        assert_eq!(old.not().mask(&v).unwrap(), vec![true, false, false, true]);
+    }
+
+    #[test]
+    fn test_select() {
+        let mask = vec![true, false, true];
+        assert_eq!(select(&[1., 2., 3.], &mask), vec![1., 3.]);
+        assert_eq!(selected_fraction(&[]), 0.);
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let f = ParticleFilter::gt("mass", 1.);
+        assert!(matches!(f.mask(&values()), Err(ApiError::KeyError(_))));
+    }
+}
@@ -0,0 +1,163 @@
+//! Growth rate estimators with standard errors.
+//!
+//! Rates are obtained by local polynomial regression over a sliding window of exports, which
+//! smooths the Monte Carlo noise and handles irregular export times.
+use crate::process::linalg::{invert, solve};
+use ndarray::{Array1, Array2};
+
+/// Growth rate and its standard error at each export
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrowthRate {
+    pub mu: Array1<f64>,
+    /// NaN where the window has no degree of freedom left
+    pub std_error: Array1<f64>,
+}
+
+/// Least squares polynomial of degree `order` in `t - t[i]` over the window centered on `i`.
+///
+/// The window is shifted at the bounds so that it always holds `window` points. Times are
+/// divided by the half-span of the window before building the powers, otherwise the normal
+/// equations are too ill-conditioned for the solver as soon as hourly exports are in seconds.
+///
+/// # Returns
+/// * Coefficients (value, first derivative, ...) at `t[i]` and their covariance, `None` if the
+///   system is singular.
+fn local_polynomial(
+    t: &[f64],
+    y: &[f64],
+    i: usize,
+    window: usize,
+    order: usize,
+) -> Option<(Array1<f64>, Array2<f64>)> {
+    let n = t.len();
+    let window = window.min(n);
+    let start = i.saturating_sub(window / 2).min(n - window);
+    let range = start..start + window;
+    let n_coef = order + 1;
+    let h = range
+        .clone()
+        .map(|k| (t[k] - t[i]).abs())
+        .fold(0., f64::max);
+    let h = if h > 0. { h } else { 1. };
+
+    let mut xtx = Array2::zeros((n_coef, n_coef));
+    let mut xty = Array1::zeros(n_coef);
+    for k in range.clone() {
+        let dt = (t[k] - t[i]) / h;
+        let powers: Vec<f64> = (0..n_coef).map(|p| dt.powi(p as i32)).collect();
+        for a in 0..n_coef {
+            xty[a] += powers[a] * y[k];
+            for b in 0..n_coef {
+                xtx[[a, b]] += powers[a] * powers[b];
+            }
+        }
+    }
+    let coef = solve(&xtx, &xty)?;
+
+    let rss: f64 = range
+        .map(|k| {
+            let dt = (t[k] - t[i]) / h;
+            let fit: f64 = (0..n_coef).map(|p| coef[p] * dt.powi(p as i32)).sum();
+            (y[k] - fit).powi(2)
+        })
+        .sum();
+    let dof = window as f64 - n_coef as f64;
+    let variance = if dof > 0. { rss / dof } else { f64::NAN };
+    let mut covariance = invert(&xtx)? * variance;
+
+    // Back to the coefficients in `t - t[i]`
+    let coef = Array1::from_iter((0..n_coef).map(|p| coef[p] / h.powi(p as i32)));
+    for ((p, q), c) in covariance.indexed_iter_mut() {
+        *c /= h.powi((p + q) as i32);
+    }
+    Some((coef, covariance))
+}
+
+/// Specific rate `d ln(y) / dt` from a line fitted to `ln(y)` over a sliding window
+pub fn log_linear_rate(t: &[f64], y: &[f64], window: usize) -> GrowthRate {
+    let log_y: Vec<f64> = y.iter().map(|y| y.ln()).collect();
+    let mut mu = Array1::from_elem(t.len(), f64::NAN);
+    let mut std_error = Array1::from_elem(t.len(), f64::NAN);
+    for i in 0..t.len() {
+        if let Some((coef, cov)) = local_polynomial(t, &log_y, i, window, 1) {
+            mu[i] = coef[1];
+            std_error[i] = cov[[1, 1]].sqrt();
+        }
+    }
+    GrowthRate { mu, std_error }
+}
+
+/// Specific rate `y' / y` from a Savitzky-Golay polynomial of degree `order` fitted to `y` over a
+/// sliding window, the standard error is propagated with the delta method.
+pub fn savitzky_golay_rate(t: &[f64], y: &[f64], window: usize, order: usize) -> GrowthRate {
+    let mut mu = Array1::from_elem(t.len(), f64::NAN);
+    let mut std_error = Array1::from_elem(t.len(), f64::NAN);
+    for i in 0..t.len() {
+        if let Some((coef, cov)) = local_polynomial(t, y, i, window, order.max(1)) {
+            let (y0, y1) = (coef[0], coef[1]);
+            mu[i] = y1 / y0;
+            // Gradient of y1 / y0 with respect to (y0, y1)
+            let g = [-y1 / y0.powi(2), 1. / y0];
+            let variance = g[0] * g[0] * cov[[0, 0]]
+                + g[1] * g[1] * cov[[1, 1]]
+                + 2. * g[0] * g[1] * cov[[0, 1]];
+            std_error[i] = variance.sqrt();
+        }
+    }
+    GrowthRate { mu, std_error }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exponential(mu: f64) -> (Vec<f64>, Vec<f64>) {
+        // Irregular export times
+        let t: Vec<f64> = (0..30)
+            .map(|i| i as f64 * 100. + (i % 3) as f64 * 7.)
+            .collect();
+        let y = t.iter().map(|t| 2. * (mu * t).exp()).collect();
+        (t, y)
+    }
+
+    #[test]
+    fn test_log_linear() {
+        let (t, y) = exponential(3e-4);
+        let r = log_linear_rate(&t, &y, 5);
+        assert!(r.mu.iter().all(|m| (m - 3e-4).abs() < 1e-12));
+        assert!(r.std_error.iter().all(|e| *e < 1e-10));
+    }
+
+    #[test]
+    fn test_savitzky_golay() {
+        let (t, y) = exponential(1e-4);
+        let r = savitzky_golay_rate(&t, &y, 7, 3);
+        assert!(r.mu.iter().all(|m| (m - 1e-4).abs() < 1e-7));
+    }
+
+    #[test]
+    fn test_savitzky_golay_hourly() {
+        // Hourly exports, the raw powers of the times are out of reach of the solver
+        let t: Vec<f64> = (0..30).map(|i| i as f64 * 3600.).collect();
+        let y: Vec<f64> = t.iter().map(|t| 2. * (1e-5 * t).exp()).collect();
+        let r = savitzky_golay_rate(&t, &y, 7, 3);
+        assert!(r.mu.iter().all(|m| (m - 1e-5).abs() < 1e-8));
+        let r = savitzky_golay_rate(&t, &y, 9, 4);
+        assert!(r.mu.iter().all(|m| (m - 1e-5).abs() < 1e-8));
+        assert!(r.std_error.iter().all(|e| e.is_finite()));
+    }
+
+    #[test]
+    fn test_standard_error() {
+        let t: Vec<f64> = (0..20).map(|i| i as f64).collect();
+        let y: Vec<f64> = t
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (0.1 * t + if i % 2 == 0 { 0.01 } else { -0.01 }).exp())
+            .collect();
+        let r = log_linear_rate(&t, &y, 5);
+        assert!(r.std_error.iter().all(|e| *e > 0. && e.is_finite()));
+        // No degree of freedom left
+        assert!(log_linear_rate(&t[..2], &y[..2], 5).std_error[0].is_nan());
+    }
+}
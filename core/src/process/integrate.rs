@@ -0,0 +1,153 @@
+//! Integration of time series over the non-uniform export times.
+//!
+//! Exports are not evenly spaced (restarts, adaptive export frequency), every rule takes the time
+//! of each sample into account.
+use crate::error::ApiError;
+use crate::process::interpolate::bin_average;
+use ndarray::{Array1, ArrayView1};
+use std::str::FromStr;
+
+/// Quadrature rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Trapezoid,
+    /// Composite Simpson rule on non-uniform intervals, exact for quadratics
+    Simpson,
+}
+
+impl FromStr for Rule {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "trapezoid" | "trapz" => Ok(Rule::Trapezoid),
+            "simpson" => Ok(Rule::Simpson),
+            _ => Err(ApiError::Default(format!(
+                "Unknown integration rule '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Integral of `y(t)` over `[t[0], t[n - 1]]`
+pub fn integrate(t: &[f64], y: &ArrayView1<f64>, rule: Rule) -> f64 {
+    match rule {
+        Rule::Trapezoid => trapezoid(t, y),
+        Rule::Simpson => simpson(t, y),
+    }
+}
+
+/// Integral of `y(t)` with the trapezoidal rule
+pub fn trapezoid(t: &[f64], y: &ArrayView1<f64>) -> f64 {
+    (1..t.len())
+        .map(|i| 0.5 * (y[i - 1] + y[i]) * (t[i] - t[i - 1]))
+        .sum()
+}
+
+/// Integral of `y(t)` with the composite Simpson rule.
+///
+/// Intervals are taken by pairs, an odd last interval is integrated with the parabola through
+/// the last three samples. Pairs with an empty interval fall back to the trapezoidal rule.
+pub fn simpson(t: &[f64], y: &ArrayView1<f64>) -> f64 {
+    let n = t.len();
+    if n < 3 {
+        return trapezoid(t, y);
+    }
+    let mut sum = 0.;
+    let mut i = 0;
+    while i + 2 < n {
+        let (h0, h1) = (t[i + 1] - t[i], t[i + 2] - t[i + 1]);
+        if h0 > 0. && h1 > 0. {
+            let h = h0 + h1;
+            sum += h / 6.
+                * ((2. - h1 / h0) * y[i]
+                    + h * h / (h0 * h1) * y[i + 1]
+                    + (2. - h0 / h1) * y[i + 2]);
+        } else {
+            sum += trapezoid(&t[i..i + 3], &y.slice(ndarray::s![i..i + 3]));
+        }
+        i += 2;
+    }
+    if i + 2 == n {
+        // Odd number of intervals
+        let (h0, h1) = (t[n - 2] - t[n - 3], t[n - 1] - t[n - 2]);
+        if h0 > 0. && h1 > 0. {
+            let alpha = (2. * h1 * h1 + 3. * h0 * h1) / (6. * (h0 + h1));
+            let beta = (h1 * h1 + 3. * h0 * h1) / (6. * h0);
+            let eta = h1 * h1 * h1 / (6. * h0 * (h0 + h1));
+            sum += alpha * y[n - 1] + beta * y[n - 2] - eta * y[n - 3];
+        } else {
+            sum += 0.5 * (y[n - 2] + y[n - 1]) * h1;
+        }
+    }
+    sum
+}
+
+/// Integral of `y(t)` from `t[0]` to each sample with the trapezoidal rule, starts at 0
+pub fn cumulative_trapezoid(t: &[f64], y: &ArrayView1<f64>) -> Array1<f64> {
+    let mut acc = 0.;
+    let mut result = Array1::zeros(t.len());
+    for i in 1..t.len() {
+        acc += 0.5 * (y[i - 1] + y[i]) * (t[i] - t[i - 1]);
+        result[i] = acc;
+    }
+    result
+}
+
+/// Average of `y(t)` from `t[0]` to each sample, `y[0]` until time has elapsed
+pub fn running_average(t: &[f64], y: &ArrayView1<f64>) -> Array1<f64> {
+    let integral = cumulative_trapezoid(t, y);
+    (0..t.len())
+        .map(|i| {
+            let dt = t[i] - t[0];
+            if dt > 0. {
+                integral[i] / dt
+            } else {
+                y[0]
+            }
+        })
+        .collect()
+}
+
+/// Time average of `y(t)` over `[start, end]` restricted to the sampled range, NaN without overlap
+pub fn window_average(t: &[f64], y: &ArrayView1<f64>, start: f64, end: f64) -> f64 {
+    bin_average(t, y, &[start, end])[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{array, Array1};
+
+    #[test]
+    fn test_trapezoid() {
+        let t = [0., 1., 3.];
+        let y = array![0., 1., 3.];
+        assert_eq!(trapezoid(&t, &y.view()), 4.5);
+        assert_eq!(cumulative_trapezoid(&t, &y.view()), array![0., 0.5, 4.5]);
+        assert_eq!(running_average(&t, &y.view()), array![0., 0.5, 1.5]);
+        assert_eq!(trapezoid(&[1.], &array![2.].view()), 0.);
+    }
+
+    #[test]
+    fn test_simpson() {
+        // Exact for quadratics on non-uniform grids, with even and odd number of intervals
+        for t in [vec![0., 0.5, 1.5, 2., 3.], vec![0., 0.2, 1., 1.7, 2.5, 3.]] {
+            let y: Array1<f64> = t.iter().map(|x| x * x - x + 1.).collect();
+            let exact = 9. - 4.5 + 3.;
+            assert!((simpson(&t, &y.view()) - exact).abs() < 1e-12);
+        }
+        assert_eq!(simpson(&[0., 2.], &array![1., 3.].view()), 4.);
+        assert_eq!("simpson".parse::<Rule>().unwrap(), Rule::Simpson);
+    }
+
+    #[test]
+    fn test_window_average() {
+        let t = [0., 1., 2.];
+        let y = array![0., 2., 2.];
+        assert_eq!(window_average(&t, &y.view(), 0., 2.), 1.5);
+        assert_eq!(window_average(&t, &y.view(), 1., 10.), 2.);
+        assert!(window_average(&t, &y.view(), 3., 4.).is_nan());
+    }
+}
@@ -0,0 +1,314 @@
+//! Interpolation of time series onto another time grid.
+//!
+//! Series are resampled along their first axis, see [`resample_axis`]. With
+//! [`Method::BinAverage`] the grid holds the edges of the bins and the series is averaged over
+//! each bin, the new time of each value is the center of its bin.
+use crate::error::ApiError;
+use ndarray::{Array, Array1, ArrayView, ArrayView1, Axis, Dimension, RemoveAxis, Zip};
+use std::str::FromStr;
+
+/// How a series is evaluated between its samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Linear,
+    /// Value of the last sample, for piecewise-constant series
+    Previous,
+    /// Piecewise cubic that preserves monotonicity (PCHIP), no overshoot between samples
+    MonotoneCubic,
+    /// Time average of the linear interpolant over each bin
+    BinAverage,
+}
+
+impl FromStr for Method {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "linear" => Ok(Method::Linear),
+            "previous" => Ok(Method::Previous),
+            "cubic" | "monotone_cubic" | "pchip" => Ok(Method::MonotoneCubic),
+            "bin_average" | "mean" => Ok(Method::BinAverage),
+            _ => Err(ApiError::Default(format!("Unknown interpolation '{}'", s))),
+        }
+    }
+}
+
+/// Series resampled onto a new grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resampled<D: Dimension> {
+    /// Time of each value, the bin centers with [`Method::BinAverage`]
+    pub time: Array1<f64>,
+    /// Values along the first axis, the other axes are unchanged
+    pub values: Array<f64, D>,
+}
+
+/// Linear interpolation of `y(x)` at `x_new`, `x` must be increasing.
+///
+/// Values outside `[x[0], x[n - 1]]` are NaN, no extrapolation is done.
+pub fn linear(x: &[f64], y: &ArrayView1<f64>, x_new: &[f64]) -> Array1<f64> {
+    x_new
+        .iter()
+        .map(|xi| match bracket(x, *xi) {
+            Some((i, 0.)) => y[i],
+            Some((i, w)) => y[i] * (1. - w) + y[i + 1] * w,
+            None => f64::NAN,
+        })
+        .collect()
+}
+
+/// Value of the last sample at or before each point of `x_new`, NaN outside `[x[0], x[n - 1]]`.
+pub fn previous(x: &[f64], y: &ArrayView1<f64>, x_new: &[f64]) -> Array1<f64> {
+    x_new
+        .iter()
+        .map(|xi| match bracket(x, *xi) {
+            Some((i, _)) => y[i],
+            None => f64::NAN,
+        })
+        .collect()
+}
+
+/// Monotone piecewise cubic interpolation (Fritsch-Carlson), NaN outside `[x[0], x[n - 1]]`.
+///
+/// Between two samples the interpolant stays within their values, so that positive or
+/// cumulative series keep their sign and their monotonicity.
+pub fn monotone_cubic(x: &[f64], y: &ArrayView1<f64>, x_new: &[f64]) -> Array1<f64> {
+    let d = pchip_slopes(x, y);
+    x_new
+        .iter()
+        .map(|xi| match bracket(x, *xi) {
+            Some((i, 0.)) => y[i],
+            Some((i, t)) => {
+                let h = x[i + 1] - x[i];
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (2. * t3 - 3. * t2 + 1.) * y[i]
+                    + (t3 - 2. * t2 + t) * h * d[i]
+                    + (-2. * t3 + 3. * t2) * y[i + 1]
+                    + (t3 - t2) * h * d[i + 1]
+            }
+            None => f64::NAN,
+        })
+        .collect()
+}
+
+/// Derivative at each sample: weighted harmonic mean of the neighbouring secants, zero at
+/// local extrema
+fn pchip_slopes(x: &[f64], y: &ArrayView1<f64>) -> Vec<f64> {
+    let n = x.len();
+    let secant = |i: usize| {
+        let h = x[i + 1] - x[i];
+        if h > 0. {
+            (y[i + 1] - y[i]) / h
+        } else {
+            0.
+        }
+    };
+    let mut d = vec![0.; n];
+    if n < 2 {
+        return d;
+    }
+    d[0] = secant(0);
+    d[n - 1] = secant(n - 2);
+    for i in 1..n - 1 {
+        let (s0, s1) = (secant(i - 1), secant(i));
+        if s0 * s1 <= 0. {
+            continue;
+        }
+        let (h0, h1) = (x[i] - x[i - 1], x[i + 1] - x[i]);
+        let (w0, w1) = (2. * h1 + h0, h1 + 2. * h0);
+        d[i] = (w0 + w1) / (w0 / s0 + w1 / s1);
+    }
+    d
+}
+
+/// Time average of the linear interpolant of `y(x)` over each bin `[edges[i], edges[i + 1]]`.
+///
+/// Bins partially outside `[x[0], x[n - 1]]` are averaged over their overlap, bins without
+/// overlap are NaN.
+pub fn bin_average(x: &[f64], y: &ArrayView1<f64>, edges: &[f64]) -> Array1<f64> {
+    let (Some(x0), Some(x1)) = (x.first(), x.last()) else {
+        return Array1::from_elem(edges.len().saturating_sub(1), f64::NAN);
+    };
+    edges
+        .windows(2)
+        .map(|bin| {
+            let (a, b) = (bin[0].max(*x0), bin[1].min(*x1));
+            if b <= a {
+                return f64::NAN;
+            }
+            // The interpolant is linear between the samples, the trapezoidal rule is exact
+            let mut t = vec![a];
+            t.extend(x.iter().copied().filter(|xi| *xi > a && *xi < b));
+            t.push(b);
+            let v = linear(x, y, &t);
+            let area: f64 = t
+                .windows(2)
+                .zip(v.windows(2))
+                .map(|(t, v)| 0.5 * (v[0] + v[1]) * (t[1] - t[0]))
+                .sum();
+            area / (b - a)
+        })
+        .collect()
+}
+
+/// Resamples `y(x)` onto `grid`, the bin edges with [`Method::BinAverage`].
+pub fn resample(x: &[f64], y: &ArrayView1<f64>, grid: &[f64], method: Method) -> Array1<f64> {
+    match method {
+        Method::Linear => linear(x, y, grid),
+        Method::Previous => previous(x, y, grid),
+        Method::MonotoneCubic => monotone_cubic(x, y, grid),
+        Method::BinAverage => bin_average(x, y, grid),
+    }
+}
+
+/// Resamples every series along the first axis of `y`, see [`resample`].
+pub fn resample_axis<D: Dimension + RemoveAxis>(
+    x: &[f64],
+    y: &ArrayView<f64, D>,
+    grid: &[f64],
+    method: Method,
+) -> Resampled<D> {
+    let time = resampled_time(grid, method);
+    let mut shape = y.raw_dim();
+    shape[0] = time.len();
+    let mut values = Array::zeros(shape);
+    Zip::from(values.lanes_mut(Axis(0)))
+        .and(y.lanes(Axis(0)))
+        .for_each(|mut out, series| out.assign(&resample(x, &series, grid, method)));
+    Resampled { time, values }
+}
+
+/// Time of the values resampled onto `grid`
+pub fn resampled_time(grid: &[f64], method: Method) -> Array1<f64> {
+    match method {
+        Method::BinAverage => grid.windows(2).map(|b| 0.5 * (b[0] + b[1])).collect(),
+        _ => Array1::from_vec(grid.to_vec()),
+    }
+}
+
+/// Points from `start` to `end` (included when reached) every `step`.
+///
+/// Points are clamped to `end`, which rounding could otherwise exceed when it is reached.
+pub fn uniform_grid(start: f64, end: f64, step: f64) -> Vec<f64> {
+    if step.is_nan() || step <= 0. || end < start {
+        return vec![];
+    }
+    let n = ((end - start) / step + 1e-9).floor() as usize;
+    (0..=n).map(|i| (start + i as f64 * step).min(end)).collect()
+}
+
+/// Index `i` and weight `w` such that `xi = x[i] * (1 - w) + x[i + 1] * w`
+fn bracket(x: &[f64], xi: f64) -> Option<(usize, f64)> {
+    let n = x.len();
+    if n == 0 || xi.is_nan() || xi < x[0] || xi > x[n - 1] {
+        return None;
+    }
+    let i = x.partition_point(|v| *v <= xi).saturating_sub(1);
+    if i + 1 >= n || x[i + 1] == x[i] {
+        return Some((i, 0.));
+    }
+    Some((i, (xi - x[i]) / (x[i + 1] - x[i])))
+}
+
+/// Sorted union of two grids restricted to their overlap, points closer than `tolerance` are
+/// merged.
+pub fn common_grid(a: &[f64], b: &[f64], tolerance: f64) -> Vec<f64> {
+    let (Some(a0), Some(a1), Some(b0), Some(b1)) = (a.first(), a.last(), b.first(), b.last())
+    else {
+        return vec![];
+    };
+    let start = a0.max(*b0);
+    let end = a1.min(*b1);
+    let mut grid: Vec<f64> = a
+        .iter()
+        .chain(b)
+        .copied()
+        .filter(|t| *t >= start && *t <= end)
+        .collect();
+    grid.sort_by(f64::total_cmp);
+    grid.dedup_by(|x, y| (*x - *y).abs() <= tolerance);
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_linear() {
+        let x = [0., 1., 3.];
+        let y = array![0., 2., 6.];
+        let r = linear(&x, &y.view(), &[0., 0.5, 1., 2., 3.]);
+        assert_eq!(r, array![0., 1., 2., 4., 6.]);
+        assert!(linear(&x, &y.view(), &[-1.])[0].is_nan());
+        assert!(linear(&x, &y.view(), &[3.5])[0].is_nan());
+    }
+
+    #[test]
+    fn test_common_grid() {
+        let a = [0., 1., 2., 3.];
+        let b = [0.5, 1., 2.5, 4.];
+        assert_eq!(common_grid(&a, &b, 1e-9), vec![0.5, 1., 2., 2.5, 3.]);
+        assert!(common_grid(&a, &[], 1e-9).is_empty());
+    }
+
+    #[test]
+    fn test_uniform_grid() {
+        assert_eq!(uniform_grid(0., 0.3, 0.1).last(), Some(&0.3));
+        assert_eq!(uniform_grid(0., 1., 0.4), vec![0., 0.4, 0.8]);
+        assert!(uniform_grid(1., 0., 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_previous() {
+        let x = [0., 1., 3.];
+        let y = array![1., 2., 6.];
+        let r = previous(&x, &y.view(), &[0., 0.9, 1., 2.9, 3.]);
+        assert_eq!(r, array![1., 1., 2., 2., 6.]);
+        assert!(previous(&x, &y.view(), &[3.5])[0].is_nan());
+    }
+
+    #[test]
+    fn test_monotone_cubic() {
+        let x = [0., 1., 2., 3., 4.];
+        let y = array![0., 0., 1., 1., 1.];
+        let grid: Vec<f64> = (0..=40).map(|i| i as f64 * 0.1).collect();
+        let r = monotone_cubic(&x, &y.view(), &grid);
+        // Samples are interpolated and the step does not overshoot
+        assert_eq!(r[10], 0.);
+        assert_eq!(r[20], 1.);
+        assert!(r.windows(2).into_iter().all(|w| w[1] >= w[0] - 1e-12));
+        assert!(r.iter().all(|v| (0. ..=1.).contains(v)));
+        // Exact on lines
+        let line = array![1., 3., 5., 7., 9.];
+        let r = monotone_cubic(&x, &line.view(), &[0.5, 2.25]);
+        assert!((r[0] - 2.).abs() < 1e-12 && (r[1] - 5.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bin_average() {
+        let x = [0., 1., 2.];
+        let y = array![0., 2., 2.];
+        let r = bin_average(&x, &y.view(), &[0., 1., 2., 3.]);
+        assert_eq!(r.slice(ndarray::s![..2]), array![1., 2.]);
+        // No overlap with the samples
+        assert!(r[2].is_nan());
+        // Averaged over the overlap only
+        assert_eq!(bin_average(&x, &y.view(), &[1.5, 3.]), array![2.]);
+        assert_eq!(bin_average(&x, &y.view(), &[0., 2.]), array![1.5]);
+    }
+
+    #[test]
+    fn test_resample_axis() {
+        let x = [0., 2.];
+        let y = array![[0., 10.], [2., 30.]];
+        let r = resample_axis(&x, &y.view(), &[0., 1., 2.], Method::Linear);
+        assert_eq!(r.values, array![[0., 10.], [1., 20.], [2., 30.]]);
+        let r = resample_axis(&x, &y.view(), &[0., 2.], Method::BinAverage);
+        assert_eq!(r.time, array![1.]);
+        assert_eq!(r.values, array![[1., 20.]]);
+        assert_eq!(uniform_grid(0., 1., 0.25), vec![0., 0.25, 0.5, 0.75, 1.]);
+        assert_eq!("pchip".parse::<Method>().unwrap(), Method::MonotoneCubic);
+    }
+}
@@ -0,0 +1,70 @@
+//! Dense linear algebra for the small systems of least squares problems.
+use ndarray::{Array1, Array2};
+
+/// Gauss-Jordan elimination with partial pivoting of `a x = b` for every column of `b`.
+///
+/// Returns `None` if `a` is singular.
+fn gauss_jordan(mut a: Array2<f64>, mut b: Array2<f64>) -> Option<Array2<f64>> {
+    let n = a.nrows();
+    let scale = a.iter().fold(0., |m: f64, x| m.max(x.abs()));
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[[*i, col]].abs().total_cmp(&a[[*j, col]].abs()))?;
+        if a[[pivot, col]].abs() <= f64::EPSILON * scale * n as f64 {
+            return None;
+        }
+        for k in 0..n {
+            a.swap([col, k], [pivot, k]);
+        }
+        for k in 0..b.ncols() {
+            b.swap([col, k], [pivot, k]);
+        }
+        let d = a[[col, col]];
+        a.row_mut(col).mapv_inplace(|x| x / d);
+        b.row_mut(col).mapv_inplace(|x| x / d);
+        for row in 0..n {
+            if row != col {
+                let f = a[[row, col]];
+                if f != 0. {
+                    let a_col = a.row(col).to_owned();
+                    let b_col = b.row(col).to_owned();
+                    a.row_mut(row).scaled_add(-f, &a_col);
+                    b.row_mut(row).scaled_add(-f, &b_col);
+                }
+            }
+        }
+    }
+    Some(b)
+}
+
+/// Solves `a x = b` for a square matrix `a`, `None` if `a` is singular
+pub fn solve(a: &Array2<f64>, b: &Array1<f64>) -> Option<Array1<f64>> {
+    let b = b.clone().insert_axis(ndarray::Axis(1));
+    gauss_jordan(a.clone(), b).map(|x| x.column(0).to_owned())
+}
+
+/// Inverse of a square matrix, `None` if it is singular
+pub fn invert(a: &Array2<f64>) -> Option<Array2<f64>> {
+    gauss_jordan(a.clone(), Array2::eye(a.nrows()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_solve() {
+        let a = array![[0., 2.], [1., 1.]];
+        let x = solve(&a, &array![4., 3.]).unwrap();
+        assert_eq!(x, array![1., 2.]);
+        assert!(solve(&array![[1., 2.], [2., 4.]], &array![1., 1.]).is_none());
+    }
+
+    #[test]
+    fn test_invert() {
+        let a = array![[4., 7.], [2., 6.]];
+        let inv = invert(&a).unwrap();
+        let id = a.dot(&inv);
+        assert!((id - Array2::<f64>::eye(2)).iter().all(|x| x.abs() < 1e-12));
+    }
+}
@@ -1,3 +1,14 @@
+pub mod expr;
+pub mod filter;
+pub mod growth;
+pub mod integrate;
+pub mod interpolate;
+pub mod linalg;
+pub mod reduce;
+pub mod spectral;
+pub mod stats;
+pub mod steady;
+
 use crate::api::Estimator;
 use crate::error::ApiError;
 use crate::Weight;
@@ -53,6 +64,8 @@ pub struct Histogram {
     bins: Vec<f64>,
     counts: Vec<f64>,
     bin_counts: usize,
+    /// Range set by [`Histogram::with_range`], shared by every call to `add`
+    range: Option<(f64, f64)>,
 }
 
 impl Histogram {
@@ -61,17 +74,31 @@ impl Histogram {
             bins: Vec::new(),
             counts: Vec::new(),
             bin_counts,
+            range: None,
         }
     }
 
+    /// Histogram with bins fixed over `[min, max]`, values outside the range are counted in the
+    /// first or last bin.
+    ///
+    /// Without a range, each call to `add` bins its values over their own range.
+    pub fn with_range(bin_counts: usize, min: f64, max: f64) -> Self {
+        let mut hist = Self::new(bin_counts);
+        hist.range = Some((min, max));
+        hist
+    }
+
     pub fn add(&mut self, values: Vec<f64>) {
         if values.is_empty() {
             return;
         }
 
         // Determine the range of the data
-        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
-        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let (min, max) = self.range.unwrap_or_else(|| {
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            (min, max)
+        });
         let bin_width = (max - min) / self.bin_counts as f64;
 
         // Initialize bins if not already done
@@ -84,7 +111,7 @@ impl Histogram {
 
         // Add values to the appropriate bins
         for value in values {
-            let bin_index = ((value - min) / bin_width).floor() as usize;
+            let bin_index = ((value - min) / bin_width).floor().max(0.) as usize;
             let bin_index = bin_index.min(self.bin_counts - 1); // Clamp to the last bin if necessary
             self.counts[bin_index] += 1.0;
         }
@@ -134,3 +161,24 @@ pub(crate) fn estimate(
         Estimator::Weighted => Ok(weighted_estimator),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_range() {
+        // Without a range every batch is binned over its own range
+        let mut h = Histogram::new(2);
+        h.add(vec![0., 1., 2.]);
+        h.add(vec![10., 20.]);
+        assert_eq!(h.get_bins(), &[0., 1.]);
+        assert_eq!(h.get_counts(), &[2., 3.]);
+
+        let mut h = Histogram::with_range(2, 0., 2.);
+        h.add(vec![0.5, 1.5]);
+        h.add(vec![-1., 10.]);
+        assert_eq!(h.get_bins(), &[0., 1.]);
+        assert_eq!(h.get_counts(), &[2., 2.]);
+    }
+}
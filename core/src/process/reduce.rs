@@ -0,0 +1,220 @@
+//! Fold-style reducers over streamed values.
+//!
+//! Reducers are updated chunk by chunk and hold a constant amount of memory, partial reducers
+//! (e.g. one per rank) can be combined with `merge`.
+use crate::error::ApiError;
+use crate::process::Histogram;
+use ndarray::ArrayView1;
+
+/// Accumulates values chunk by chunk
+pub trait Reducer {
+    fn update(&mut self, values: ArrayView1<f64>);
+}
+
+/// Sum and count of the values
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Sum {
+    pub sum: f64,
+    pub n: usize,
+}
+
+impl Reducer for Sum {
+    fn update(&mut self, values: ArrayView1<f64>) {
+        self.sum += values.sum();
+        self.n += values.len();
+    }
+}
+
+impl Sum {
+    pub fn merge(&mut self, other: &Self) {
+        self.sum += other.sum;
+        self.n += other.n;
+    }
+}
+
+/// Extrema of the values, NaN values are ignored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinMax {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for MinMax {
+    fn default() -> Self {
+        Self {
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Reducer for MinMax {
+    fn update(&mut self, values: ArrayView1<f64>) {
+        for v in values.iter().filter(|v| !v.is_nan()) {
+            self.min = self.min.min(*v);
+            self.max = self.max.max(*v);
+        }
+    }
+}
+
+impl MinMax {
+    pub fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// Mean and variance with Welford's online algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Welford {
+    pub n: usize,
+    pub mean: f64,
+    /// Sum of squared deviations from the mean
+    pub m2: f64,
+}
+
+impl Reducer for Welford {
+    fn update(&mut self, values: ArrayView1<f64>) {
+        for v in values {
+            self.n += 1;
+            let delta = v - self.mean;
+            self.mean += delta / self.n as f64;
+            self.m2 += delta * (v - self.mean);
+        }
+    }
+}
+
+impl Welford {
+    pub fn mean(&self) -> f64 {
+        if self.n == 0 {
+            f64::NAN
+        } else {
+            self.mean
+        }
+    }
+
+    /// Population variance
+    pub fn variance(&self) -> f64 {
+        if self.n == 0 {
+            f64::NAN
+        } else {
+            self.m2 / self.n as f64
+        }
+    }
+
+    /// Unbiased sample variance
+    pub fn sample_variance(&self) -> f64 {
+        if self.n < 2 {
+            f64::NAN
+        } else {
+            self.m2 / (self.n - 1) as f64
+        }
+    }
+
+    pub fn std(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Combines two partial results (Chan et al.)
+    pub fn merge(&mut self, other: &Self) {
+        if other.n == 0 {
+            return;
+        }
+        let n = self.n + other.n;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.n as f64 / n as f64;
+        self.m2 += other.m2 + delta * delta * (self.n * other.n) as f64 / n as f64;
+        self.n = n;
+    }
+}
+
+/// Counts of values in bins fixed over a range.
+///
+/// The range must be known before the first chunk, e.g. from a [`MinMax`] pre-pass, so that
+/// every chunk is counted in the same bins. Values outside the range are counted in the first or
+/// last bin.
+pub struct Binned(Histogram);
+
+impl Binned {
+    pub fn new(n_bins: usize, min: f64, max: f64) -> Result<Self, ApiError> {
+        if n_bins == 0 || !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(ApiError::Default(format!(
+                "Invalid histogram range [{}, {}] with {} bins",
+                min, max, n_bins
+            )));
+        }
+        Ok(Self(Histogram::with_range(n_bins, min, max)))
+    }
+
+    /// Bins over the extrema found by a previous pass
+    pub fn from_min_max(n_bins: usize, range: &MinMax) -> Result<Self, ApiError> {
+        Self::new(n_bins, range.min, range.max)
+    }
+
+    pub fn histogram(&self) -> &Histogram {
+        &self.0
+    }
+}
+
+impl Reducer for Binned {
+    fn update(&mut self, values: ArrayView1<f64>) {
+        self.0.add(values.to_vec());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_chunked_equals_direct() {
+        let x = array![1., 4., 2., 8., 5., 7.];
+        let mut w = Welford::default();
+        let mut s = Sum::default();
+        let mut m = MinMax::default();
+        for chunk in x
+            .exact_chunks(4)
+            .into_iter()
+            .chain([x.slice(ndarray::s![4..])])
+        {
+            w.update(chunk);
+            s.update(chunk);
+            m.update(chunk);
+        }
+        assert_eq!(s, Sum { sum: 27., n: 6 });
+        assert_eq!((m.min, m.max), (1., 8.));
+        assert!((w.mean() - 4.5).abs() < 1e-12);
+        assert!((w.variance() - x.var(0.)).abs() < 1e-12);
+        assert!((w.sample_variance() - x.var(1.)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = Welford::default();
+        a.update(array![1., 2., 3.].view());
+        let mut b = Welford::default();
+        b.update(array![10., 20.].view());
+        a.merge(&b);
+        let x = array![1., 2., 3., 10., 20.];
+        assert!((a.mean() - x.mean().unwrap()).abs() < 1e-12);
+        assert!((a.variance() - x.var(0.)).abs() < 1e-12);
+        assert!(Welford::default().mean().is_nan());
+    }
+
+    #[test]
+    fn test_binned() {
+        let x = array![0.5, 1.5, 1.7, 0.];
+        let mut m = MinMax::default();
+        m.update(x.view());
+        let mut h = Binned::from_min_max(2, &m).unwrap();
+        h.update(x.slice(ndarray::s![..2]));
+        // Outliers of later chunks stay in the bins of the range
+        h.update(x.slice(ndarray::s![2..]));
+        h.update(array![5.].view());
+        assert_eq!(h.histogram().get_bins(), &[0., 0.85]);
+        assert_eq!(h.histogram().get_counts(), &[2., 3.]);
+        assert!(Binned::new(2, 1., 1.).is_err());
+        assert!(Binned::from_min_max(2, &MinMax::default()).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Frequency-domain analysis of oscillating time series.
+//!
+//! Spectra need evenly spaced samples: series exported on a non-uniform grid are first
+//! interpolated linearly onto a uniform grid with the median time step, see [`uniform`]. Means
+//! are removed before the analysis.
+//!
+//! # Example
+//! ```ignore
+//! let p = periodogram(&time, &concentration)?;
+//! let circulation_time = p.dominant_period();
+//! let tau = autocorrelation_time(&time, &concentration)?;
+//! ```
+use crate::error::ApiError;
+use crate::process::interpolate::{linear, uniform_grid};
+use ndarray::{Array1, ArrayView1};
+
+/// Relative spread of the time steps below which a grid is considered uniform
+const UNIFORM_TOLERANCE: f64 = 1e-6;
+
+/// One-sided power spectral density of a series
+#[derive(Debug, Clone, PartialEq)]
+pub struct Periodogram {
+    /// Frequencies in Hz, from 0 to the Nyquist frequency
+    pub frequency: Array1<f64>,
+    /// Power spectral density at each frequency, in unit² / Hz
+    pub power: Array1<f64>,
+}
+
+impl Periodogram {
+    /// Frequency of the highest peak, the zero frequency excluded
+    pub fn dominant_frequency(&self) -> Option<f64> {
+        self.power
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, p)| p.is_finite())
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .filter(|(_, p)| **p > 0.)
+            .map(|(i, _)| self.frequency[i])
+    }
+
+    /// Period of the highest peak, in s
+    pub fn dominant_period(&self) -> Option<f64> {
+        self.dominant_frequency().map(|f| 1. / f)
+    }
+}
+
+/// Evenly spaced samples of `y(t)`.
+///
+/// # Returns
+/// * `(f64, Vec<f64>)` - Time step and values, interpolated with the median step if the grid of
+///   `t` is not uniform.
+pub fn uniform(t: &[f64], y: &[f64]) -> Result<(f64, Vec<f64>), ApiError> {
+    if t.len() != y.len() {
+        return Err(ApiError::ShapeError);
+    }
+    if t.len() < 4 {
+        return Err(ApiError::OutOfRange(t.len(), 4));
+    }
+    let mut steps: Vec<f64> = t.windows(2).map(|w| w[1] - w[0]).collect();
+    if steps.iter().any(|dt| dt.is_nan() || *dt <= 0.) {
+        return Err(ApiError::Default("Time must be increasing".to_string()));
+    }
+    steps.sort_by(f64::total_cmp);
+    let dt = steps[steps.len() / 2];
+    if (steps[steps.len() - 1] - steps[0]) <= UNIFORM_TOLERANCE * dt {
+        return Ok((dt, y.to_vec()));
+    }
+    let grid = uniform_grid(t[0], t[t.len() - 1], dt);
+    Ok((dt, linear(t, &ArrayView1::from(y), &grid).to_vec()))
+}
+
+/// Periodogram of `y(t)`, zero-padded to a power of two.
+pub fn periodogram(t: &[f64], y: &[f64]) -> Result<Periodogram, ApiError> {
+    let (dt, y) = uniform(t, y)?;
+    let n = y.len();
+    let n_fft = n.next_power_of_two();
+    let spectrum = fft(&centered(&y, n_fft));
+
+    let n_freq = n_fft / 2 + 1;
+    let frequency = (0..n_freq)
+        .map(|k| k as f64 / (n_fft as f64 * dt))
+        .collect();
+    let power = (0..n_freq)
+        .map(|k| {
+            let (re, im) = spectrum[k];
+            // One-sided: every frequency but 0 and Nyquist holds the power of its negative twin
+            let factor = if k == 0 || 2 * k == n_fft { 1. } else { 2. };
+            factor * dt * (re * re + im * im) / n as f64
+        })
+        .collect();
+    Ok(Periodogram { frequency, power })
+}
+
+/// Normalised autocorrelation of evenly spaced samples, for lags `0..n`.
+pub fn autocorrelation(y: &[f64]) -> Array1<f64> {
+    let n = y.len();
+    if n == 0 {
+        return Array1::zeros(0);
+    }
+    // Padding to twice the length avoids circular correlation
+    let n_fft = (2 * n).next_power_of_two();
+    let spectrum: Vec<(f64, f64)> = fft(&centered(y, n_fft))
+        .into_iter()
+        .map(|(re, im)| (re * re + im * im, 0.))
+        .collect();
+    let acov = inverse_fft(&spectrum);
+    let c0 = acov[0].0;
+    (0..n)
+        .map(|k| if c0 > 0. { acov[k].0 / c0 } else { f64::NAN })
+        .collect()
+}
+
+/// Integral of the autocorrelation of `y(t)` up to its first zero crossing, in s.
+///
+/// This is the time over which fluctuations stay correlated, NaN for a constant series.
+pub fn autocorrelation_time(t: &[f64], y: &[f64]) -> Result<f64, ApiError> {
+    let (dt, y) = uniform(t, y)?;
+    let r = autocorrelation(&y);
+    if r[0].is_nan() {
+        return Ok(f64::NAN);
+    }
+    let mut tau = 0.5 * r[0];
+    for k in 1..r.len() {
+        if r[k] <= 0. {
+            // Linear interpolation down to the crossing
+            tau += 0.5 * r[k - 1] * r[k - 1] / (r[k - 1] - r[k]) - 0.5 * r[k - 1];
+            return Ok(tau * dt);
+        }
+        tau += r[k];
+    }
+    Ok((tau - 0.5 * r[r.len() - 1]) * dt)
+}
+
+/// Values minus their mean, padded with zeros to `n_fft` complex values
+fn centered(y: &[f64], n_fft: usize) -> Vec<(f64, f64)> {
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let mut x = vec![(0., 0.); n_fft];
+    for (xi, yi) in x.iter_mut().zip(y) {
+        xi.0 = yi - mean;
+    }
+    x
+}
+
+/// Iterative radix-2 FFT, the length must be a power of two
+fn fft(x: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    transform(x, -1.)
+}
+
+/// Inverse of [`fft`], normalised
+fn inverse_fft(x: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let n = x.len() as f64;
+    transform(x, 1.)
+        .into_iter()
+        .map(|(re, im)| (re / n, im / n))
+        .collect()
+}
+
+fn transform(x: &[(f64, f64)], sign: f64) -> Vec<(f64, f64)> {
+    let n = x.len();
+    debug_assert!(n.is_power_of_two());
+    let bits = n.trailing_zeros();
+    let mut a: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            x[if bits == 0 {
+                0
+            } else {
+                i.reverse_bits() >> (usize::BITS - bits)
+            }]
+        })
+        .collect();
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2. * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (s, c) = (angle * k as f64).sin_cos();
+                let (re, im) = a[start + k + len / 2];
+                let t = (re * c - im * s, re * s + im * c);
+                let u = a[start + k];
+                a[start + k] = (u.0 + t.0, u.1 + t.1);
+                a[start + k + len / 2] = (u.0 - t.0, u.1 - t.1);
+            }
+        }
+        len *= 2;
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_fft() {
+        let x: Vec<(f64, f64)> = (0..8).map(|i| (i as f64, 0.)).collect();
+        let back = inverse_fft(&fft(&x));
+        for (a, b) in x.iter().zip(&back) {
+            assert!((a.0 - b.0).abs() < 1e-12 && b.1.abs() < 1e-12);
+        }
+        // Sum of the samples at the zero frequency
+        assert!((fft(&x)[0].0 - 28.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_periodogram() {
+        // 2 s period sampled every 0.1 s
+        let t: Vec<f64> = (0..256).map(|i| i as f64 * 0.1).collect();
+        let y: Vec<f64> = t.iter().map(|t| 3. + (2. * PI * t / 2.).sin()).collect();
+        let p = periodogram(&t, &y).unwrap();
+        assert!((p.dominant_period().unwrap() - 2.).abs() < 0.05);
+        assert_eq!(p.frequency.len(), 129);
+        // Parseval: the variance is the integral of the density
+        let df = p.frequency[1];
+        let variance: f64 = p.power.sum() * df;
+        assert!((variance - 0.5).abs() < 0.02, "{}", variance);
+    }
+
+    #[test]
+    fn test_uniform() {
+        let t = [0., 1., 2., 2.5, 3., 4.];
+        let y = [0., 1., 2., 2.5, 3., 4.];
+        let (dt, v) = uniform(&t, &y).unwrap();
+        assert_eq!(dt, 1.);
+        assert_eq!(v, vec![0., 1., 2., 3., 4.]);
+        assert!(uniform(&t[..3], &y[..3]).is_err());
+        // 3 * 0.1 rounds above 0.3, the last point must stay inside the samples
+        let t = [0., 0.1, 0.2, 0.25, 0.3];
+        let (dt, v) = uniform(&t, &t).unwrap();
+        assert_eq!(dt, 0.1);
+        assert_eq!(v.len(), 4);
+        assert!(v.iter().all(|x| x.is_finite()));
+        assert_eq!(v[3], 0.3);
+    }
+
+    #[test]
+    fn test_autocorrelation_time() {
+        // AR(1) process with unit time step: the integral of r(k) = phi^k is about 1/(1 - phi)
+        let phi: f64 = 0.9;
+        let mut state: u64 = 7;
+        let mut y = vec![0.];
+        for _ in 1..20000 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let noise = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+            y.push(phi * y[y.len() - 1] + noise);
+        }
+        let t: Vec<f64> = (0..y.len()).map(|i| i as f64).collect();
+        let r = autocorrelation(&y);
+        assert_eq!(r[0], 1.);
+        assert!((r[1] - phi).abs() < 0.02);
+        let tau = autocorrelation_time(&t, &y).unwrap();
+        let expected = 1. / (1. - phi) - 0.5;
+        assert!((tau - expected).abs() / expected < 0.2, "{}", tau);
+        assert!(autocorrelation_time(&t[..10], &[1.; 10]).unwrap().is_nan());
+    }
+}
@@ -0,0 +1,310 @@
+//! Descriptive statistics across replicate samples.
+use crate::error::ApiError;
+use ndarray::{Array, Axis, Dimension};
+
+/// Mean, standard deviation and confidence band of a quantity over replicates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Band<D: Dimension> {
+    pub mean: Array<f64, D>,
+    /// Sample standard deviation (n - 1 normalisation), NaN for a single replicate
+    pub std: Array<f64, D>,
+    /// Lower bound of the confidence interval of the mean
+    pub lower: Array<f64, D>,
+    /// Upper bound of the confidence interval of the mean
+    pub upper: Array<f64, D>,
+    /// Number of replicates
+    pub n: usize,
+    /// Confidence level of the band, e.g. 0.95
+    pub level: f64,
+}
+
+/// Computes the mean and the Student confidence band of the mean of replicates.
+///
+/// # Arguments
+/// * `samples` - One array per replicate, all of the same shape.
+/// * `level` - Confidence level in (0, 1).
+pub fn band<D: Dimension>(samples: &[Array<f64, D>], level: f64) -> Result<Band<D>, ApiError> {
+    if !(level > 0. && level < 1.) {
+        return Err(ApiError::Default(format!(
+            "Confidence level must be in (0, 1), got {}",
+            level
+        )));
+    }
+    let first = samples
+        .first()
+        .ok_or_else(|| ApiError::Default("No sample".to_string()))?;
+    if samples.iter().any(|s| s.shape() != first.shape()) {
+        return Err(ApiError::ShapeError);
+    }
+
+    let n = samples.len();
+    let views: Vec<_> = samples.iter().map(|s| s.view()).collect();
+    let stacked = ndarray::stack(Axis(0), &views).map_err(|_| ApiError::ShapeError)?;
+
+    let mean = stacked
+        .mean_axis(Axis(0))
+        .ok_or(ApiError::ShapeError)?
+        .into_dimensionality::<D>()
+        .map_err(|_| ApiError::ShapeError)?;
+    let std = if n > 1 {
+        stacked
+            .std_axis(Axis(0), 1.)
+            .into_dimensionality::<D>()
+            .map_err(|_| ApiError::ShapeError)?
+    } else {
+        Array::from_elem(mean.raw_dim(), f64::NAN)
+    };
+
+    let half_width = if n > 1 {
+        student_t_quantile(0.5 + level / 2., (n - 1) as f64) / (n as f64).sqrt()
+    } else {
+        f64::NAN
+    };
+    let lower = &mean - &(&std * half_width);
+    let upper = &mean + &(&std * half_width);
+
+    Ok(Band {
+        mean,
+        std,
+        lower,
+        upper,
+        n,
+        level,
+    })
+}
+
+/// Quantile of the standard normal distribution (Acklam's rational approximation, relative
+/// error below 1.2e-9).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    if p <= 0. {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1. {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p < P_LOW {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - P_LOW {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
+}
+
+/// Quantile of the Student t distribution with `dof` degrees of freedom.
+///
+/// The Cornish-Fisher expansion (Abramowitz & Stegun 26.7.5) is refined by Newton steps on the
+/// closed-form distribution function for integer degrees of freedom up to 30, which makes it
+/// exact to rounding there. Above, or for fractional degrees of freedom, the expansion alone is
+/// used.
+pub fn student_t_quantile(p: f64, dof: f64) -> f64 {
+    if dof == 1. {
+        return (std::f64::consts::PI * (p - 0.5)).tan();
+    }
+    if dof == 2. {
+        return (2. * p - 1.) / (2. * p * (1. - p)).sqrt();
+    }
+    let z = normal_quantile(p);
+    let z2 = z * z;
+    let g1 = (z2 + 1.) * z / 4.;
+    let g2 = ((5. * z2 + 16.) * z2 + 3.) * z / 96.;
+    let g3 = (((3. * z2 + 19.) * z2 + 17.) * z2 - 15.) * z / 384.;
+    let g4 = ((((79. * z2 + 776.) * z2 + 1482.) * z2 - 1920.) * z2 - 945.) * z / 92160.;
+    let mut t = z + g1 / dof + g2 / dof.powi(2) + g3 / dof.powi(3) + g4 / dof.powi(4);
+
+    if dof.fract() != 0. || dof > EXACT_MAX_DOF as f64 || !t.is_finite() {
+        return t;
+    }
+    let dof = dof as usize;
+    for _ in 0..10 {
+        let (cdf, pdf) = student_t_cdf_pdf(t, dof);
+        let step = (cdf - p) / pdf;
+        t -= step;
+        if step.abs() <= 1e-14 * t.abs() {
+            break;
+        }
+    }
+    t
+}
+
+/// Degrees of freedom up to which [`student_t_quantile`] inverts the exact distribution function
+const EXACT_MAX_DOF: usize = 30;
+
+/// Distribution function and density of the Student t distribution for integer degrees of
+/// freedom, from the finite series of Abramowitz & Stegun 26.7.3 and 26.7.4.
+fn student_t_cdf_pdf(t: f64, dof: usize) -> (f64, f64) {
+    let nu = dof as f64;
+    let theta = (t / nu.sqrt()).atan();
+    let (sin, cos) = theta.sin_cos();
+    let cos2 = cos * cos;
+    // P(|T| < t)
+    let a = if dof % 2 == 1 {
+        let mut term = cos;
+        let mut sum = if dof > 1 { cos } else { 0. };
+        for k in (3..dof).step_by(2) {
+            term *= (k - 1) as f64 / k as f64 * cos2;
+            sum += term;
+        }
+        2. / std::f64::consts::PI * (theta + sin * sum)
+    } else {
+        let mut term = 1.;
+        let mut sum = 1.;
+        for k in (2..dof).step_by(2) {
+            term *= (k - 1) as f64 / k as f64 * cos2;
+            sum += term;
+        }
+        sin * sum
+    };
+    // Gamma((nu + 1) / 2) / Gamma(nu / 2) by recurrence from nu = 1 or 2
+    let mut ratio = if dof % 2 == 1 {
+        1. / std::f64::consts::PI.sqrt()
+    } else {
+        std::f64::consts::PI.sqrt() / 2.
+    };
+    for k in ((2 - dof % 2)..dof).step_by(2) {
+        ratio *= (k as f64 + 1.) / k as f64;
+    }
+    let pdf = ratio / (nu * std::f64::consts::PI).sqrt() * (1. + t * t / nu).powf(-(nu + 1.) / 2.);
+    (0.5 + a / 2., pdf)
+}
+
+/// Empirical cumulative distributions of two samples evaluated on their merged sorted values.
+///
+/// Calls `f(x_i, x_{i+1}, F_a, F_b)` on each interval between consecutive merged values.
+fn merged_cdf<F: FnMut(f64, f64, f64, f64)>(a: &[f64], b: &[f64], mut f: F) {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_by(f64::total_cmp);
+    b.sort_by(f64::total_cmp);
+    let mut merged: Vec<f64> = a.iter().chain(&b).copied().collect();
+    merged.sort_by(f64::total_cmp);
+
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    for w in merged.windows(2) {
+        let cdf_a = a.partition_point(|x| *x <= w[0]) as f64 / na;
+        let cdf_b = b.partition_point(|x| *x <= w[0]) as f64 / nb;
+        f(w[0], w[1], cdf_a, cdf_b);
+    }
+}
+
+/// First Wasserstein (earth mover's) distance between two samples, NaN if one is empty
+pub fn wasserstein_distance(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return f64::NAN;
+    }
+    let mut distance = 0.;
+    merged_cdf(a, b, |x0, x1, fa, fb| {
+        distance += (fa - fb).abs() * (x1 - x0)
+    });
+    distance
+}
+
+/// Two-sample Kolmogorov-Smirnov statistic, NaN if one sample is empty
+pub fn ks_statistic(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return f64::NAN;
+    }
+    let mut statistic: f64 = 0.;
+    merged_cdf(a, b, |_, _, fa, fb| {
+        statistic = statistic.max((fa - fb).abs())
+    });
+    statistic
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_normal_quantile() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.5)).abs() < 1e-12);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_student_quantile() {
+        // Reference values from tables
+        for (dof, t) in [
+            (1., 12.706),
+            (2., 4.303),
+            (4., 2.776),
+            (9., 2.262),
+            (30., 2.042),
+        ] {
+            let q = student_t_quantile(0.975, dof);
+            assert!((q - t).abs() / t < 2e-3, "dof {}: {} != {}", dof, q, t);
+        }
+        // Tails at small degrees of freedom, where the expansion alone is off by about 1%
+        for (p, dof, t) in [(0.995, 3., 5.840909), (0.975, 3., 3.182446), (0.005, 5., -4.032143)] {
+            let q = student_t_quantile(p, dof);
+            assert!((q - t).abs() < 1e-5, "dof {}: {} != {}", dof, q, t);
+        }
+        assert!((student_t_quantile(0.995, 30.) - 2.749996).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_band() {
+        let samples = vec![array![1., 2.], array![3., 2.], array![5., 2.]];
+        let b = band(&samples, 0.95).unwrap();
+        assert_eq!(b.mean, array![3., 2.]);
+        assert_eq!(b.std, array![2., 0.]);
+        let half = 4.303 * 2. / 3f64.sqrt();
+        assert!((b.upper[0] - 3. - half).abs() < 1e-2);
+        assert_eq!(b.lower[1], 2.);
+
+        assert!(band(&samples[..1], 0.95).unwrap().std[0].is_nan());
+        assert!(band(&[array![1.], array![1., 2.]], 0.95).is_err());
+        assert!(band(&samples, 1.).is_err());
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = [0., 1., 2.];
+        let shifted = [0.5, 1.5, 2.5];
+        assert!((wasserstein_distance(&a, &shifted) - 0.5).abs() < 1e-12);
+        assert_eq!(wasserstein_distance(&a, &a), 0.);
+        assert!((ks_statistic(&a, &shifted) - 1. / 3.).abs() < 1e-12);
+        assert_eq!(ks_statistic(&a, &[10., 11.]), 1.);
+        assert!(ks_statistic(&a, &[]).is_nan());
+    }
+}
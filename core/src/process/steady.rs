@@ -0,0 +1,283 @@
+//! Detection of the steady state of a time series, e.g. the biomass of a chemostat.
+//!
+//! A detector returns the first sample of the steady state, the mean of the series from that
+//! sample on and its confidence interval. Exported series are autocorrelated, the standard error
+//! of the mean is estimated with non-overlapping batch means.
+use crate::error::ApiError;
+use crate::process::stats::{normal_quantile, student_t_quantile};
+
+/// How the onset of the steady state is detected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detector {
+    /// Every window of `window` samples from the onset on changes by less than `threshold`
+    /// relative to its mean, the change being the slope of a fitted line times the window span.
+    Slope { window: usize, threshold: f64 },
+    /// Marginal Standard Error Rule on means of `batch` samples (MSER-5 with `batch = 5`): the
+    /// onset minimises the standard error of the mean of the remaining samples.
+    Mser { batch: usize },
+    /// Geweke test: the onset is the first truncation, by steps of 5% of the series, at which the
+    /// mean of the `first` fraction of the remaining samples does not differ significantly from
+    /// the mean of the `last` fraction.
+    Geweke { first: f64, last: f64 },
+}
+
+impl Detector {
+    pub fn slope(window: usize, threshold: f64) -> Self {
+        Detector::Slope { window, threshold }
+    }
+
+    pub fn mser() -> Self {
+        Detector::Mser { batch: 5 }
+    }
+
+    pub fn geweke() -> Self {
+        Detector::Geweke {
+            first: 0.1,
+            last: 0.5,
+        }
+    }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        let valid = match *self {
+            Detector::Slope { window, threshold } => window >= 2 && threshold >= 0.,
+            Detector::Mser { batch } => batch >= 1,
+            Detector::Geweke { first, last } => first > 0. && last > 0. && first + last <= 1.,
+        };
+        if !valid {
+            return Err(ApiError::Default(format!("Invalid detector {:?}", self)));
+        }
+        Ok(())
+    }
+}
+
+/// Onset and statistics of the steady state of a series
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteadyState {
+    /// Index of the first steady sample
+    pub onset: usize,
+    /// Time of the first steady sample
+    pub time: f64,
+    /// Mean of the samples from the onset on
+    pub mean: f64,
+    /// Standard error of the mean, from batch means
+    pub std_error: f64,
+    /// Bounds of the confidence interval of the mean
+    pub lower: f64,
+    pub upper: f64,
+    pub level: f64,
+}
+
+/// Detects the steady state of `y(t)`.
+///
+/// # Arguments
+/// * `level` - Confidence level of the interval of the mean, and of the Geweke test.
+///
+/// # Returns
+/// * `Option<SteadyState>` - `None` if the series does not reach a steady state.
+pub fn detect(
+    t: &[f64],
+    y: &[f64],
+    detector: Detector,
+    level: f64,
+) -> Result<Option<SteadyState>, ApiError> {
+    detector.validate()?;
+    if !(level > 0. && level < 1.) {
+        return Err(ApiError::Default(format!(
+            "Confidence level must be in (0, 1), got {}",
+            level
+        )));
+    }
+    if t.len() != y.len() {
+        return Err(ApiError::ShapeError);
+    }
+
+    let onset = match detector {
+        Detector::Slope { window, threshold } => slope_onset(t, y, window, threshold),
+        Detector::Mser { batch } => mser_onset(y, batch),
+        Detector::Geweke { first, last } => geweke_onset(y, first, last, level),
+    };
+    Ok(onset.map(|onset| {
+        let steady = &y[onset..];
+        let mean = steady.iter().sum::<f64>() / steady.len() as f64;
+        let (std_error, dof) = batch_std_error(steady);
+        let half_width = if dof > 0 {
+            student_t_quantile(0.5 + level / 2., dof as f64) * std_error
+        } else {
+            f64::NAN
+        };
+        SteadyState {
+            onset,
+            time: t[onset],
+            mean,
+            std_error,
+            lower: mean - half_width,
+            upper: mean + half_width,
+            level,
+        }
+    }))
+}
+
+/// First window start from which every window is flat, `None` if the last window is not
+fn slope_onset(t: &[f64], y: &[f64], window: usize, threshold: f64) -> Option<usize> {
+    let n = y.len();
+    if window > n {
+        return None;
+    }
+    let is_flat = |i: usize| {
+        let (t, y) = (&t[i..i + window], &y[i..i + window]);
+        let (slope, mean) = linear_fit(t, y);
+        (slope * (t[window - 1] - t[0])).abs() <= threshold * mean.abs()
+    };
+    let mut onset = None;
+    for i in (0..=n - window).rev() {
+        if !is_flat(i) {
+            break;
+        }
+        onset = Some(i);
+    }
+    onset
+}
+
+/// Slope of the least-squares line through `(t, y)` and mean of `y`
+fn linear_fit(t: &[f64], y: &[f64]) -> (f64, f64) {
+    let n = t.len() as f64;
+    let t_mean = t.iter().sum::<f64>() / n;
+    let y_mean = y.iter().sum::<f64>() / n;
+    let (mut sty, mut stt) = (0., 0.);
+    for (ti, yi) in t.iter().zip(y) {
+        sty += (ti - t_mean) * (yi - y_mean);
+        stt += (ti - t_mean).powi(2);
+    }
+    let slope = if stt > 0. { sty / stt } else { 0. };
+    (slope, y_mean)
+}
+
+/// Truncation minimising the MSER statistic, `None` if it falls in the second half
+fn mser_onset(y: &[f64], batch: usize) -> Option<usize> {
+    // Batches are aligned on the end of the series, leftover samples go to the transient
+    let k = y.len() / batch;
+    if k < 2 {
+        return None;
+    }
+    let start = y.len() - k * batch;
+    let means: Vec<f64> = y[start..]
+        .chunks(batch)
+        .map(|b| b.iter().sum::<f64>() / batch as f64)
+        .collect();
+
+    let mut best = (f64::INFINITY, 0);
+    for d in 0..k - 1 {
+        let rest = &means[d..];
+        let m = rest.len() as f64;
+        let mean = rest.iter().sum::<f64>() / m;
+        let stat = rest.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (m * m);
+        if stat < best.0 {
+            best = (stat, d);
+        }
+    }
+    (best.1 <= k / 2).then_some(start + best.1 * batch)
+}
+
+/// First truncation at which the Geweke z-score is not significant
+fn geweke_onset(y: &[f64], first: f64, last: f64, level: f64) -> Option<usize> {
+    let n = y.len();
+    let z_crit = normal_quantile(0.5 + level / 2.);
+    let step = (n / 20).max(1);
+    (0..=n / 2).step_by(step).find(|d| {
+        let rest = &y[*d..];
+        let n_a = (first * rest.len() as f64) as usize;
+        let n_b = (last * rest.len() as f64) as usize;
+        if n_a < 2 || n_b < 2 {
+            return false;
+        }
+        let (a, b) = (&rest[..n_a], &rest[rest.len() - n_b..]);
+        let mean = |x: &[f64]| x.iter().sum::<f64>() / x.len() as f64;
+        let (se_a, _) = batch_std_error(a);
+        let (se_b, _) = batch_std_error(b);
+        let se = (se_a.powi(2) + se_b.powi(2)).sqrt();
+        let diff = mean(a) - mean(b);
+        if se > 0. {
+            (diff / se).abs() < z_crit
+        } else {
+            diff == 0.
+        }
+    })
+}
+
+/// Standard error of the mean from non-overlapping batch means of about `sqrt(n)` samples, and
+/// its degrees of freedom. Falls back to independent samples with less than 2 batches.
+fn batch_std_error(y: &[f64]) -> (f64, usize) {
+    let n = y.len();
+    let size = (n as f64).sqrt().floor().max(1.) as usize;
+    let k = n / size;
+    let (values, k) = if k >= 2 && size > 1 {
+        let start = n - k * size;
+        let means: Vec<f64> = y[start..]
+            .chunks(size)
+            .map(|b| b.iter().sum::<f64>() / size as f64)
+            .collect();
+        (means, k)
+    } else {
+        (y.to_vec(), n)
+    };
+    if k < 2 {
+        return (f64::NAN, 0);
+    }
+    let mean = values.iter().sum::<f64>() / k as f64;
+    let var = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (k - 1) as f64;
+    ((var / k as f64).sqrt(), k - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exponential approach to 1 with reproducible uniform noise
+    fn series(n: usize) -> (Vec<f64>, Vec<f64>) {
+        let mut state: u64 = 12345;
+        let t: Vec<f64> = (0..n).map(|i| i as f64).collect();
+        let y = t
+            .iter()
+            .map(|t| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let noise = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+                1. - (-t / 10.).exp() + 0.02 * noise
+            })
+            .collect();
+        (t, y)
+    }
+
+    #[test]
+    fn test_slope() {
+        let (t, y) = series(200);
+        let s = detect(&t, &y, Detector::slope(20, 0.01), 0.95)
+            .unwrap()
+            .unwrap();
+        assert!(s.onset > 20 && s.onset < 80, "{}", s.onset);
+        assert!((s.mean - 1.).abs() < 1e-2);
+        assert!(s.lower < s.mean && s.mean < s.upper);
+        // A growing series never settles
+        let y: Vec<f64> = t.iter().map(|t| t * 2.).collect();
+        assert!(detect(&t, &y, Detector::slope(20, 0.01), 0.95)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_mser() {
+        let (t, y) = series(300);
+        let s = detect(&t, &y, Detector::mser(), 0.95).unwrap().unwrap();
+        assert!(s.onset > 10 && s.onset < 150, "{}", s.onset);
+        assert_eq!(s.time, t[s.onset]);
+    }
+
+    #[test]
+    fn test_geweke() {
+        let (t, y) = series(300);
+        let s = detect(&t, &y, Detector::geweke(), 0.95).unwrap().unwrap();
+        assert!(s.onset > 0 && s.onset <= 150, "{}", s.onset);
+        assert!(detect(&t, &y, Detector::slope(1, 0.1), 0.95).is_err());
+    }
+}
@@ -0,0 +1,486 @@
+//! Parameter sweeps over many runs.
+//!
+//! A [`Study`] gathers runs with user tags (e.g. a feed rate that is not stored in the result
+//! files) and builds a [`StudyTable`] of their initial parameters, tags and scalar KPIs.
+use crate::api::{ModelEstimator, Phase, PostProcessReader};
+use crate::error::ApiError;
+use crate::PostProcess;
+use csv::Writer;
+use ndarray::Axis;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Coefficient of variation under which a species is considered mixed
+pub const DEFAULT_MIXING_THRESHOLD: f64 = 0.05;
+
+/// Scalar indicator computed on each run of a study
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kpi {
+    /// Last spatial average biomass concentration
+    FinalBiomass,
+    /// Maximum of [`ModelEstimator::mu_direct`]
+    MaxMu,
+    /// Time from which the volume-weighted coefficient of variation of a liquid concentration
+    /// stays below `threshold`
+    MixingTime { species: String, threshold: f64 },
+    /// Biomass produced per substrate consumed in the liquid phase, between first and last export
+    Yield { substrate: String },
+}
+
+impl Kpi {
+    pub fn mixing_time(species: &str) -> Self {
+        Kpi::MixingTime {
+            species: species.to_string(),
+            threshold: DEFAULT_MIXING_THRESHOLD,
+        }
+    }
+
+    pub fn yield_on(substrate: &str) -> Self {
+        Kpi::Yield {
+            substrate: substrate.to_string(),
+        }
+    }
+
+    /// Computes the indicator, NaN if it is not reached (e.g. a species never mixed)
+    pub fn evaluate<R: PostProcessReader + ModelEstimator>(
+        &self,
+        reader: &R,
+    ) -> Result<f64, ApiError> {
+        match self {
+            Kpi::FinalBiomass => Ok(reader
+                .get_spatial_average_biomass_concentration()?
+                .last()
+                .copied()
+                .unwrap_or(f64::NAN)),
+            Kpi::MaxMu => Ok(reader
+                .mu_direct()?
+                .iter()
+                .copied()
+                .filter(|x| x.is_finite())
+                .fold(f64::NAN, f64::max)),
+            Kpi::MixingTime { species, threshold } => {
+                mixing_time(reader, species.as_str(), *threshold)
+            }
+            Kpi::Yield { substrate } => {
+                let x = reader.get_spatial_average_biomass_concentration()?;
+                let s =
+                    reader.get_spatial_average_concentration(substrate.as_str(), Phase::Liquid)?;
+                match (x.first(), x.last(), s.first(), s.last()) {
+                    (Some(x0), Some(x1), Some(s0), Some(s1)) => Ok((x1 - x0) / (s0 - s1)),
+                    _ => Ok(f64::NAN),
+                }
+            }
+        }
+    }
+}
+
+/// Time from which the coefficient of variation of a liquid concentration stays below threshold
+fn mixing_time<R: PostProcessReader>(
+    reader: &R,
+    species: &str,
+    threshold: f64,
+) -> Result<f64, ApiError> {
+    let i_species = reader.species_index(species)?;
+    let concentrations = reader.get_concentrations(Phase::Liquid);
+    let c = concentrations.index_axis(Axis(2), i_species);
+    let volume = reader.v_liquid();
+    let time = reader.time();
+
+    let mut mixed_since = None;
+    for (i, (c, v)) in c.outer_iter().zip(volume.outer_iter()).enumerate() {
+        let v_tot = v.sum();
+        let mean = (&c * &v).sum() / v_tot;
+        let variance = ((&c - mean).powi(2) * v).sum() / v_tot;
+        let cv = variance.sqrt() / mean.abs();
+        if cv <= threshold || variance == 0. {
+            mixed_since.get_or_insert(i);
+        } else {
+            mixed_since = None;
+        }
+    }
+    Ok(mixed_since.map(|i| time[i]).unwrap_or(f64::NAN))
+}
+
+impl fmt::Display for Kpi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kpi::FinalBiomass => write!(f, "final_biomass"),
+            Kpi::MaxMu => write!(f, "max_mu"),
+            Kpi::MixingTime { species, threshold } => {
+                if *threshold == DEFAULT_MIXING_THRESHOLD {
+                    write!(f, "mixing_time:{}", species)
+                } else {
+                    write!(f, "mixing_time:{}:{}", species, threshold)
+                }
+            }
+            Kpi::Yield { substrate } => write!(f, "yield:{}", substrate),
+        }
+    }
+}
+
+/// Parses `final_biomass`, `max_mu`, `mixing_time:<species>[:<threshold>]` and
+/// `yield:<substrate>`
+impl FromStr for Kpi {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split(':').map(str::trim).collect();
+        let unknown = || ApiError::Default(format!("Unknown KPI '{}'", s));
+        match parts.as_slice() {
+            ["final_biomass"] => Ok(Kpi::FinalBiomass),
+            ["max_mu"] => Ok(Kpi::MaxMu),
+            ["mixing_time", species] => Ok(Kpi::mixing_time(species)),
+            ["mixing_time", species, threshold] => Ok(Kpi::MixingTime {
+                species: species.to_string(),
+                threshold: threshold.parse().map_err(|_| unknown())?,
+            }),
+            ["yield", substrate] => Ok(Kpi::yield_on(substrate)),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+/// Value of a study table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Cell {
+    Number(f64),
+    Text(String),
+    /// Tag not set for a run
+    Missing,
+}
+
+impl Cell {
+    /// Total order used for sorting: numbers, then text, then missing values
+    fn cmp_total(&self, other: &Cell) -> Ordering {
+        match (self, other) {
+            (Cell::Number(a), Cell::Number(b)) => a.total_cmp(b),
+            (Cell::Text(a), Cell::Text(b)) => a.cmp(b),
+            (Cell::Missing, Cell::Missing) => Ordering::Equal,
+            (Cell::Number(_), _) | (Cell::Text(_), Cell::Missing) => Ordering::Less,
+            _ => Ordering::Greater,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Number(x) => write!(f, "{}", x),
+            Cell::Text(s) => write!(f, "{}", s),
+            Cell::Missing => Ok(()),
+        }
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(x: f64) -> Self {
+        Cell::Number(x)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(s: &str) -> Self {
+        Cell::Text(s.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(s: String) -> Self {
+        Cell::Text(s)
+    }
+}
+
+/// A run of a study with its user tags
+#[derive(Debug)]
+pub struct StudyRun {
+    pub name: String,
+    pub run: PostProcess,
+    pub tags: BTreeMap<String, Cell>,
+}
+
+/// Many runs of a parameter sweep.
+///
+/// # Example
+/// ```ignore
+/// let mut study = Study::open(&["sweep_1", "sweep_2", "sweep_3"], None)?;
+/// study.tag("sweep_1", "feed_rate", 0.1)?;
+/// let mut table = study.table(&[Kpi::FinalBiomass, Kpi::mixing_time("glucose")])?;
+/// table.sort_by(&["number_particles", "delta_time"])?;
+/// std::fs::write("sweep.csv", table.to_csv()?)?;
+/// ```
+#[derive(Debug, Default)]
+pub struct Study {
+    runs: Vec<StudyRun>,
+}
+
+impl Study {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens every folder with [`PostProcess::new`], runs are named after their folder
+    pub fn open<S: AsRef<str>>(folders: &[S], root: Option<String>) -> Result<Self, ApiError> {
+        let mut study = Self::new();
+        for folder in folders {
+            let folder = folder.as_ref();
+            study.add_run(folder, PostProcess::new(folder, root.clone())?)?;
+        }
+        Ok(study)
+    }
+
+    /// Adds a run, names must be unique
+    pub fn add_run(&mut self, name: &str, run: PostProcess) -> Result<(), ApiError> {
+        if self.runs.iter().any(|r| r.name == name) {
+            return Err(ApiError::Default(format!("Run '{}' already exists", name)));
+        }
+        self.runs.push(StudyRun {
+            name: name.to_string(),
+            run,
+            tags: BTreeMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Sets a user tag of a run, e.g. a feed rate or a model variant
+    pub fn tag(&mut self, run: &str, key: &str, value: impl Into<Cell>) -> Result<(), ApiError> {
+        let run = self
+            .runs
+            .iter_mut()
+            .find(|r| r.name == run)
+            .ok_or_else(|| ApiError::KeyError(run.to_string()))?;
+        run.tags.insert(key.to_string(), value.into());
+        Ok(())
+    }
+
+    pub fn runs(&self) -> &[StudyRun] {
+        &self.runs
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PostProcess> {
+        self.runs.iter().find(|r| r.name == name).map(|r| &r.run)
+    }
+
+    pub fn n_run(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Builds the table of the study.
+    ///
+    /// Columns are `run`, the initial parameters, the tags (sorted by name) and one column per
+    /// KPI named after its [`Display`](fmt::Display) form.
+    pub fn table(&self, kpis: &[Kpi]) -> Result<StudyTable, ApiError> {
+        let mut columns = vec!["run".to_string()];
+        if let Some(first) = self.runs.first() {
+            columns.extend(
+                first
+                    .run
+                    .get_initial_parameters()
+                    .parameters()
+                    .into_iter()
+                    .map(|(name, _)| name.to_string()),
+            );
+        }
+        let mut tag_names: Vec<&String> = self.runs.iter().flat_map(|r| r.tags.keys()).collect();
+        tag_names.sort();
+        tag_names.dedup();
+        columns.extend(tag_names.iter().map(|t| t.to_string()));
+        columns.extend(kpis.iter().map(|k| k.to_string()));
+
+        let mut rows = Vec::with_capacity(self.runs.len());
+        for r in &self.runs {
+            let mut row = vec![Cell::Text(r.name.clone())];
+            row.extend(
+                r.run
+                    .get_initial_parameters()
+                    .parameters()
+                    .into_iter()
+                    .map(|(_, x)| Cell::Number(x)),
+            );
+            row.extend(
+                tag_names
+                    .iter()
+                    .map(|t| r.tags.get(*t).cloned().unwrap_or(Cell::Missing)),
+            );
+            for kpi in kpis {
+                row.push(Cell::Number(kpi.evaluate(&r.run)?));
+            }
+            rows.push(row);
+        }
+        StudyTable::new(columns, rows)
+    }
+}
+
+/// Table of a study, one row per run
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudyTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl StudyTable {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Cell>>) -> Result<Self, ApiError> {
+        if rows.iter().any(|r| r.len() != columns.len()) {
+            return Err(ApiError::ShapeError);
+        }
+        Ok(Self { columns, rows })
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.rows
+    }
+
+    fn column_index(&self, name: &str) -> Result<usize, ApiError> {
+        self.columns
+            .iter()
+            .position(|c| c == name)
+            .ok_or_else(|| ApiError::KeyError(name.to_string()))
+    }
+
+    /// Values of a column
+    pub fn column(&self, name: &str) -> Result<Vec<&Cell>, ApiError> {
+        let i = self.column_index(name)?;
+        Ok(self.rows.iter().map(|r| &r[i]).collect())
+    }
+
+    /// Stable sort of the rows by the given columns, in order of priority
+    pub fn sort_by(&mut self, columns: &[&str]) -> Result<(), ApiError> {
+        let indices = columns
+            .iter()
+            .map(|c| self.column_index(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.rows.sort_by(|a, b| {
+            indices
+                .iter()
+                .map(|i| a[*i].cmp_total(&b[*i]))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(())
+    }
+
+    /// Splits the rows by value of a column, groups are ordered by first appearance
+    pub fn group_by(&self, column: &str) -> Result<Vec<(Cell, StudyTable)>, ApiError> {
+        let i = self.column_index(column)?;
+        let mut groups: Vec<(Cell, StudyTable)> = vec![];
+        for row in &self.rows {
+            match groups.iter_mut().find(|(key, _)| *key == row[i]) {
+                Some((_, table)) => table.rows.push(row.clone()),
+                None => groups.push((
+                    row[i].clone(),
+                    StudyTable {
+                        columns: self.columns.clone(),
+                        rows: vec![row.clone()],
+                    },
+                )),
+            }
+        }
+        Ok(groups)
+    }
+
+    pub fn to_csv(&self) -> Result<String, ApiError> {
+        let to_api_error = |e: csv::Error| ApiError::Default(e.to_string());
+        let mut wtr = Writer::from_writer(vec![]);
+        wtr.write_record(&self.columns).map_err(to_api_error)?;
+        for row in &self.rows {
+            wtr.write_record(row.iter().map(|c| c.to_string()))
+                .map_err(to_api_error)?;
+        }
+        let data = wtr
+            .into_inner()
+            .map_err(|e| ApiError::Default(e.to_string()))?;
+        String::from_utf8(data).map_err(|e| ApiError::Default(e.to_string()))
+    }
+
+    /// JSON object mapping each column to its values, missing tags are `null`
+    pub fn to_json(&self) -> Result<String, ApiError> {
+        let object: Map<String, Value> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values: Vec<&Cell> = self.rows.iter().map(|r| &r[i]).collect();
+                (
+                    name.clone(),
+                    serde_json::to_value(values).unwrap_or(Value::Null),
+                )
+            })
+            .collect();
+        serde_json::to_string_pretty(&object).map_err(|e| ApiError::Default(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> StudyTable {
+        StudyTable::new(
+            vec!["run".into(), "delta_time".into(), "feed".into()],
+            vec![
+                vec!["a".into(), 0.1.into(), "high".into()],
+                vec!["b".into(), 0.05.into(), Cell::Missing],
+                vec!["c".into(), 0.1.into(), "low".into()],
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_kpi_parse() {
+        for s in [
+            "final_biomass",
+            "max_mu",
+            "mixing_time:glucose",
+            "yield:glucose",
+        ] {
+            assert_eq!(s.parse::<Kpi>().unwrap().to_string(), s);
+        }
+        assert_eq!(
+            "mixing_time:o2:0.1".parse::<Kpi>().unwrap(),
+            Kpi::MixingTime {
+                species: "o2".to_string(),
+                threshold: 0.1
+            }
+        );
+        assert!("mixing_time".parse::<Kpi>().is_err());
+    }
+
+    #[test]
+    fn test_sort_group() {
+        let mut t = table();
+        t.sort_by(&["delta_time", "feed"]).unwrap();
+        let runs: Vec<String> = t
+            .column("run")
+            .unwrap()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        assert_eq!(runs, vec!["b", "a", "c"]);
+
+        let groups = t.group_by("delta_time").unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].0, Cell::Number(0.1));
+        assert_eq!(groups[1].1.rows().len(), 2);
+        assert!(t.sort_by(&["unknown"]).is_err());
+    }
+
+    #[test]
+    fn test_export() {
+        let t = table();
+        let csv = t.to_csv().unwrap();
+        assert_eq!(csv.lines().next(), Some("run,delta_time,feed"));
+        assert_eq!(csv.lines().nth(2), Some("b,0.05,"));
+        let json: Value = serde_json::from_str(&t.to_json().unwrap()).unwrap();
+        assert_eq!(json["feed"][1], Value::Null);
+        assert_eq!(json["delta_time"][0], Value::from(0.1));
+    }
+}
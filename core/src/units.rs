@@ -0,0 +1,293 @@
+//! Physical units of the quantities returned by the API.
+//!
+//! BioMC writes every quantity in SI units, [`Quantity::unit`] gives the unit in which each kind
+//! of series is returned and [`Unit`] provides the conversions needed for plots and exports.
+use crate::error::ApiError;
+use ndarray::{Array, Dimension as NdDimension};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Physical dimension of a unit, conversions are only allowed within the same dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Dimension {
+    Time,
+    Volume,
+    Mass,
+    Concentration,
+    Rate,
+    ConcentrationRate,
+    Dimensionless,
+}
+
+/// Supported units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    Second,
+    Minute,
+    Hour,
+    CubicMeter,
+    Liter,
+    Milliliter,
+    Kilogram,
+    Gram,
+    KgPerCubicMeter,
+    GramPerLiter,
+    MilligramPerLiter,
+    PerSecond,
+    PerHour,
+    KgPerCubicMeterPerSecond,
+    GramPerLiterPerHour,
+    Dimensionless,
+}
+
+impl Unit {
+    const ALL: [Unit; 16] = [
+        Unit::Second,
+        Unit::Minute,
+        Unit::Hour,
+        Unit::CubicMeter,
+        Unit::Liter,
+        Unit::Milliliter,
+        Unit::Kilogram,
+        Unit::Gram,
+        Unit::KgPerCubicMeter,
+        Unit::GramPerLiter,
+        Unit::MilligramPerLiter,
+        Unit::PerSecond,
+        Unit::PerHour,
+        Unit::KgPerCubicMeterPerSecond,
+        Unit::GramPerLiterPerHour,
+        Unit::Dimensionless,
+    ];
+
+    /// Symbol used in labels and headers
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Second => "s",
+            Unit::Minute => "min",
+            Unit::Hour => "h",
+            Unit::CubicMeter => "m3",
+            Unit::Liter => "L",
+            Unit::Milliliter => "mL",
+            Unit::Kilogram => "kg",
+            Unit::Gram => "g",
+            Unit::KgPerCubicMeter => "kg/m3",
+            Unit::GramPerLiter => "g/L",
+            Unit::MilligramPerLiter => "mg/L",
+            Unit::PerSecond => "1/s",
+            Unit::PerHour => "1/h",
+            Unit::KgPerCubicMeterPerSecond => "kg/m3/s",
+            Unit::GramPerLiterPerHour => "g/L/h",
+            Unit::Dimensionless => "-",
+        }
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        match self {
+            Unit::Second | Unit::Minute | Unit::Hour => Dimension::Time,
+            Unit::CubicMeter | Unit::Liter | Unit::Milliliter => Dimension::Volume,
+            Unit::Kilogram | Unit::Gram => Dimension::Mass,
+            Unit::KgPerCubicMeter | Unit::GramPerLiter | Unit::MilligramPerLiter => {
+                Dimension::Concentration
+            }
+            Unit::PerSecond | Unit::PerHour => Dimension::Rate,
+            Unit::KgPerCubicMeterPerSecond | Unit::GramPerLiterPerHour => {
+                Dimension::ConcentrationRate
+            }
+            Unit::Dimensionless => Dimension::Dimensionless,
+        }
+    }
+
+    /// Value of one unit expressed in the SI unit of the same dimension
+    fn si_factor(&self) -> f64 {
+        match self {
+            Unit::Second => 1.,
+            Unit::Minute => 60.,
+            Unit::Hour => 3600.,
+            Unit::CubicMeter => 1.,
+            Unit::Liter => 1e-3,
+            Unit::Milliliter => 1e-6,
+            Unit::Kilogram => 1.,
+            Unit::Gram => 1e-3,
+            Unit::KgPerCubicMeter => 1.,
+            Unit::GramPerLiter => 1.,
+            Unit::MilligramPerLiter => 1e-3,
+            Unit::PerSecond => 1.,
+            Unit::PerHour => 1. / 3600.,
+            Unit::KgPerCubicMeterPerSecond => 1.,
+            Unit::GramPerLiterPerHour => 1. / 3600.,
+            Unit::Dimensionless => 1.,
+        }
+    }
+
+    /// Factor by which values in `self` are multiplied to be expressed in `to`
+    pub fn conversion_factor(&self, to: Unit) -> Result<f64, ApiError> {
+        if self.dimension() != to.dimension() {
+            return Err(ApiError::UnitError(
+                self.symbol().to_string(),
+                to.symbol().to_string(),
+            ));
+        }
+        Ok(self.si_factor() / to.si_factor())
+    }
+
+    pub fn convert(&self, value: f64, to: Unit) -> Result<f64, ApiError> {
+        Ok(value * self.conversion_factor(to)?)
+    }
+
+    pub fn convert_array<D: NdDimension>(
+        &self,
+        values: &Array<f64, D>,
+        to: Unit,
+    ) -> Result<Array<f64, D>, ApiError> {
+        let factor = self.conversion_factor(to)?;
+        Ok(values * factor)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Unit::ALL
+            .iter()
+            .find(|u| u.symbol().eq_ignore_ascii_case(s))
+            .copied()
+            .or(match s {
+                "sec" => Some(Unit::Second),
+                "hr" => Some(Unit::Hour),
+                "m^3" | "m³" => Some(Unit::CubicMeter),
+                "kg/m^3" | "kg/m³" => Some(Unit::KgPerCubicMeter),
+                "" => Some(Unit::Dimensionless),
+                _ => None,
+            })
+            .ok_or_else(|| ApiError::Default(format!("Unknown unit '{}'", s)))
+    }
+}
+
+/// Kind of series returned by the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quantity {
+    Time,
+    Volume,
+    Mass,
+    /// Species and biomass concentrations
+    Concentration,
+    /// Specific growth rate
+    GrowthRate,
+    /// Gas-liquid mass transfer rate
+    MassTransferRate,
+    /// Particle counts and tallies
+    Count,
+}
+
+impl Quantity {
+    /// Unit in which the API returns this quantity, as written by BioMC
+    pub fn unit(&self) -> Unit {
+        match self {
+            Quantity::Time => Unit::Second,
+            Quantity::Volume => Unit::CubicMeter,
+            Quantity::Mass => Unit::Kilogram,
+            Quantity::Concentration => Unit::KgPerCubicMeter,
+            Quantity::GrowthRate => Unit::PerSecond,
+            Quantity::MassTransferRate => Unit::KgPerCubicMeterPerSecond,
+            Quantity::Count => Unit::Dimensionless,
+        }
+    }
+}
+
+impl FromStr for Quantity {
+    type Err = ApiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "time" => Ok(Quantity::Time),
+            "volume" => Ok(Quantity::Volume),
+            "mass" => Ok(Quantity::Mass),
+            "concentration" => Ok(Quantity::Concentration),
+            "growth_rate" | "mu" => Ok(Quantity::GrowthRate),
+            "mtr" | "mass_transfer_rate" => Ok(Quantity::MassTransferRate),
+            "count" => Ok(Quantity::Count),
+            _ => Err(ApiError::Default(format!("Unknown quantity '{}'", s))),
+        }
+    }
+}
+
+/// Formats a column header such as `time [h]`
+pub fn label(name: &str, unit: Unit) -> String {
+    format!("{} [{}]", name, unit.symbol())
+}
+
+/// Splits a column header such as `time [h]` into its name and unit symbol, `None` if there is
+/// no unit
+pub fn split_label(header: &str) -> (&str, Option<&str>) {
+    let header = header.trim();
+    match (header.find('['), header.strip_suffix(']')) {
+        (Some(open), Some(rest)) => (header[..open].trim(), Some(&rest[open + 1..])),
+        _ => (header, None),
+    }
+}
+
+/// Splits a column header such as `time [h]` into its name and unit, `None` if there is no unit
+pub fn parse_label(header: &str) -> Result<(String, Option<Unit>), ApiError> {
+    let (name, symbol) = split_label(header);
+    Ok((name.to_string(), symbol.map(str::parse).transpose()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_time_conversion() {
+        assert_eq!(Unit::Second.convert(7200., Unit::Hour).unwrap(), 2.);
+        assert_eq!(Unit::Hour.convert(0.5, Unit::Minute).unwrap(), 30.);
+    }
+
+    #[test]
+    fn test_concentration_conversion() {
+        assert_eq!(
+            Unit::KgPerCubicMeter
+                .convert(1.5, Unit::GramPerLiter)
+                .unwrap(),
+            1.5
+        );
+        let c = array![1., 2.];
+        let mg = Unit::GramPerLiter
+            .convert_array(&c, Unit::MilligramPerLiter)
+            .unwrap();
+        assert_eq!(mg, array![1000., 2000.]);
+    }
+
+    #[test]
+    fn test_incompatible_units() {
+        assert!(Unit::Second.convert(1., Unit::Liter).is_err());
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!("h".parse::<Unit>().unwrap(), Unit::Hour);
+        assert_eq!("g/l".parse::<Unit>().unwrap(), Unit::GramPerLiter);
+        assert_eq!("kg/m^3".parse::<Unit>().unwrap(), Unit::KgPerCubicMeter);
+        assert!("furlong".parse::<Unit>().is_err());
+    }
+
+    #[test]
+    fn test_parse_label() {
+        let (name, unit) = parse_label(&label("glucose", Unit::GramPerLiter)).unwrap();
+        assert_eq!(name, "glucose");
+        assert_eq!(unit, Some(Unit::GramPerLiter));
+        assert_eq!(parse_label(" od ").unwrap(), ("od".to_string(), None));
+        assert!(parse_label("x [furlong]").is_err());
+        assert_eq!(split_label("x [furlong]"), ("x", Some("furlong")));
+    }
+}
@@ -0,0 +1,320 @@
+//! Restriction of a run to a window of exports.
+use crate::api::{
+    Estimator, ModelEstimator, ParticleTable, Phase, PostProcessReader, SpeciesKey,
+};
+use crate::datamodel::chunks::ParticleChunks;
+use crate::datamodel::{tallies::Tallies, Weight};
+use crate::error::ApiError;
+use crate::metadata::{FinalSummary, InitialParameters, RunMetadata};
+use crate::impl_unique::mu_direct;
+use crate::process::filter::ParticleFilter;
+use crate::PostProcess;
+use ndarray::{s, Array1, Array2, ArrayView2, ArrayView3, Axis};
+use std::ops::Range;
+
+/// A run restricted to a contiguous range of exports.
+///
+/// Export indices are relative to the window: export `0` of the view is export `exports.start`
+/// of the run. Records are sliced without copy and biological datasets are only read for the
+/// exports of the window. Probes, which are not resolved in time, are not available on a view.
+///
+/// # Example
+/// ```ignore
+/// let last_hour = pp.view(t_end - 3600.0..f64::INFINITY)?;
+/// let x = last_hour.get_biomass_concentration()?;
+/// ```
+#[derive(Debug)]
+pub struct PostProcessView<'a> {
+    pp: &'a PostProcess,
+    exports: Range<usize>,
+    number_particle: Array2<f64>,
+    tallies: Option<Tallies>,
+}
+
+impl<'a> PostProcessView<'a> {
+    pub(crate) fn new(pp: &'a PostProcess, exports: Range<usize>) -> Result<Self, ApiError> {
+        let n_export = pp.n_export();
+        if exports.start > exports.end || exports.end > n_export {
+            return Err(ApiError::OutOfRange(exports.end, n_export));
+        }
+
+        let number_particle = pp
+            .get_number_particle()
+            .slice(s![exports.clone(), ..])
+            .to_owned();
+
+        // Tallies are written as one row of events per export
+        let tallies = pp.tallies().map(|t| t.slice(exports.clone()));
+
+        Ok(Self {
+            pp,
+            exports,
+            number_particle,
+            tallies,
+        })
+    }
+
+    /// Range of exports of the run covered by the view
+    pub fn exports(&self) -> Range<usize> {
+        self.exports.clone()
+    }
+
+    /// See [`PostProcess::particle_chunks`], `i_export` is relative to the view.
+    pub fn particle_chunks(
+        &self,
+        i_export: usize,
+        keys: &[&str],
+        chunk_size: usize,
+    ) -> Result<ParticleChunks, ApiError> {
+        self.pp.particle_chunks(self.global(i_export)?, keys, chunk_size)
+    }
+
+    /// Converts an export index of the view into an export index of the run
+    fn global(&self, i_export: usize) -> Result<usize, ApiError> {
+        if i_export >= self.exports.len() {
+            return Err(ApiError::OutOfRange(i_export, self.exports.len()));
+        }
+        Ok(self.exports.start + i_export)
+    }
+
+    fn window(&self, series: Array1<f64>) -> Array1<f64> {
+        series.slice(s![self.exports.clone()]).to_owned()
+    }
+}
+
+impl PostProcessReader for PostProcessView<'_> {
+    fn time(&self) -> &[f64] {
+        &self.pp.time()[self.exports.clone()]
+    }
+
+    fn v_liquid(&self) -> ArrayView2<'_, f64> {
+        self.pp.v_liquid().slice_move(s![self.exports.clone(), ..])
+    }
+
+    fn v_gas(&self) -> Option<ArrayView2<'_, f64>> {
+        self.pp
+            .v_gas()
+            .map(|v| v.slice_move(s![self.exports.clone(), ..]))
+    }
+
+    fn weight(&self) -> &Weight {
+        self.pp.weight()
+    }
+
+    fn tallies(&self) -> Option<&Tallies> {
+        self.tallies.as_ref()
+    }
+
+    fn get_initial_parameters(&self) -> InitialParameters {
+        self.pp.get_initial_parameters()
+    }
+
+    fn get_final_summary(&self) -> Option<FinalSummary> {
+        self.pp.get_final_summary()
+    }
+
+    fn get_run_metadata(&self) -> RunMetadata {
+        RunMetadata {
+            n_export: self.n_export(),
+            ..self.pp.get_run_metadata()
+        }
+    }
+
+    fn time_array(&self) -> Array1<f64> {
+        Array1::from_vec(self.time().to_vec())
+    }
+
+    fn get_max_n_export_bio(&self) -> usize {
+        self.pp
+            .get_max_n_export_bio()
+            .saturating_sub(self.exports.start)
+            .min(self.exports.len())
+    }
+
+    fn get_bio_exports(&self) -> Vec<usize> {
+        self.pp
+            .get_bio_exports()
+            .into_iter()
+            .filter(|i| self.exports.contains(i))
+            .map(|i| i - self.exports.start)
+            .collect()
+    }
+
+    fn n_export(&self) -> usize {
+        self.exports.len()
+    }
+
+    fn species_names(&self) -> Vec<String> {
+        self.pp.species_names()
+    }
+
+    fn get_property_names(&self) -> Vec<String> {
+        self.pp.get_property_names()
+    }
+
+    fn get_spatial_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        Ok(self.window(self.pp.get_spatial_average_concentration(species, phase)?))
+    }
+
+    fn get_spatial_average_property(&self, key: &str) -> Result<Array2<f64>, ApiError> {
+        self.pp
+            .spatial_average_property_in(key, self.exports.clone())
+    }
+
+    fn get_spatial_average_biomass_concentration(&self) -> Result<Array1<f64>, ApiError> {
+        self.pp
+            .spatial_average_biomass_concentration_in(self.exports.clone())
+    }
+
+    fn get_concentrations(&self, phase: Phase) -> ArrayView3<'_, f64> {
+        self.pp
+            .get_concentrations(phase)
+            .slice_move(s![self.exports.clone(), .., ..])
+    }
+
+    fn get_spatial_average_mtr(&self, species: impl SpeciesKey) -> Result<Array1<f64>, ApiError> {
+        Ok(self.window(self.pp.get_spatial_average_mtr(species)?))
+    }
+
+    fn get_variance_concentration(
+        &self,
+        species: impl SpeciesKey,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        Ok(self.window(self.pp.get_variance_concentration(species, phase)?))
+    }
+
+    fn get_time_average_concentration(
+        &self,
+        species: impl SpeciesKey,
+        position: usize,
+        phase: Phase,
+    ) -> Result<Array1<f64>, ApiError> {
+        let species = self.species_index(species)?;
+        self.pp
+            .time_average_concentration_in(species, position, phase, self.exports.clone())
+    }
+
+    fn get_biomass_concentration(&self) -> Result<Array2<f64>, ApiError> {
+        self.pp.biomass_concentration_in(self.exports.clone())
+    }
+
+    /// Probes are accumulated over the whole run without export times, they cannot be restricted
+    /// to the window: use the probes of the run instead.
+    fn get_probes(&self) -> Result<Array1<f64>, ApiError> {
+        Err(ApiError::Default(
+            "Probes are not resolved in time and are not available on a view".to_string(),
+        ))
+    }
+
+    fn get_growth_in_number(&self) -> Array1<f64> {
+        self.number_particle.sum_axis(Axis(1))
+    }
+
+    fn get_number_particle(&self) -> &Array2<f64> {
+        &self.number_particle
+    }
+
+    fn get_properties(&self, key: &str, i_export: usize) -> Result<Array1<f64>, ApiError> {
+        self.pp.get_properties(key, self.global(i_export)?)
+    }
+
+    fn get_particle_table(
+        &self,
+        keys: &[&str],
+        i_export: usize,
+    ) -> Result<ParticleTable, ApiError> {
+        self.pp.get_particle_table(keys, self.global(i_export)?)
+    }
+
+    fn get_time_population_mean(&self, key: &str) -> Result<Array1<f64>, ApiError> {
+        self.pp.time_population_mean_in(key, self.exports.clone())
+    }
+
+    fn get_histogram_array(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> Result<(Array1<f64>, Array1<f64>), ApiError> {
+        self.pp
+            .get_histogram_array(n_bins, self.global(i_export)?, key)
+    }
+
+    fn get_histogram(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> Result<(Vec<f64>, Vec<f64>), ApiError> {
+        self.pp.get_histogram(n_bins, self.global(i_export)?, key)
+    }
+
+    fn get_population_mean(&self, key: &str, i_export: usize) -> Result<f64, ApiError> {
+        self.pp.get_population_mean(key, self.global(i_export)?)
+    }
+
+    fn get_properties_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(Array1<f64>, f64), ApiError> {
+        self.pp
+            .get_properties_filtered(key, self.global(i_export)?, filter)
+    }
+
+    fn get_histogram_filtered(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+        filter: &ParticleFilter,
+    ) -> Result<(Vec<f64>, Vec<f64>, f64), ApiError> {
+        self.pp
+            .get_histogram_filtered(n_bins, self.global(i_export)?, key, filter)
+    }
+
+    fn get_population_mean_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError> {
+        self.pp
+            .get_population_mean_filtered(key, self.global(i_export)?, filter)
+    }
+}
+
+impl ModelEstimator for PostProcessView<'_> {
+    fn mu_direct(&self) -> Result<Array1<f64>, ApiError> {
+        mu_direct(self)
+    }
+
+    fn estimate(&self, etype: Estimator, key: &str, i_export: usize) -> Result<f64, ApiError> {
+        self.pp.estimate(etype, key, self.global(i_export)?)
+    }
+
+    fn estimate_time(&self, etype: Estimator, key: &str) -> Result<Array1<f64>, ApiError> {
+        let mut estimator = Array1::<f64>::zeros(self.n_export());
+        for i in 0..self.n_export() {
+            estimator[i] = self.estimate(etype, key, i)?;
+        }
+        Ok(estimator)
+    }
+
+    fn estimate_filtered(
+        &self,
+        etype: Estimator,
+        key: &str,
+        i_export: usize,
+        filter: &ParticleFilter,
+    ) -> Result<(f64, f64), ApiError> {
+        self.pp
+            .estimate_filtered(etype, key, self.global(i_export)?, filter)
+    }
+}
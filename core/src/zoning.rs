@@ -0,0 +1,298 @@
+//! Aggregation of compartments into named reactor zones.
+//!
+//! A [`Zoning`] maps compartment indices to zones such as impeller, top, bottom or feed zone.
+//! It can be built in code or loaded from a JSON or CSV file, zone-level quantities are then
+//! available through [`crate::api::ZoneReader`].
+use crate::error::ApiError;
+use ndarray::{Array2, ArrayView2, Axis};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A named group of compartments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub compartments: Vec<usize>,
+}
+
+/// Partition of (part of) the compartments into disjoint zones.
+///
+/// Compartments that do not belong to any zone are ignored by zone-level quantities.
+///
+/// # Example
+/// ```ignore
+/// let zoning = Zoning::new()
+///     .with_zone("impeller", vec![0, 1, 2])?
+///     .with_zone("top", (3..50).collect())?;
+/// let c = pp.get_zone_concentrations(&zoning, Phase::Liquid)?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Zoning {
+    zones: Vec<Zone>,
+}
+
+impl Zoning {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a zone, compartments already assigned to a zone or repeated are rejected.
+    pub fn add_zone(&mut self, name: &str, compartments: Vec<usize>) -> Result<(), ApiError> {
+        if self.zones.iter().any(|z| z.name == name) {
+            return Err(ApiError::Default(format!("Zone '{}' already exists", name)));
+        }
+        for (i, c) in compartments.iter().enumerate() {
+            if compartments[..i].contains(c) {
+                return Err(ApiError::Default(format!(
+                    "Compartment {} is repeated in zone '{}'",
+                    c, name
+                )));
+            }
+            if let Some(zone) = self.zone_of(*c) {
+                return Err(ApiError::Default(format!(
+                    "Compartment {} already belongs to zone '{}'",
+                    c, zone
+                )));
+            }
+        }
+        self.zones.push(Zone {
+            name: name.to_string(),
+            compartments,
+        });
+        Ok(())
+    }
+
+    /// Builder version of [`Zoning::add_zone`]
+    pub fn with_zone(mut self, name: &str, compartments: Vec<usize>) -> Result<Self, ApiError> {
+        self.add_zone(name, compartments)?;
+        Ok(self)
+    }
+
+    /// Parses a zoning written either as a list of `{"name": ..., "compartments": [...]}` or as an
+    /// object mapping zone names to compartment lists.
+    pub fn from_json(json: &str) -> Result<Self, ApiError> {
+        let zones: Vec<Zone> = match serde_json::from_str::<Vec<Zone>>(json) {
+            Ok(zones) => zones,
+            Err(_) => serde_json::from_str::<BTreeMap<String, Vec<usize>>>(json)
+                .map_err(|e| ApiError::Default(format!("Invalid zoning: {}", e)))?
+                .into_iter()
+                .map(|(name, compartments)| Zone { name, compartments })
+                .collect(),
+        };
+        let mut zoning = Zoning::new();
+        for zone in zones {
+            zoning.add_zone(&zone.name, zone.compartments)?;
+        }
+        Ok(zoning)
+    }
+
+    /// Parses a zoning written as CSV with a `compartment,zone` header, zones are ordered by first
+    /// appearance.
+    pub fn from_csv(csv: &str) -> Result<Self, ApiError> {
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let mut zones: Vec<Zone> = vec![];
+        for record in reader.deserialize::<(usize, String)>() {
+            let (compartment, name) =
+                record.map_err(|e| ApiError::Default(format!("Invalid zoning: {}", e)))?;
+            match zones.iter_mut().find(|z| z.name == name) {
+                Some(zone) => zone.compartments.push(compartment),
+                None => zones.push(Zone {
+                    name,
+                    compartments: vec![compartment],
+                }),
+            }
+        }
+        let mut zoning = Zoning::new();
+        for zone in zones {
+            zoning.add_zone(&zone.name, zone.compartments)?;
+        }
+        Ok(zoning)
+    }
+
+    /// Loads a zoning from a `.json` or `.csv` file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ApiError> {
+        let path = path.as_ref();
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ApiError::Default(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Self::from_csv(&content),
+            _ => Self::from_json(&content),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.zones)
+    }
+
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.zones.iter().map(|z| z.name.clone()).collect()
+    }
+
+    pub fn n_zone(&self) -> usize {
+        self.zones.len()
+    }
+
+    /// Name of the zone containing a compartment
+    pub fn zone_of(&self, compartment: usize) -> Option<&str> {
+        self.zones
+            .iter()
+            .find(|z| z.compartments.contains(&compartment))
+            .map(|z| z.name.as_str())
+    }
+
+    /// Checks that every compartment exists
+    pub fn check(&self, n_compartment: usize) -> Result<(), ApiError> {
+        match self
+            .zones
+            .iter()
+            .flat_map(|z| z.compartments.iter())
+            .find(|c| **c >= n_compartment)
+        {
+            Some(c) => Err(ApiError::OutOfRange(*c, n_compartment)),
+            None => Ok(()),
+        }
+    }
+
+    /// Sums a compartment quantity over each zone.
+    ///
+    /// # Arguments
+    /// * `values` - Quantity of shape (nt, n_compartment).
+    ///
+    /// # Returns
+    /// * `Array2<f64>` - Quantity of shape (nt, n_zone).
+    pub fn sum(&self, values: &ArrayView2<f64>) -> Array2<f64> {
+        let mut result = Array2::zeros((values.nrows(), self.n_zone()));
+        for (i_zone, zone) in self.zones.iter().enumerate() {
+            for c in &zone.compartments {
+                let mut column = result.column_mut(i_zone);
+                column += &values.column(*c);
+            }
+        }
+        result
+    }
+
+    /// Weighted mean of a compartment quantity over each zone, NaN for zones of zero weight.
+    ///
+    /// # Arguments
+    /// * `values` - Quantity of shape (nt, n_compartment).
+    /// * `weights` - Weights of shape (nt, n_compartment), e.g. compartment volumes.
+    ///
+    /// # Returns
+    /// * `Array2<f64>` - Mean of shape (nt, n_zone).
+    pub fn weighted_mean(
+        &self,
+        values: &ArrayView2<f64>,
+        weights: &ArrayView2<f64>,
+    ) -> Array2<f64> {
+        let weighted = values * weights;
+        self.sum(&weighted.view()) / self.sum(weights)
+    }
+
+    /// Weighted variance of a compartment quantity within each zone.
+    ///
+    /// Same definition as [`crate::api::PostProcessReader::get_variance_concentration`]: the sum
+    /// of the weighted squared deviations from the zone mean, not divided by the zone weight.
+    ///
+    /// # Returns
+    /// * `Array2<f64>` - Variance of shape (nt, n_zone).
+    pub fn weighted_variance(
+        &self,
+        values: &ArrayView2<f64>,
+        weights: &ArrayView2<f64>,
+    ) -> Array2<f64> {
+        let mean = self.weighted_mean(values, weights);
+        let mut result = Array2::zeros((values.nrows(), self.n_zone()));
+        for (i_zone, zone) in self.zones.iter().enumerate() {
+            let zone_mean = mean.column(i_zone);
+            let mut column = result.column_mut(i_zone);
+            for c in &zone.compartments {
+                let deviation = &values.column(*c) - &zone_mean;
+                column += &(deviation.powi(2) * weights.column(*c));
+            }
+        }
+        result
+    }
+
+    /// Zone index of each compartment, `None` for unassigned compartments
+    pub fn assignment(&self, n_compartment: usize) -> Vec<Option<usize>> {
+        let mut assignment = vec![None; n_compartment];
+        for (i_zone, zone) in self.zones.iter().enumerate() {
+            for c in &zone.compartments {
+                if *c < n_compartment {
+                    assignment[*c] = Some(i_zone);
+                }
+            }
+        }
+        assignment
+    }
+}
+
+/// Applies `f` to each (nt, n_compartment) slice along the last axis of a 3D array
+pub(crate) fn map_last_axis<F>(
+    values: &ndarray::ArrayView3<f64>,
+    n_zone: usize,
+    f: F,
+) -> ndarray::Array3<f64>
+where
+    F: Fn(&ArrayView2<f64>) -> Array2<f64>,
+{
+    let (nt, _, n_species) = values.dim();
+    let mut result = ndarray::Array3::zeros((nt, n_zone, n_species));
+    for (i, slice) in values.axis_iter(Axis(2)).enumerate() {
+        result.index_axis_mut(Axis(2), i).assign(&f(&slice));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    fn zoning() -> Zoning {
+        Zoning::new()
+            .with_zone("bottom", vec![0, 1])
+            .unwrap()
+            .with_zone("top", vec![2])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_overlap_rejected() {
+        assert!(zoning().with_zone("feed", vec![1]).is_err());
+        assert!(zoning().with_zone("top", vec![3]).is_err());
+        assert!(zoning().with_zone("feed", vec![3, 4, 3]).is_err());
+        assert!(zoning().check(2).is_err());
+        assert!(zoning().check(3).is_ok());
+    }
+
+    #[test]
+    fn test_parse() {
+        let json =
+            r#"[{"name": "bottom", "compartments": [0, 1]}, {"name": "top", "compartments": [2]}]"#;
+        assert_eq!(Zoning::from_json(json).unwrap(), zoning());
+        let map = r#"{"bottom": [0, 1], "top": [2]}"#;
+        assert_eq!(Zoning::from_json(map).unwrap(), zoning());
+        let csv = "compartment,zone\n0,bottom\n2,top\n1,bottom\n";
+        assert_eq!(Zoning::from_csv(csv).unwrap(), zoning());
+    }
+
+    #[test]
+    fn test_weighted_mean() {
+        let c = array![[1., 3., 5.], [2., 2., 2.]];
+        let v = array![[1., 3., 2.], [1., 1., 1.]];
+        let z = zoning();
+        assert_eq!(z.sum(&v.view()), array![[4., 2.], [2., 1.]]);
+        assert_eq!(
+            z.weighted_mean(&c.view(), &v.view()),
+            array![[2.5, 5.], [2., 2.]]
+        );
+        let var = z.weighted_variance(&c.view(), &v.view());
+        assert_eq!(var, array![[3., 0.], [0., 0.]]);
+    }
+}
@@ -1,10 +1,29 @@
-use bcore::api::ModelEstimator;
-use bcore::Weight;
-use bcore::{PostProcess, PostProcessReader};
+use bcore::api::{
+    GrowthEstimator, ModelEstimator, ParticleTable, Resampler, SpeciesKey, SpectralAnalyzer,
+    SteadyStateDetector, TimeIntegrator, ZoneReader,
+};
+use bcore::error::ApiError;
+use bcore::experimental::ExperimentalData;
+use bcore::export::ExportUnits;
+use bcore::fit::{FitResult, Model};
+use bcore::units::{Quantity, Unit};
+use bcore::ensemble::{Ensemble as CoreEnsemble, DEFAULT_LEVEL};
+use bcore::study::{Cell, Kpi, Study, StudyTable};
+use bcore::zoning::Zoning;
+use bcore::{Band, GrowthRate, Method, Resampled, Rule, TallyTable, Weight};
+use bcore::{Detector, Periodogram, SteadyState};
+use bcore::{FinalSummary, InitialParameters, RunMetadata};
+use bcore::{MinMax, ParticleChunks, Reducer, Sum, Welford};
+use bcore::{
+    Comparison, Diagnostic, OpenMode, ParticleFilter, PostProcess, PostProcessReader,
+    PostProcessView,
+};
+use numpy::ndarray::{ArrayView1, Ix1, Ix2, Ix3};
 use numpy::PyArray2;
-use numpy::{PyArray1, PyArray3};
+use numpy::{PyArray, PyArray1, PyArray3};
 use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
 /// A struct that wraps the `PostProcess` type for Python bindings.
 ///
 /// The `PythonPostProcess` struct is designed to provide a Python interface for the
@@ -27,6 +46,125 @@ struct PythonPostProcess {
     inner: PostProcess,
 }
 
+/// A predicate selecting particles from their properties, combinable with `&`, `|` and `~`.
+///
+/// # Example
+///
+/// ```python
+/// old = ParticleFilter("age", ">", 3600)
+/// slow = ParticleFilter("nu_meta", "<", 0.5)
+/// mean, fraction = post_process.get_population_mean_filtered("nu_eff", 10, old & ~slow)
+/// ```
+#[derive(Debug, Clone)]
+#[pyclass(name = "ParticleFilter")]
+struct PythonParticleFilter {
+    inner: ParticleFilter,
+}
+
+#[pymethods]
+impl PythonParticleFilter {
+    /// Creates a filter comparing a property to a threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `key` (`str`): Name of the property, stored or derived.
+    /// * `op` (`str`): One of `<`, `<=`, `>`, `>=`, `==` and `!=`.
+    /// * `value` (`float`): Threshold.
+    #[new]
+    fn new(key: &str, op: &str, value: f64) -> PyResult<Self> {
+        let op = op
+            .parse::<Comparison>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Self {
+            inner: ParticleFilter::compare(key, op, value),
+        })
+    }
+
+    fn __and__(&self, other: &Self) -> Self {
+        Self {
+            inner: self.inner.clone().and(other.inner.clone()),
+        }
+    }
+
+    fn __or__(&self, other: &Self) -> Self {
+        Self {
+            inner: self.inner.clone().or(other.inner.clone()),
+        }
+    }
+
+    fn __invert__(&self) -> Self {
+        Self {
+            inner: self.inner.clone().not(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner)
+    }
+}
+
+/// Named groups of compartments used to aggregate results by reactor zone.
+///
+/// # Example
+///
+/// ```python
+/// zoning = Zoning({"impeller": [0, 1, 2], "top": list(range(3, 50))})
+/// zoning = Zoning.from_file("zoning.json")
+/// c = post_process.get_zone_concentrations(zoning, Phase.Liquid)
+/// ```
+#[derive(Debug, Clone)]
+#[pyclass(name = "Zoning")]
+struct PythonZoning {
+    inner: Zoning,
+}
+
+#[pymethods]
+impl PythonZoning {
+    /// Creates a zoning from a dict mapping zone names to compartment indices
+    #[new]
+    #[pyo3(signature = (zones=None))]
+    fn new(zones: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let mut inner = Zoning::new();
+        if let Some(zones) = zones {
+            for (name, compartments) in zones.iter() {
+                inner
+                    .add_zone(&name.extract::<String>()?, compartments.extract()?)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+            }
+        }
+        Ok(Self { inner })
+    }
+
+    /// Loads a zoning from a `.json` file or a `.csv` file with a `compartment,zone` header
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        Zoning::from_file(path)
+            .map(|inner| Self { inner })
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    fn add_zone(&mut self, name: &str, compartments: Vec<usize>) -> PyResult<()> {
+        self.inner
+            .add_zone(name, compartments)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn names(&self) -> Vec<String> {
+        self.inner.names()
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        self.inner
+            .to_json()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Zoning({:?})", self.inner.names())
+    }
+}
+
 /// An enum representing different phases .
 /// # Example
 /// ```python
@@ -89,6 +227,307 @@ impl From<bcore::api::Estimator> for Estimator {
     }
 }
 
+/// A species given either by its index or by its name
+///
+/// # Example
+/// ```python
+/// glucose = post_process.get_spatial_average_concentration("glucose", Phase.Liquid)
+/// same = post_process.get_spatial_average_concentration(0, Phase.Liquid)
+/// ```
+#[derive(FromPyObject)]
+enum SpeciesArg {
+    Index(usize),
+    Name(String),
+}
+
+impl SpeciesKey for SpeciesArg {
+    fn species_index(&self, names: &[String]) -> Result<usize, ApiError> {
+        match self {
+            SpeciesArg::Index(i) => i.species_index(names),
+            SpeciesArg::Name(name) => name.species_index(names),
+        }
+    }
+}
+
+/// Converts a serialized JSON document to the equivalent Python object
+fn json_to_py(py: Python<'_>, json: &str) -> PyResult<PyObject> {
+    let obj = py.import("json")?.call_method1("loads", (json,))?;
+    Ok(obj.unbind())
+}
+
+/// Converts diagnostics to a list of dict with keys `severity`, `file`, `object` and `message`
+fn diagnostics_to_py(py: Python<'_>, diagnostics: &[Diagnostic]) -> PyResult<PyObject> {
+    let list = PyList::empty(py);
+    for d in diagnostics {
+        let item = PyDict::new(py);
+        item.set_item("severity", format!("{:?}", d.severity))?;
+        item.set_item("file", &d.file)?;
+        item.set_item("object", &d.object)?;
+        item.set_item("message", &d.message)?;
+        list.append(item)?;
+    }
+    Ok(list.into_any().unbind())
+}
+
+/// Checks the consistency of the files of a run without opening it
+///
+/// # Arguments
+///
+/// * `folder` (`str`): Name of the folder containing the simulation results.
+/// * `root` (`Option<String>`): Optional root directory.
+///
+/// # Returns
+///
+/// * `list[dict]`: Diagnostics with keys `severity`, `file`, `object` and `message`, empty if the
+///   run is consistent.
+///
+/// # Example
+///
+/// ```python
+/// for d in validate("path/to/folder", "optional/root"):
+///     print(d["severity"], d["object"], d["message"])
+/// ```
+#[pyfunction]
+#[pyo3(signature = (folder, root=None))]
+fn validate(py: Python<'_>, folder: &str, root: Option<String>) -> PyResult<PyObject> {
+    diagnostics_to_py(py, &bcore::validate(folder, root))
+}
+
+fn parse_unit(unit: &str) -> PyResult<Unit> {
+    unit.parse::<Unit>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Builds a steady-state detector from its name, `slope`, `mser` or `geweke`
+fn parse_detector(method: &str, window: usize, threshold: f64, batch: usize) -> PyResult<Detector> {
+    match method {
+        "slope" => Ok(Detector::slope(window, threshold)),
+        "mser" => Ok(Detector::Mser { batch }),
+        "geweke" => Ok(Detector::geweke()),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown method '{}', expected slope, mser or geweke",
+            method
+        ))),
+    }
+}
+
+/// Converts a steady state to a dict, `None` if the series is not steady
+fn steady_state_to_py(py: Python<'_>, steady: Option<SteadyState>) -> PyResult<PyObject> {
+    let Some(steady) = steady else {
+        return Ok(py.None());
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("onset", steady.onset)?;
+    dict.set_item("time", steady.time)?;
+    dict.set_item("mean", steady.mean)?;
+    dict.set_item("std_error", steady.std_error)?;
+    dict.set_item("lower", steady.lower)?;
+    dict.set_item("upper", steady.upper)?;
+    dict.set_item("level", steady.level)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Converts the steady state of every species to a dict keyed by species name
+fn species_steady_state_to_py(
+    py: Python<'_>,
+    species: Vec<(String, Option<SteadyState>)>,
+) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    for (name, steady) in species {
+        dict.set_item(name, steady_state_to_py(py, steady)?)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+fn parse_rule(rule: &str) -> PyResult<Rule> {
+    rule.parse::<Rule>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+fn parse_method(method: &str) -> PyResult<Method> {
+    method
+        .parse::<Method>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Frequencies and power spectral density of a periodogram
+type PyPeriodogram = (Py<PyArray1<f64>>, Py<PyArray1<f64>>);
+
+fn periodogram_to_py(py: Python<'_>, periodogram: Periodogram) -> PyPeriodogram {
+    (
+        PyArray1::from_owned_array(py, periodogram.frequency).unbind(),
+        PyArray1::from_owned_array(py, periodogram.power).unbind(),
+    )
+}
+
+/// Times and values of a resampled series
+type PyResampled<D> = (Py<PyArray1<f64>>, Py<PyArray<f64, D>>);
+
+/// Converts resampled values to a tuple `(time, values)`
+fn resampled_to_py<D: numpy::ndarray::Dimension>(
+    py: Python<'_>,
+    resampled: Resampled<D>,
+) -> PyResampled<D> {
+    (
+        PyArray1::from_owned_array(py, resampled.time).unbind(),
+        PyArray::from_owned_array(py, resampled.values).unbind(),
+    )
+}
+
+/// Converts values from one unit to another.
+///
+/// # Arguments
+///
+/// * `values` (`np.ndarray`): Values expressed in `from_unit`.
+/// * `from_unit` (`str`): Unit symbol of the values, e.g. `"s"` or `"kg/m3"`.
+/// * `to_unit` (`str`): Unit symbol to convert to, e.g. `"h"` or `"g/L"`.
+///
+/// # Example
+///
+/// ```python
+/// t_h = convert(post_process.time, "s", "h")
+/// ```
+#[pyfunction]
+fn convert(
+    py: Python<'_>,
+    values: Vec<f64>,
+    from_unit: &str,
+    to_unit: &str,
+) -> PyResult<Py<PyArray1<f64>>> {
+    let e = parse_unit(from_unit)?
+        .convert_array(&numpy::ndarray::Array1::from_vec(values), parse_unit(to_unit)?)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(PyArray1::from_owned_array(py, e).unbind())
+}
+
+/// Compares the time series of two runs on the union of their export times.
+///
+/// # Returns
+///
+/// * `dict`: `time` and, for each series (`biomass`, `liquid:<species>`, `gas:<species>`,
+///   `mtr:<species>`), a dict with `a`, `b`, `abs_diff`, `rel_diff`, `rmse` and `max_abs_diff`.
+///
+/// # Example
+///
+/// ```python
+/// c = compare(before, after)
+/// print(c["liquid:glucose"]["rmse"])
+/// ```
+#[pyfunction]
+fn compare(py: Python<'_>, a: &PythonPostProcess, b: &PythonPostProcess) -> PyResult<PyObject> {
+    let comparison = bcore::compare::compare(&a.inner, &b.inner)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+    let dict = PyDict::new(py);
+    dict.set_item("time", PyArray1::from_vec(py, comparison.time.clone()))?;
+    for series in comparison.series {
+        let item = PyDict::new(py);
+        item.set_item("abs_diff", PyArray1::from_owned_array(py, series.abs_diff()))?;
+        item.set_item("rel_diff", PyArray1::from_owned_array(py, series.rel_diff()))?;
+        item.set_item("rmse", series.rmse())?;
+        item.set_item("max_abs_diff", series.max_abs_diff())?;
+        item.set_item("a", PyArray1::from_owned_array(py, series.a))?;
+        item.set_item("b", PyArray1::from_owned_array(py, series.b))?;
+        dict.set_item(series.name, item)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Compares the distributions of a property at export times shared by two runs.
+///
+/// # Returns
+///
+/// * `dict`: Arrays `time`, `i_export_a`, `i_export_b`, `wasserstein` and `ks`.
+#[pyfunction]
+fn compare_distributions(
+    py: Python<'_>,
+    a: &PythonPostProcess,
+    b: &PythonPostProcess,
+    key: &str,
+) -> PyResult<PyObject> {
+    let distances = bcore::compare::compare_distributions(&a.inner, &b.inner, key)
+        .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+    let dict = PyDict::new(py);
+    let column = |f: fn(&bcore::compare::DistributionDistance) -> f64| {
+        PyArray1::from_vec(py, distances.iter().map(f).collect())
+    };
+    dict.set_item("time", column(|d| d.time))?;
+    dict.set_item("i_export_a", distances.iter().map(|d| d.i_export_a).collect::<Vec<_>>())?;
+    dict.set_item("i_export_b", distances.iter().map(|d| d.i_export_b).collect::<Vec<_>>())?;
+    dict.set_item("wasserstein", column(|d| d.wasserstein))?;
+    dict.set_item("ks", column(|d| d.ks))?;
+    Ok(dict.into_any().unbind())
+}
+
+fn parse_model(model: &str) -> PyResult<Model> {
+    model
+        .parse::<Model>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Converts a fit to a dict, parameters and standard errors are dicts keyed by parameter name
+fn fit_result_to_py(py: Python<'_>, result: &FitResult) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    let parameters = PyDict::new(py);
+    let std_errors = PyDict::new(py);
+    for ((name, p), e) in result
+        .model
+        .parameter_names()
+        .iter()
+        .zip(&result.parameters)
+        .zip(result.std_errors())
+    {
+        parameters.set_item(name, p)?;
+        std_errors.set_item(name, e)?;
+    }
+    dict.set_item("model", result.model.to_string())?;
+    dict.set_item("parameters", parameters)?;
+    dict.set_item("std_errors", std_errors)?;
+    dict.set_item(
+        "covariance",
+        PyArray2::from_owned_array(py, result.covariance.clone()),
+    )?;
+    dict.set_item("rss", result.rss)?;
+    dict.set_item("rmse", result.rmse)?;
+    dict.set_item("r2", result.r2)?;
+    dict.set_item("n", result.n)?;
+    dict.set_item("converged", result.converged)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Fits a kinetic model to `y(x)` by (weighted) nonlinear least squares.
+///
+/// # Arguments
+///
+/// * `model` (`str`): `exponential`, `logistic`, `gompertz` or `monod`.
+/// * `x`, `y` (`list[float]`): Data, non finite points are ignored.
+/// * `weights` (`list[float]`, optional): Weight of each point, typically `1 / std**2`.
+///
+/// # Returns
+///
+/// * `dict`: `model`, `parameters`, `std_errors`, `covariance`, `rss`, `rmse`, `r2`, `n` and
+///   `converged`.
+///
+/// # Example
+///
+/// ```python
+/// r = fit("logistic", pp.time, pp.get_spatial_average_biomass_concentration())
+/// print(r["parameters"]["mu"], "+/-", r["std_errors"]["mu"])
+/// ```
+#[pyfunction]
+#[pyo3(signature = (model, x, y, weights=None))]
+fn fit(
+    py: Python<'_>,
+    model: &str,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    weights: Option<Vec<f64>>,
+) -> PyResult<PyObject> {
+    match bcore::fit::fit(parse_model(model)?, &x, &y, weights.as_deref()) {
+        Ok(r) => fit_result_to_py(py, &r),
+        Err(e) => Err(PyValueError::new_err(e.to_string())),
+    }
+}
+
 #[pymethods]
 impl PythonPostProcess {
     /// Creates a new instance of `PythonPostProcess`.
@@ -100,6 +539,7 @@ impl PythonPostProcess {
     ///
     /// * `folder` (`str`): A path to the folder where the post-processing files or resources are located.
     /// * `root` (`Option<String>`): An optional root path used for additional processing logic, or `None` if not provided.
+    /// * `lenient` (`bool`): Open an incomplete run by truncating it to its last consistent export.
     ///
     /// # Returns
     ///
@@ -117,13 +557,18 @@ impl PythonPostProcess {
     /// If the `PostProcess::new` function fails (e.g., due to invalid paths or other internal errors),
     /// this function returns a `PyValueError` with the message `"Error creating object"`.
     #[new]
-    #[pyo3(signature = (folder, root=None))]
-    fn new(folder: &str, root: Option<String>) -> PyResult<Self> {
+    #[pyo3(signature = (folder, root=None, lenient=false))]
+    fn new(folder: &str, root: Option<String>, lenient: bool) -> PyResult<Self> {
         // if let Ok(pp) = PostProcess::new(folder, root) {
         //     return Ok(Self { inner: pp });
         // }
+        let mode = if lenient {
+            OpenMode::Lenient
+        } else {
+            OpenMode::Strict
+        };
 
-        match PostProcess::new(folder, root) {
+        match PostProcess::open(folder, root, mode) {
             Ok(pp) => Ok(Self { inner: pp }),
             Err(err) => {
                 println!("{:?}", err);
@@ -132,6 +577,274 @@ impl PythonPostProcess {
         }
     }
 
+    /// Gets the recovery report of a run opened with `lenient=True`
+    ///
+    /// # Returns
+    ///
+    /// * `dict | None`: What has been dropped while opening the run, `None` in strict mode.
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// post_process = PostProcess("path/to/folder", lenient=True)
+    /// report = post_process.recovery_report
+    /// print(report["missing_ranks"], report["n_export_kept"])
+    /// ```
+    #[getter]
+    fn recovery_report(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        match self.inner.recovery_report() {
+            Some(report) => {
+                let json = report
+                    .to_json()
+                    .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+                Ok(Some(json_to_py(py, &json)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Iterates over the particles of an export by chunks of at most `chunk_size` particles.
+    ///
+    /// Each chunk is a dict of arrays keyed by property, with the partial file index under
+    /// `rank`. Particles are read rank after rank, so the memory used is bounded by the chunk.
+    ///
+    /// # Example
+    /// ```python
+    /// total = 0.0
+    /// for chunk in post_process.particle_chunks(10, ["mass", "age"], 1_000_000):
+    ///     total += chunk["mass"].sum()
+    /// ```
+    #[pyo3(signature = (i_export, keys, chunk_size=1_000_000))]
+    fn particle_chunks(
+        &self,
+        i_export: usize,
+        keys: Vec<String>,
+        chunk_size: usize,
+    ) -> PyResult<PythonParticleChunks> {
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        match self.inner.particle_chunks(i_export, &keys, chunk_size) {
+            Ok(inner) => Ok(PythonParticleChunks { inner }),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Statistics of a property at an export computed chunk by chunk, without loading every
+    /// particle. Returns a dict with `n`, `sum`, `mean`, `variance`, `std`, `min` and `max`.
+    #[pyo3(signature = (i_export, key, chunk_size=1_000_000))]
+    fn chunked_statistics(
+        &self,
+        py: Python<'_>,
+        i_export: usize,
+        key: &str,
+        chunk_size: usize,
+    ) -> PyResult<PyObject> {
+        let to_py_err = |e: ApiError| PyErr::new::<PyRuntimeError, _>(e.to_string());
+        let chunks = self
+            .inner
+            .particle_chunks(i_export, &[key], chunk_size)
+            .map_err(to_py_err)?;
+        let (mut sum, mut extrema, mut moments) =
+            (Sum::default(), MinMax::default(), Welford::default());
+        for chunk in chunks {
+            let chunk = chunk.map_err(to_py_err)?;
+            let values = chunk.values.column(0);
+            sum.update(values);
+            extrema.update(values);
+            moments.update(values);
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("n", sum.n)?;
+        dict.set_item("sum", sum.sum)?;
+        dict.set_item("mean", moments.mean())?;
+        dict.set_item("variance", moments.variance())?;
+        dict.set_item("std", moments.std())?;
+        dict.set_item("min", extrema.min)?;
+        dict.set_item("max", extrema.max)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Particle counts and storage of each rank, read from the partial files.
+    ///
+    /// Returns a dict with `ranks`, `number_particle` (n_rank, nt), `number_particle_compartment`
+    /// (n_rank, nt, n_compartment), `export_size` in bytes (n_rank, n_export_bio), `imbalance`
+    /// (max / mean of the rank counts at each export), `max_imbalance`, `particles_per_thread`
+    /// and `total_export_size`.
+    fn rank_report(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let report = self
+            .inner
+            .rank_report()
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let dict = PyDict::new(py);
+        dict.set_item("n_rank", report.n_rank)?;
+        dict.set_item("n_node_thread", report.n_node_thread)?;
+        dict.set_item("ranks", report.ranks.clone())?;
+        dict.set_item(
+            "number_particle",
+            PyArray2::from_owned_array(py, report.number_particle()),
+        )?;
+        dict.set_item("imbalance", PyArray1::from_owned_array(py, report.imbalance()))?;
+        dict.set_item("max_imbalance", report.max_imbalance())?;
+        dict.set_item(
+            "particles_per_thread",
+            PyArray1::from_owned_array(py, report.particles_per_thread()),
+        )?;
+        dict.set_item(
+            "total_export_size",
+            PyArray1::from_owned_array(py, report.total_export_size()),
+        )?;
+        dict.set_item(
+            "number_particle_compartment",
+            PyArray3::from_owned_array(py, report.number_particle_compartment),
+        )?;
+        dict.set_item("export_size", PyArray2::from_owned_array(py, report.export_size))?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Reads the exports written since the last read of a running simulation
+    ///
+    /// # Returns
+    ///
+    /// * `tuple[int, int] | None`: Range `(start, stop)` of the new exports, `None` if nothing new.
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// post_process = PostProcess("running", lenient=True)
+    /// while not post_process.is_finished:
+    ///     if (new := post_process.refresh()) is not None:
+    ///         print(post_process.time[new[0]:new[1]])
+    ///     time.sleep(10)
+    /// ```
+    fn refresh(&mut self) -> PyResult<Option<(usize, usize)>> {
+        match self.inner.refresh() {
+            Ok(update) => Ok(update.map(|u| (u.exports.start, u.exports.end))),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    #[getter]
+    fn is_finished(&self) -> bool {
+        self.inner.is_finished()
+    }
+
+    /// Parameters of the simulation at its start as a dict, e.g. `delta_time` or
+    /// `number_particles`.
+    fn get_initial_parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        initial_parameters_to_py(py, &self.inner.get_initial_parameters())
+    }
+
+    /// Results written at the end of the simulation as a dict with `events` and
+    /// `number_particles`, None while the simulation is running.
+    fn get_final_summary(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        self.inner
+            .get_final_summary()
+            .map(|f| final_summary_to_py(py, &f))
+            .transpose()
+    }
+
+    /// Parallel layout and shapes of the run as a dict.
+    fn get_run_metadata(&self, py: Python<'_>) -> PyResult<PyObject> {
+        run_metadata_to_py(py, &self.inner.get_run_metadata())
+    }
+
+    /// Checks the consistency of the files of the run, see `validate`
+    fn validate(&self, py: Python<'_>) -> PyResult<PyObject> {
+        diagnostics_to_py(py, &self.inner.validate())
+    }
+
+    /// Gets the names of the species, ordered as the last axis of concentrations
+    #[getter]
+    fn species_names(&self) -> Vec<String> {
+        self.inner.species_names()
+    }
+
+    /// Sets the names of the species, used when the result file does not store them
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// post_process.set_species_names(["glucose", "o2", "acetate", "co2"])
+    /// ```
+    fn set_species_names(&mut self, names: Vec<String>) -> PyResult<()> {
+        self.inner
+            .set_species_names(names)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Registers a property derived from stored properties
+    ///
+    /// The derived property can be used wherever a property key is accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` (`str`): Name of the new property.
+    /// * `expression` (`str`): Expression over property names using `+ - * / ^`, `log`, `exp`,
+    ///   `sqrt` and `abs`.
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// post_process.register_property("active_mass", "mass * nu_eff")
+    /// post_process.get_population_mean("active_mass", 10)
+    /// ```
+    fn register_property(&mut self, name: &str, expression: &str) -> PyResult<()> {
+        self.inner
+            .register_property(name, expression)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Exports the spatial average concentration of every species as CSV labelled by species name
+    ///
+    /// Headers carry the unit of each column, e.g. `time [h]` and `glucose [g/L]`.
+    #[pyo3(signature = (phase, time_unit="s", concentration_unit="kg/m3"))]
+    fn get_csv_concentrations(
+        &self,
+        phase: Phase,
+        time_unit: &str,
+        concentration_unit: &str,
+    ) -> PyResult<String> {
+        let units = ExportUnits {
+            time: parse_unit(time_unit)?,
+            concentration: parse_unit(concentration_unit)?,
+        };
+        bcore::export::concentrations_to_csv(&self.inner, phase.into(), units)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Returns the unit symbol in which a kind of series is returned
+    ///
+    /// # Arguments
+    ///
+    /// * `quantity` (`str`): One of `time`, `volume`, `mass`, `concentration`, `growth_rate`,
+    ///   `mtr` or `count`.
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// plt.xlabel(f"time [{post_process.unit('time')}]")
+    /// ```
+    fn unit(&self, quantity: &str) -> PyResult<String> {
+        let quantity = quantity
+            .parse::<Quantity>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(self.inner.unit(quantity).symbol().to_string())
+    }
+
+    /// Returns the time converted to the given unit
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// t_h = post_process.time_in("h")
+    /// ```
+    fn time_in(&self, py: Python<'_>, unit: &str) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self
+            .inner
+            .time_in(parse_unit(unit)?)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
     fn get_property_names(&self) -> PyResult<Vec<String>> {
         Ok(self.inner.get_property_names())
     }
@@ -187,6 +900,23 @@ impl PythonPostProcess {
         self.inner.get_max_n_export_bio()
     }
 
+    /// Exports at which biological data have been written, as indices of `time`
+    fn get_bio_exports(&self) -> Vec<usize> {
+        self.inner.get_bio_exports()
+    }
+
+    /// Time of each export returned by `get_bio_exports`
+    fn get_bio_export_time(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        PyArray1::from_owned_array(py, self.inner.get_bio_export_time()).unbind()
+    }
+
+    /// Biological export nearest to a time (s), to pass to e.g. `get_properties`
+    fn nearest_bio_export(&self, time: f64) -> PyResult<usize> {
+        self.inner
+            .nearest_bio_export(time)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
     #[getter]
     fn weight(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
         match self.inner.weight() {
@@ -198,20 +928,21 @@ impl PythonPostProcess {
     fn get_spatial_average_concentration(
         &self,
         py: Python<'_>,
-        species: usize,
+        species: SpeciesArg,
         phase: Phase,
-    ) -> Py<PyArray1<f64>> {
+    ) -> PyResult<Py<PyArray1<f64>>> {
         let e = self
             .inner
-            .get_spatial_average_concentration(species, phase.into());
+            .get_spatial_average_concentration(species, phase.into())
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
 
-        PyArray1::from_owned_array(py, e).unbind()
+        Ok(PyArray1::from_owned_array(py, e).unbind())
     }
 
     fn get_variance_concentration(
         &self,
         py: Python<'_>,
-        species: usize,
+        species: SpeciesArg,
         phase: Phase,
     ) -> Py<PyArray1<f64>> {
         if let Ok(e) = self.inner.get_variance_concentration(species, phase.into()) {
@@ -221,7 +952,7 @@ impl PythonPostProcess {
         }
     }
 
-    fn get_spatial_average_mtr(&self, py: Python<'_>, species: usize) -> Py<PyArray1<f64>> {
+    fn get_spatial_average_mtr(&self, py: Python<'_>, species: SpeciesArg) -> Py<PyArray1<f64>> {
         match self.inner.get_spatial_average_mtr(species) {
             Ok(e) => PyArray1::from_owned_array(py, e).unbind(),
             Err(e) => panic!("{}", e),
@@ -234,27 +965,28 @@ impl PythonPostProcess {
         PyArray3::from_owned_array(py, e.to_owned()).unbind()
     }
 
+    /// Running time average of the concentration in the compartment `position`, from the first
+    /// export up to each export.
     fn get_time_average_concentration(
         &self,
         py: Python<'_>,
-        species: usize,
+        species: SpeciesArg,
         position: usize,
         phase: Phase,
-    ) -> Py<PyArray1<f64>> {
-        if let Ok(e) = self
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self
             .inner
             .get_time_average_concentration(species, position, phase.into())
         {
-            return PyArray1::from_owned_array(py, e).unbind();
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
         }
-        panic!("")
     }
 
-    fn get_spatial_property(&self, py: Python<'_>, name: &str) -> Py<PyArray2<f64>> {
-        // TODO
+    fn get_spatial_property(&self, py: Python<'_>, name: &str) -> PyResult<Py<PyArray2<f64>>> {
         match self.inner.get_spatial_average_property(name) {
-            Ok(e) => PyArray2::from_owned_array(py, e).unbind(),
-            Err(e) => panic!("{}", e),
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyValueError::new_err(e.to_string())),
         }
     }
 
@@ -290,6 +1022,29 @@ impl PythonPostProcess {
         PyArray1::from_owned_array(py, e.unwrap()).unbind() //TODO
     }
 
+    /// Reads several properties at an export in a single pass over the partial files.
+    ///
+    /// Returns a dict with one array per key, plus `rank` (partial file index) and `index` (index
+    /// within the partial file) of each particle. Arrays are handed over without copy.
+    ///
+    /// # Example
+    /// ```python
+    /// table = post_process.get_particle_table(["mass", "age"], 10)
+    /// np.corrcoef(table["mass"], table["age"])
+    /// ```
+    fn get_particle_table(
+        &self,
+        py: Python<'_>,
+        keys: Vec<String>,
+        i_export: usize,
+    ) -> PyResult<PyObject> {
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        match self.inner.get_particle_table(&keys, i_export) {
+            Ok(table) => particle_table_to_py(py, table),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
     fn get_population_mean(&self, key: &str, i_export: usize) -> PyResult<f64> {
         if let Ok(o) = self.inner.get_population_mean(key, i_export) {
             Ok(o)
@@ -324,6 +1079,118 @@ impl PythonPostProcess {
         }
     }
 
+    /// Fetches a property of the particles selected by a filter
+    ///
+    /// # Returns
+    ///
+    /// * `(np.ndarray, float)`: Values of the selected particles and the fraction selected.
+    fn get_properties_filtered(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        i_export: usize,
+        filter: &PythonParticleFilter,
+    ) -> PyResult<(Py<PyArray1<f64>>, f64)> {
+        match self.inner.get_properties_filtered(key, i_export, &filter.inner) {
+            Ok((values, fraction)) => Ok((PyArray1::from_owned_array(py, values).unbind(), fraction)),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Histogram of a property over the particles selected by a filter
+    ///
+    /// # Returns
+    ///
+    /// * `(np.ndarray, np.ndarray, float)`: Bins, counts and the fraction selected.
+    fn get_histogram_filtered(
+        &self,
+        py: Python<'_>,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+        filter: &PythonParticleFilter,
+    ) -> PyResult<(Py<PyArray1<f64>>, Py<PyArray1<f64>>, f64)> {
+        match self
+            .inner
+            .get_histogram_filtered(n_bins, i_export, key, &filter.inner)
+        {
+            Ok((bins, counts, fraction)) => Ok((
+                PyArray1::from_owned_array(py, bins.into()).unbind(),
+                PyArray1::from_owned_array(py, counts.into()).unbind(),
+                fraction,
+            )),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Mean of a property over the particles selected by a filter
+    ///
+    /// # Returns
+    ///
+    /// * `(float, float)`: The mean, NaN if no particle is selected, and the fraction selected.
+    fn get_population_mean_filtered(
+        &self,
+        key: &str,
+        i_export: usize,
+        filter: &PythonParticleFilter,
+    ) -> PyResult<(f64, f64)> {
+        self.inner
+            .get_population_mean_filtered(key, i_export, &filter.inner)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Estimates a property over the particles selected by a filter
+    ///
+    /// # Returns
+    ///
+    /// * `(float, float)`: The estimate and the fraction selected.
+    fn estimate_filtered(
+        &self,
+        etype: Estimator,
+        key: &str,
+        i_export: usize,
+        filter: &PythonParticleFilter,
+    ) -> PyResult<(f64, f64)> {
+        self.inner
+            .estimate_filtered(etype.into(), key, i_export, &filter.inner)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Restricts the run to the exports whose time lies in `[t_start, t_end)`
+    ///
+    /// Export indices of the view are relative to the window.
+    ///
+    /// # Example
+    ///
+    /// ```python
+    /// last_hour = post_process.view(post_process.time[-1] - 3600, float("inf"))
+    /// x = last_hour.get_biomass_concentration()
+    /// ```
+    fn view(slf: PyRef<'_, Self>, t_start: f64, t_end: f64) -> PyResult<PythonPostProcessView> {
+        let exports = slf
+            .inner
+            .view(t_start..t_end)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .exports();
+        Ok(PythonPostProcessView {
+            parent: slf.into(),
+            start: exports.start,
+            end: exports.end,
+        })
+    }
+
+    /// Restricts the run to the exports `[start, end)`
+    fn exports(slf: PyRef<'_, Self>, start: usize, end: usize) -> PyResult<PythonPostProcessView> {
+        slf.inner
+            .exports(start..end)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(PythonPostProcessView {
+            parent: slf.into(),
+            start,
+            end,
+        })
+    }
+
     pub fn mu_direct(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
         match self.inner.mu_direct() {
             Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
@@ -348,6 +1215,247 @@ impl PythonPostProcess {
         }
     }
 
+    /// Integral over time of a series with one value per export.
+    ///
+    /// `rule` is `trapezoid` or `simpson`, both account for unevenly spaced exports.
+    #[pyo3(signature = (values, rule="trapezoid"))]
+    fn integrate(&self, values: Vec<f64>, rule: &str) -> PyResult<f64> {
+        let rule = parse_rule(rule)?;
+        self.inner
+            .integrate(ArrayView1::from(&values), rule)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Time average over `[start, end]` (s) of a series with one value per export
+    fn window_average(&self, values: Vec<f64>, start: f64, end: f64) -> PyResult<f64> {
+        self.inner
+            .window_average(ArrayView1::from(&values), start, end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Area under the spatial average concentration since the first export (kg.s/m3)
+    fn cumulative_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self.inner.cumulative_concentration(species, phase.into()) {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Area under the spatial average biomass concentration since the first export (kg.s/m3)
+    fn cumulative_biomass_concentration(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        match self.inner.cumulative_biomass_concentration() {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Mass transferred per unit volume since the first export (kg/m3)
+    fn cumulative_mtr(&self, py: Python<'_>, species: SpeciesArg) -> PyResult<Py<PyArray1<f64>>> {
+        match self.inner.cumulative_mtr(species) {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Time average over `[start, end]` (s) of the spatial average concentration
+    fn window_average_concentration(
+        &self,
+        species: SpeciesArg,
+        phase: Phase,
+        start: f64,
+        end: f64,
+    ) -> PyResult<f64> {
+        self.inner
+            .window_average_concentration(species, phase.into(), start, end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Time average over `[start, end]` (s) of the spatial average biomass concentration
+    fn window_average_biomass_concentration(&self, start: f64, end: f64) -> PyResult<f64> {
+        self.inner
+            .window_average_biomass_concentration(start, end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Time average over `[start, end]` (s) of the spatial average mass transfer rate
+    fn window_average_mtr(&self, species: SpeciesArg, start: f64, end: f64) -> PyResult<f64> {
+        self.inner
+            .window_average_mtr(species, start, end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Steady state of a series with one value per export.
+    ///
+    /// `method` is `slope` (every window of `window` exports changes by less than `threshold`
+    /// relative to its mean), `mser` (batches of `batch` exports) or `geweke`.
+    ///
+    /// # Returns
+    ///
+    /// * `dict | None`: `onset` (export index), `time`, `mean`, `std_error`, `lower`, `upper`
+    ///   and `level`, or `None` if the series does not reach a steady state.
+    #[pyo3(signature = (values, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state(
+        &self,
+        py: Python<'_>,
+        values: Vec<f64>,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        match self
+            .inner
+            .steady_state(ArrayView1::from(&values), detector, level)
+        {
+            Ok(steady) => steady_state_to_py(py, steady),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Steady state of the spatial average biomass concentration, see `steady_state`.
+    #[pyo3(signature = (method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    fn steady_state_biomass(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        match self.inner.steady_state_biomass(detector, level) {
+            Ok(steady) => steady_state_to_py(py, steady),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Steady state of the spatial average concentration of every species, keyed by name.
+    #[pyo3(signature = (phase, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state_species(
+        &self,
+        py: Python<'_>,
+        phase: Phase,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        match self
+            .inner
+            .steady_state_species(phase.into(), detector, level)
+        {
+            Ok(species) => species_steady_state_to_py(py, species),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Steady state of the population mean of a particle property, see `steady_state`.
+    #[pyo3(signature = (key, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state_population_mean(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        match self
+            .inner
+            .steady_state_population_mean(key, detector, level)
+        {
+            Ok(steady) => steady_state_to_py(py, steady),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Periodogram of the concentration of a species in one compartment.
+    ///
+    /// Non-uniform export times are first interpolated onto a uniform grid.
+    ///
+    /// # Returns
+    ///
+    /// * `(np.ndarray, np.ndarray)`: Frequencies (Hz) and power spectral density.
+    fn concentration_periodogram(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        compartment: usize,
+    ) -> PyResult<PyPeriodogram> {
+        self.inner
+            .concentration_periodogram(species, phase.into(), compartment)
+            .map(|p| periodogram_to_py(py, p))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Dominant period (s) of the concentration in each compartment, NaN without oscillation
+    fn dominant_period_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self.inner.dominant_period_concentration(species, phase.into()) {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Autocorrelation time (s) of the concentration in each compartment
+    fn autocorrelation_time_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        match self
+            .inner
+            .autocorrelation_time_concentration(species, phase.into())
+        {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Circulation time (s): median over the compartments of the dominant period
+    fn circulation_time(&self, species: SpeciesArg, phase: Phase) -> PyResult<f64> {
+        self.inner
+            .circulation_time(species, phase.into())
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Periodogram of the population mean of a particle property, see
+    /// `concentration_periodogram`.
+    fn population_mean_periodogram(&self, py: Python<'_>, key: &str) -> PyResult<PyPeriodogram> {
+        self.inner
+            .population_mean_periodogram(key)
+            .map(|p| periodogram_to_py(py, p))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Autocorrelation time (s) of the population mean of a particle property
+    fn autocorrelation_time_population_mean(&self, key: &str) -> PyResult<f64> {
+        self.inner
+            .autocorrelation_time_population_mean(key)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
     pub fn estimate_time(
         &self,
         py: Python<'_>,
@@ -376,14 +1484,1290 @@ impl PythonPostProcess {
             None => panic!("No data"),
         }
     }
-}
 
-#[pymodule]
-mod biomc_pp {
-    #[pymodule_export]
-    use super::Estimator;
-    #[pymodule_export]
-    use super::Phase;
-    #[pymodule_export]
-    use super::PythonPostProcess;
+    /// Tallies as a dict of arrays keyed by event name, with the export times under `time`.
+    ///
+    /// `kind` is `cumulative` (counts since the start), `increments` (counts between exports) or
+    /// `rates` (counts per second between exports). With `scaled`, counts are multiplied by the
+    /// particle weight.
+    #[pyo3(signature = (kind="cumulative", scaled=false))]
+    fn get_tally_table(&self, py: Python<'_>, kind: &str, scaled: bool) -> PyResult<PyObject> {
+        let table = scale_tally_table(self.inner.get_tally_table(), self.inner.weight(), scaled);
+        match table {
+            Ok(t) => tally_table_to_py(py, &t, kind),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Concentration fields resampled onto a time grid.
+    ///
+    /// `method` is `linear`, `previous`, `cubic` (monotone) or `bin_average`, in which case `grid`
+    /// holds the edges of the bins and the returned times are the bin centers.
+    ///
+    /// # Returns
+    ///
+    /// * `(np.ndarray, np.ndarray)`: Times and values, shape (n_grid, n_compartment, n_species).
+    #[pyo3(signature = (phase, grid, method="linear"))]
+    fn resample_concentrations(
+        &self,
+        py: Python<'_>,
+        phase: Phase,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix3>> {
+        let method = parse_method(method)?;
+        self.inner
+            .resample_concentrations(phase.into(), &grid, method)
+            .map(|r| resampled_to_py(py, r))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Biomass concentration resampled onto a time grid, see `resample_concentrations`.
+    #[pyo3(signature = (grid, method="linear"))]
+    fn resample_biomass_concentration(
+        &self,
+        py: Python<'_>,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix2>> {
+        let method = parse_method(method)?;
+        self.inner
+            .resample_biomass_concentration(&grid, method)
+            .map(|r| resampled_to_py(py, r))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// `estimate_time` resampled onto a time grid, see `resample_concentrations`.
+    #[pyo3(signature = (etype, key, grid, method="linear"))]
+    fn resample_estimate(
+        &self,
+        py: Python<'_>,
+        etype: Estimator,
+        key: &str,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix1>> {
+        let method = parse_method(method)?;
+        self.inner
+            .resample_estimate(etype.into(), key, &grid, method)
+            .map(|r| resampled_to_py(py, r))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Cumulative tallies resampled onto a time grid, columns ordered as in `get_tally_table`.
+    #[pyo3(signature = (grid, method="linear"))]
+    fn resample_tallies(
+        &self,
+        py: Python<'_>,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix2>> {
+        let method = parse_method(method)?;
+        self.inner
+            .resample_tallies(&grid, method)
+            .map(|r| resampled_to_py(py, r))
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Volume of each zone, shape (nt, n_zone)
+    fn get_zone_volume(
+        &self,
+        py: Python<'_>,
+        zoning: &PythonZoning,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self.inner.get_zone_volume(&zoning.inner, phase.into()) {
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Volume-weighted concentrations of each zone, shape (nt, n_zone, n_species)
+    fn get_zone_concentrations(
+        &self,
+        py: Python<'_>,
+        zoning: &PythonZoning,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray3<f64>>> {
+        match self.inner.get_zone_concentrations(&zoning.inner, phase.into()) {
+            Ok(e) => Ok(PyArray3::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Biomass concentration of each zone, shape (nt, n_zone)
+    fn get_zone_biomass_concentration(
+        &self,
+        py: Python<'_>,
+        zoning: &PythonZoning,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self.inner.get_zone_biomass_concentration(&zoning.inner) {
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Number-weighted mean of a property in each zone, shape (nt, n_zone)
+    fn get_zone_spatial_property(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        zoning: &PythonZoning,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self
+            .inner
+            .get_zone_spatial_average_property(key, &zoning.inner)
+        {
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Number of particles in each zone, shape (nt, n_zone)
+    fn get_zone_number_particle(
+        &self,
+        py: Python<'_>,
+        zoning: &PythonZoning,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self.inner.get_zone_number_particle(&zoning.inner) {
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Volume-weighted variance of a concentration within each zone, shape (nt, n_zone)
+    fn get_zone_variance_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        zoning: &PythonZoning,
+    ) -> PyResult<Py<PyArray2<f64>>> {
+        match self
+            .inner
+            .get_zone_variance_concentration(species, phase.into(), &zoning.inner)
+        {
+            Ok(e) => Ok(PyArray2::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Returns the total mass represented by the particles at each export, `Σ mass·weight`.
+    fn get_total_mass(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        match self.inner.get_total_mass() {
+            Ok(e) => Ok(PyArray1::from_owned_array(py, e).unbind()),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Specific growth rate from a Savitzky-Golay polynomial fitted to the total mass over a
+    /// sliding window of exports. Returns a dict with `mu` and `std_error`.
+    #[pyo3(signature = (window=7, order=2))]
+    fn mu_savitzky_golay(&self, py: Python<'_>, window: usize, order: usize) -> PyResult<PyObject> {
+        match self.inner.mu_savitzky_golay(window, order) {
+            Ok(r) => growth_rate_to_py(py, r),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Specific growth rate from a line fitted to the logarithm of the total mass over a sliding
+    /// window of exports. Returns a dict with `mu` and `std_error`.
+    #[pyo3(signature = (window=5))]
+    fn mu_log_linear(&self, py: Python<'_>, window: usize) -> PyResult<PyObject> {
+        match self.inner.mu_log_linear(window) {
+            Ok(r) => growth_rate_to_py(py, r),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Growth rate in number from a line fitted to the logarithm of `get_growth_in_number` over
+    /// a sliding window of exports. Returns a dict with `mu` and `std_error`.
+    #[pyo3(signature = (window=5))]
+    fn mu_number(&self, py: Python<'_>, window: usize) -> PyResult<PyObject> {
+        match self.inner.mu_number(window) {
+            Ok(r) => growth_rate_to_py(py, r),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Fits `exponential`, `logistic` or `gompertz` growth to the spatial average biomass
+    /// concentration, time in seconds. See `fit` for the returned dict.
+    fn fit_biomass(&self, py: Python<'_>, model: &str) -> PyResult<PyObject> {
+        let model = parse_model(model)?;
+        match bcore::fit::fit_biomass(&self.inner, model) {
+            Ok(r) => fit_result_to_py(py, &r),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Fits the Monod model to `mu_direct` as a function of the liquid concentration of a
+    /// substrate. See `fit` for the returned dict.
+    fn fit_monod(&self, py: Python<'_>, substrate: SpeciesArg) -> PyResult<PyObject> {
+        match bcore::fit::fit_monod(&self.inner, substrate) {
+            Ok(r) => fit_result_to_py(py, &r),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+}
+
+/// A run restricted to a window of exports, created by `PostProcess.view` or
+/// `PostProcess.exports`.
+///
+/// Export indices are relative to the window and biological datasets are only read for the
+/// exports of the window.
+#[pyclass(name = "PostProcessView")]
+struct PythonPostProcessView {
+    parent: Py<PythonPostProcess>,
+    start: usize,
+    end: usize,
+}
+
+impl PythonPostProcessView {
+    fn with_view<T, F>(&self, py: Python<'_>, f: F) -> PyResult<T>
+    where
+        F: FnOnce(&PostProcessView) -> Result<T, ApiError>,
+    {
+        let parent = self.parent.borrow(py);
+        let view = parent
+            .inner
+            .exports(self.start..self.end)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        f(&view).map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+}
+
+#[pymethods]
+impl PythonPostProcessView {
+    /// Range `(start, end)` of exports of the run covered by the view
+    #[getter]
+    fn exports(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    #[getter]
+    fn time(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| Ok(v.time_array()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn n_export(&self) -> usize {
+        self.end - self.start
+    }
+
+    fn get_bio_exports(&self, py: Python<'_>) -> PyResult<Vec<usize>> {
+        self.with_view(py, |v| Ok(v.get_bio_exports()))
+    }
+
+    fn get_bio_export_time(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| Ok(v.get_bio_export_time()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn nearest_bio_export(&self, py: Python<'_>, time: f64) -> PyResult<usize> {
+        self.with_view(py, |v| v.nearest_bio_export(time))
+    }
+
+    #[getter]
+    fn v_liquid(&self, py: Python<'_>) -> PyResult<Py<PyArray2<f64>>> {
+        let e = self.with_view(py, |v| Ok(v.v_liquid().to_owned()))?;
+        Ok(PyArray2::from_owned_array(py, e).unbind())
+    }
+
+    fn get_concentrations(&self, py: Python<'_>, phase: Phase) -> PyResult<Py<PyArray3<f64>>> {
+        let phase: bcore::api::Phase = phase.into();
+        let e = self.with_view(py, |v| {
+            v.check_phase(phase)?;
+            Ok(v.get_concentrations(phase).to_owned())
+        })?;
+        Ok(PyArray3::from_owned_array(py, e).unbind())
+    }
+
+    fn get_spatial_average_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| {
+            v.get_spatial_average_concentration(species, phase.into())
+        })?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn get_biomass_concentration(&self, py: Python<'_>) -> PyResult<Py<PyArray2<f64>>> {
+        let e = self.with_view(py, |v| v.get_biomass_concentration())?;
+        Ok(PyArray2::from_owned_array(py, e).unbind())
+    }
+
+    fn get_spatial_average_biomass_concentration(
+        &self,
+        py: Python<'_>,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.get_spatial_average_biomass_concentration())?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn get_time_average_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        position: usize,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| {
+            v.get_time_average_concentration(species, position, phase.into())
+        })?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    #[pyo3(signature = (values, rule="trapezoid"))]
+    fn integrate(&self, py: Python<'_>, values: Vec<f64>, rule: &str) -> PyResult<f64> {
+        let rule = parse_rule(rule)?;
+        self.with_view(py, |v| v.integrate(ArrayView1::from(&values), rule))
+    }
+
+    fn window_average(
+        &self,
+        py: Python<'_>,
+        values: Vec<f64>,
+        start: f64,
+        end: f64,
+    ) -> PyResult<f64> {
+        self.with_view(py, |v| v.window_average(ArrayView1::from(&values), start, end))
+    }
+
+    fn cumulative_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.cumulative_concentration(species, phase.into()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn cumulative_biomass_concentration(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.cumulative_biomass_concentration())?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn cumulative_mtr(&self, py: Python<'_>, species: SpeciesArg) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.cumulative_mtr(species))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn window_average_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        start: f64,
+        end: f64,
+    ) -> PyResult<f64> {
+        self.with_view(py, |v| {
+            v.window_average_concentration(species, phase.into(), start, end)
+        })
+    }
+
+    fn window_average_biomass_concentration(
+        &self,
+        py: Python<'_>,
+        start: f64,
+        end: f64,
+    ) -> PyResult<f64> {
+        self.with_view(py, |v| v.window_average_biomass_concentration(start, end))
+    }
+
+    fn window_average_mtr(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        start: f64,
+        end: f64,
+    ) -> PyResult<f64> {
+        self.with_view(py, |v| v.window_average_mtr(species, start, end))
+    }
+
+    #[pyo3(signature = (values, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state(
+        &self,
+        py: Python<'_>,
+        values: Vec<f64>,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        let steady =
+            self.with_view(py, |v| v.steady_state(ArrayView1::from(&values), detector, level))?;
+        steady_state_to_py(py, steady)
+    }
+
+    #[pyo3(signature = (method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    fn steady_state_biomass(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        let steady = self.with_view(py, |v| v.steady_state_biomass(detector, level))?;
+        steady_state_to_py(py, steady)
+    }
+
+    #[pyo3(signature = (phase, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state_species(
+        &self,
+        py: Python<'_>,
+        phase: Phase,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        let species =
+            self.with_view(py, |v| v.steady_state_species(phase.into(), detector, level))?;
+        species_steady_state_to_py(py, species)
+    }
+
+    #[pyo3(signature = (key, method="mser", window=10, threshold=0.01, batch=5, level=0.95))]
+    #[allow(clippy::too_many_arguments)]
+    fn steady_state_population_mean(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        method: &str,
+        window: usize,
+        threshold: f64,
+        batch: usize,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let detector = parse_detector(method, window, threshold, batch)?;
+        let steady =
+            self.with_view(py, |v| v.steady_state_population_mean(key, detector, level))?;
+        steady_state_to_py(py, steady)
+    }
+
+    fn concentration_periodogram(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        compartment: usize,
+    ) -> PyResult<PyPeriodogram> {
+        let p = self.with_view(py, |v| {
+            v.concentration_periodogram(species, phase.into(), compartment)
+        })?;
+        Ok(periodogram_to_py(py, p))
+    }
+
+    fn dominant_period_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.dominant_period_concentration(species, phase.into()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn autocorrelation_time_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| {
+            v.autocorrelation_time_concentration(species, phase.into())
+        })?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn circulation_time(&self, py: Python<'_>, species: SpeciesArg, phase: Phase) -> PyResult<f64> {
+        self.with_view(py, |v| v.circulation_time(species, phase.into()))
+    }
+
+    fn population_mean_periodogram(&self, py: Python<'_>, key: &str) -> PyResult<PyPeriodogram> {
+        let p = self.with_view(py, |v| v.population_mean_periodogram(key))?;
+        Ok(periodogram_to_py(py, p))
+    }
+
+    fn autocorrelation_time_population_mean(&self, py: Python<'_>, key: &str) -> PyResult<f64> {
+        self.with_view(py, |v| v.autocorrelation_time_population_mean(key))
+    }
+
+    fn get_spatial_property(&self, py: Python<'_>, name: &str) -> PyResult<Py<PyArray2<f64>>> {
+        let e = self.with_view(py, |v| v.get_spatial_average_property(name))?;
+        Ok(PyArray2::from_owned_array(py, e).unbind())
+    }
+
+    fn get_number_particle(&self, py: Python<'_>) -> PyResult<Py<PyArray2<f64>>> {
+        let e = self.with_view(py, |v| Ok(v.get_number_particle().clone()))?;
+        Ok(PyArray2::from_owned_array(py, e).unbind())
+    }
+
+    fn get_growth_in_number(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| Ok(v.get_growth_in_number()))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn get_properties(
+        &self,
+        py: Python<'_>,
+        key: &str,
+        i_export: usize,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.get_properties(key, i_export))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    /// See `PostProcess.get_particle_table`.
+    fn get_particle_table(
+        &self,
+        py: Python<'_>,
+        keys: Vec<String>,
+        i_export: usize,
+    ) -> PyResult<PyObject> {
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let table = self.with_view(py, |v| v.get_particle_table(&keys, i_export))?;
+        particle_table_to_py(py, table)
+    }
+
+    fn get_population_mean(&self, py: Python<'_>, key: &str, i_export: usize) -> PyResult<f64> {
+        self.with_view(py, |v| v.get_population_mean(key, i_export))
+    }
+
+    fn get_time_population_mean(&self, py: Python<'_>, key: &str) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.get_time_population_mean(key))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn get_histogram(
+        &self,
+        py: Python<'_>,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> PyResult<(Py<PyArray1<f64>>, Py<PyArray1<f64>>)> {
+        let (bins, counts) = self.with_view(py, |v| v.get_histogram_array(n_bins, i_export, key))?;
+        Ok((
+            PyArray1::from_owned_array(py, bins).unbind(),
+            PyArray1::from_owned_array(py, counts).unbind(),
+        ))
+    }
+
+    fn mu_direct(&self, py: Python<'_>) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.mu_direct())?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn estimate(
+        &self,
+        py: Python<'_>,
+        etype: Estimator,
+        key: &str,
+        i_export: usize,
+    ) -> PyResult<f64> {
+        self.with_view(py, |v| v.estimate(etype.into(), key, i_export))
+    }
+
+    fn estimate_time(
+        &self,
+        py: Python<'_>,
+        etype: Estimator,
+        key: &str,
+    ) -> PyResult<Py<PyArray1<f64>>> {
+        let e = self.with_view(py, |v| v.estimate_time(etype.into(), key))?;
+        Ok(PyArray1::from_owned_array(py, e).unbind())
+    }
+
+    fn get_tallies(&self, py: Python<'_>) -> PyResult<Py<PyArray2<f64>>> {
+        let e = self.with_view(py, |v| match v.tallies() {
+            Some(t) => Ok(t.to_array().to_owned()),
+            None => Err(ApiError::RecordsError("tallies".to_string())),
+        })?;
+        Ok(PyArray2::from_owned_array(py, e).unbind())
+    }
+
+    /// See `PostProcess.get_initial_parameters`.
+    fn get_initial_parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let initial = self.with_view(py, |v| Ok(v.get_initial_parameters()))?;
+        initial_parameters_to_py(py, &initial)
+    }
+
+    /// See `PostProcess.get_final_summary`.
+    fn get_final_summary(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let summary = self.with_view(py, |v| Ok(v.get_final_summary()))?;
+        summary.map(|f| final_summary_to_py(py, &f)).transpose()
+    }
+
+    /// See `PostProcess.get_run_metadata`.
+    fn get_run_metadata(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let metadata = self.with_view(py, |v| Ok(v.get_run_metadata()))?;
+        run_metadata_to_py(py, &metadata)
+    }
+
+    /// See `PostProcess.resample_concentrations`.
+    #[pyo3(signature = (phase, grid, method="linear"))]
+    fn resample_concentrations(
+        &self,
+        py: Python<'_>,
+        phase: Phase,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix3>> {
+        let method = parse_method(method)?;
+        let r = self.with_view(py, |v| v.resample_concentrations(phase.into(), &grid, method))?;
+        Ok(resampled_to_py(py, r))
+    }
+
+    #[pyo3(signature = (grid, method="linear"))]
+    fn resample_biomass_concentration(
+        &self,
+        py: Python<'_>,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix2>> {
+        let method = parse_method(method)?;
+        let r = self.with_view(py, |v| v.resample_biomass_concentration(&grid, method))?;
+        Ok(resampled_to_py(py, r))
+    }
+
+    #[pyo3(signature = (etype, key, grid, method="linear"))]
+    fn resample_estimate(
+        &self,
+        py: Python<'_>,
+        etype: Estimator,
+        key: &str,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix1>> {
+        let method = parse_method(method)?;
+        let r = self.with_view(py, |v| v.resample_estimate(etype.into(), key, &grid, method))?;
+        Ok(resampled_to_py(py, r))
+    }
+
+    #[pyo3(signature = (grid, method="linear"))]
+    fn resample_tallies(
+        &self,
+        py: Python<'_>,
+        grid: Vec<f64>,
+        method: &str,
+    ) -> PyResult<PyResampled<Ix2>> {
+        let method = parse_method(method)?;
+        let r = self.with_view(py, |v| v.resample_tallies(&grid, method))?;
+        Ok(resampled_to_py(py, r))
+    }
+
+    /// See `PostProcess.get_tally_table`.
+    #[pyo3(signature = (kind="cumulative", scaled=false))]
+    fn get_tally_table(&self, py: Python<'_>, kind: &str, scaled: bool) -> PyResult<PyObject> {
+        let table =
+            self.with_view(py, |v| scale_tally_table(v.get_tally_table(), v.weight(), scaled))?;
+        tally_table_to_py(py, &table, kind)
+    }
+}
+
+fn particle_table_to_py(py: Python<'_>, table: ParticleTable) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    for (key, column) in table.keys.iter().zip(table.columns) {
+        dict.set_item(key, PyArray1::from_owned_array(py, column))?;
+    }
+    dict.set_item("rank", PyArray1::from_owned_array(py, table.rank))?;
+    dict.set_item("index", PyArray1::from_owned_array(py, table.index))?;
+    Ok(dict.into_any().unbind())
+}
+
+fn initial_parameters_to_py(py: Python<'_>, initial: &InitialParameters) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("delta_time", initial.delta_time)?;
+    dict.set_item("final_time", initial.final_time)?;
+    dict.set_item(
+        "initial_biomass_concentration",
+        initial.initial_biomass_concentration,
+    )?;
+    dict.set_item("initial_weight", initial.initial_weight)?;
+    dict.set_item("n_map", initial.n_map)?;
+    dict.set_item("number_compartment", initial.number_compartment)?;
+    dict.set_item("number_particles", initial.number_particles)?;
+    dict.set_item("t_per_flow_map", initial.t_per_flow_map)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn final_summary_to_py(py: Python<'_>, summary: &FinalSummary) -> PyResult<PyObject> {
+    let events = PyDict::new(py);
+    for (name, count) in &summary.events {
+        events.set_item(name, count)?;
+    }
+    let dict = PyDict::new(py);
+    dict.set_item("events", events)?;
+    dict.set_item("number_particles", summary.number_particles)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn run_metadata_to_py(py: Python<'_>, metadata: &RunMetadata) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("n_node_thread", metadata.n_node_thread)?;
+    dict.set_item("n_rank", metadata.n_rank)?;
+    dict.set_item("n_export", metadata.n_export)?;
+    dict.set_item("n_compartment", metadata.n_compartment)?;
+    dict.set_item("species_names", metadata.species_names.clone())?;
+    dict.set_item("finished", metadata.finished)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn scale_tally_table(
+    table: Result<TallyTable, ApiError>,
+    weight: &Weight,
+    scaled: bool,
+) -> Result<TallyTable, ApiError> {
+    match scaled {
+        true => table?.scaled(weight),
+        false => table,
+    }
+}
+
+fn tally_table_to_py(py: Python<'_>, table: &TallyTable, kind: &str) -> PyResult<PyObject> {
+    let values = match kind {
+        "cumulative" => table.cumulative.clone(),
+        "increments" => table.increments(),
+        "rates" => table.rates(),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown kind '{}', expected cumulative, increments or rates",
+                kind
+            )))
+        }
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("time", PyArray1::from_owned_array(py, table.time.clone()))?;
+    for (name, column) in table.columns.iter().zip(values.columns()) {
+        dict.set_item(name, PyArray1::from_owned_array(py, column.to_owned()))?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Converts a confidence band to a dict with keys `mean`, `std`, `lower` and `upper`
+fn band_to_py<D: numpy::ndarray::Dimension>(
+    py: Python<'_>,
+    band: Band<D>,
+) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("mean", PyArray::from_owned_array(py, band.mean))?;
+    dict.set_item("std", PyArray::from_owned_array(py, band.std))?;
+    dict.set_item("lower", PyArray::from_owned_array(py, band.lower))?;
+    dict.set_item("upper", PyArray::from_owned_array(py, band.upper))?;
+    dict.set_item("n", band.n)?;
+    dict.set_item("level", band.level)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn growth_rate_to_py(py: Python<'_>, rate: GrowthRate) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("mu", PyArray1::from_owned_array(py, rate.mu))?;
+    dict.set_item("std_error", PyArray1::from_owned_array(py, rate.std_error))?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Lower edges of bins and counts per run
+type BinnedCounts = (Py<PyArray1<f64>>, Py<PyArray2<f64>>);
+
+/// Replicate runs of the same simulation sharing the same time grid.
+///
+/// Time series are returned as dict with keys `mean`, `std` (sample standard deviation),
+/// `lower` and `upper` (Student confidence band of the mean at `level`), `n` and `level`.
+///
+/// # Example
+///
+/// ```python
+/// ensemble = Ensemble(["run_1", "run_2", "run_3"], "./results/")
+/// x = ensemble.spatial_average_biomass_concentration(level=0.95)
+/// plt.fill_between(ensemble.time, x["lower"], x["upper"])
+/// ```
+#[pyclass(name = "Ensemble")]
+struct PythonEnsemble {
+    inner: CoreEnsemble,
+}
+
+impl PythonEnsemble {
+    fn to_py<D: numpy::ndarray::Dimension>(
+        py: Python<'_>,
+        band: Result<Band<D>, ApiError>,
+    ) -> PyResult<PyObject> {
+        match band {
+            Ok(band) => band_to_py(py, band),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Species are resolved on the first run, all runs share the same model
+    fn species(&self, species: SpeciesArg) -> PyResult<usize> {
+        self.inner.runs()[0]
+            .species_index(species)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+}
+
+#[pymethods]
+impl PythonEnsemble {
+    /// Opens every folder and checks that the runs share the same time grid
+    #[new]
+    #[pyo3(signature = (folders, root=None))]
+    fn new(folders: Vec<String>, root: Option<String>) -> PyResult<Self> {
+        match CoreEnsemble::open(&folders, root) {
+            Ok(inner) => Ok(Self { inner }),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    #[getter]
+    fn time(&self, py: Python<'_>) -> Py<PyArray1<f64>> {
+        PyArray1::from_vec(py, self.inner.time().to_vec()).unbind()
+    }
+
+    #[getter]
+    fn n_run(&self) -> usize {
+        self.inner.n_run()
+    }
+
+    #[pyo3(signature = (level=DEFAULT_LEVEL))]
+    fn biomass_concentration(&self, py: Python<'_>, level: f64) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.biomass_concentration(level))
+    }
+
+    #[pyo3(signature = (level=DEFAULT_LEVEL))]
+    fn spatial_average_biomass_concentration(
+        &self,
+        py: Python<'_>,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.spatial_average_biomass_concentration(level))
+    }
+
+    #[pyo3(signature = (species, phase, level=DEFAULT_LEVEL))]
+    fn spatial_average_concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let species = self.species(species)?;
+        Self::to_py(
+            py,
+            self.inner
+                .spatial_average_concentration(species, phase.into(), level),
+        )
+    }
+
+    #[pyo3(signature = (species, phase, level=DEFAULT_LEVEL))]
+    fn concentration(
+        &self,
+        py: Python<'_>,
+        species: SpeciesArg,
+        phase: Phase,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        let species = self.species(species)?;
+        Self::to_py(py, self.inner.concentration(species, phase.into(), level))
+    }
+
+    #[pyo3(signature = (key, level=DEFAULT_LEVEL))]
+    fn time_population_mean(&self, py: Python<'_>, key: &str, level: f64) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.time_population_mean(key, level))
+    }
+
+    #[pyo3(signature = (etype, key, level=DEFAULT_LEVEL))]
+    fn estimate_time(
+        &self,
+        py: Python<'_>,
+        etype: Estimator,
+        key: &str,
+        level: f64,
+    ) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.estimate_time(etype.into(), key, level))
+    }
+
+    #[pyo3(signature = (level=DEFAULT_LEVEL))]
+    fn mu_direct(&self, py: Python<'_>, level: f64) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.mu_direct(level))
+    }
+
+    #[pyo3(signature = (level=DEFAULT_LEVEL))]
+    fn growth_in_number(&self, py: Python<'_>, level: f64) -> PyResult<PyObject> {
+        Self::to_py(py, self.inner.growth_in_number(level))
+    }
+
+    /// Histogram of a property over the particles of all runs, returns `(bins, counts)`
+    fn pooled_histogram(
+        &self,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> PyResult<(Vec<f64>, Vec<f64>)> {
+        self.inner
+            .pooled_histogram(n_bins, i_export, key)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Histogram of each run on the pooled bins, returns `(bins, counts)` with counts of shape
+    /// (n_run, n_bins)
+    fn histograms(
+        &self,
+        py: Python<'_>,
+        n_bins: usize,
+        i_export: usize,
+        key: &str,
+    ) -> PyResult<BinnedCounts> {
+        match self.inner.histograms(n_bins, i_export, key) {
+            Ok((bins, counts)) => Ok((
+                PyArray1::from_owned_array(py, bins).unbind(),
+                PyArray2::from_owned_array(py, counts).unbind(),
+            )),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+}
+
+/// Tag value of a study run, either a number or a string
+#[derive(FromPyObject)]
+enum TagArg {
+    Number(f64),
+    Text(String),
+}
+
+impl From<TagArg> for Cell {
+    fn from(tag: TagArg) -> Self {
+        match tag {
+            TagArg::Number(x) => Cell::Number(x),
+            TagArg::Text(s) => Cell::Text(s),
+        }
+    }
+}
+
+/// Converts a study table to a dict mapping each column to a list, ready for `pd.DataFrame`
+fn study_table_to_py(py: Python<'_>, table: &StudyTable) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    for (i, name) in table.columns().iter().enumerate() {
+        let column = PyList::empty(py);
+        for row in table.rows() {
+            match &row[i] {
+                Cell::Number(x) => column.append(x)?,
+                Cell::Text(s) => column.append(s)?,
+                Cell::Missing => column.append(py.None())?,
+            }
+        }
+        dict.set_item(name, column)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Runs of a parameter sweep with user tags and scalar KPIs.
+///
+/// KPIs are given as strings: `final_biomass`, `max_mu`, `mixing_time:<species>[:<threshold>]`
+/// and `yield:<substrate>`.
+///
+/// # Example
+///
+/// ```python
+/// study = Study(["sweep_1", "sweep_2"], "./results/")
+/// study.tag("sweep_1", "feed_rate", 0.1)
+/// df = pd.DataFrame(study.table(["final_biomass", "mixing_time:glucose"], sort_by=["delta_time"]))
+/// ```
+#[pyclass(name = "Study")]
+struct PythonStudy {
+    inner: Study,
+}
+
+impl PythonStudy {
+    fn build_table(&self, kpis: Vec<String>, sort_by: Option<Vec<String>>) -> PyResult<StudyTable> {
+        let kpis = kpis
+            .iter()
+            .map(|k| k.parse::<Kpi>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let mut table = self
+            .inner
+            .table(&kpis)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        if let Some(columns) = sort_by {
+            let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+            table
+                .sort_by(&columns)
+                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        }
+        Ok(table)
+    }
+}
+
+#[pymethods]
+impl PythonStudy {
+    /// Opens every folder, runs are named after their folder
+    #[new]
+    #[pyo3(signature = (folders=vec![], root=None))]
+    fn new(folders: Vec<String>, root: Option<String>) -> PyResult<Self> {
+        match Study::open(&folders, root) {
+            Ok(inner) => Ok(Self { inner }),
+            Err(e) => Err(PyErr::new::<PyRuntimeError, _>(e.to_string())),
+        }
+    }
+
+    /// Opens a run and adds it to the study, named after its folder unless `name` is given
+    #[pyo3(signature = (folder, root=None, name=None))]
+    fn add_run(
+        &mut self,
+        folder: &str,
+        root: Option<String>,
+        name: Option<String>,
+    ) -> PyResult<()> {
+        let run = PostProcess::new(folder, root)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        self.inner
+            .add_run(name.as_deref().unwrap_or(folder), run)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Sets a user tag of a run, e.g. a feed rate
+    fn tag(&mut self, run: &str, key: &str, value: TagArg) -> PyResult<()> {
+        self.inner
+            .tag(run, key, value)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn runs(&self) -> Vec<String> {
+        self.inner.runs().iter().map(|r| r.name.clone()).collect()
+    }
+
+    /// Table of parameters, tags and KPIs as a dict of columns
+    #[pyo3(signature = (kpis=vec![], sort_by=None))]
+    fn table(
+        &self,
+        py: Python<'_>,
+        kpis: Vec<String>,
+        sort_by: Option<Vec<String>>,
+    ) -> PyResult<PyObject> {
+        study_table_to_py(py, &self.build_table(kpis, sort_by)?)
+    }
+
+    /// Tables split by value of a column, as a list of `(value, table)`
+    #[pyo3(signature = (column, kpis=vec![], sort_by=None))]
+    fn group_by(
+        &self,
+        py: Python<'_>,
+        column: &str,
+        kpis: Vec<String>,
+        sort_by: Option<Vec<String>>,
+    ) -> PyResult<PyObject> {
+        let groups = self
+            .build_table(kpis, sort_by)?
+            .group_by(column)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        let list = PyList::empty(py);
+        for (key, table) in groups {
+            let key = match key {
+                Cell::Number(x) => x.into_pyobject(py)?.into_any().unbind(),
+                Cell::Text(s) => s.into_pyobject(py)?.into_any().unbind(),
+                Cell::Missing => py.None(),
+            };
+            list.append((key, study_table_to_py(py, &table)?))?;
+        }
+        Ok(list.into_any().unbind())
+    }
+
+    #[pyo3(signature = (kpis=vec![], sort_by=None))]
+    fn to_csv(&self, kpis: Vec<String>, sort_by: Option<Vec<String>>) -> PyResult<String> {
+        self.build_table(kpis, sort_by)?
+            .to_csv()
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+
+    #[pyo3(signature = (kpis=vec![], sort_by=None))]
+    fn to_json(&self, kpis: Vec<String>, sort_by: Option<Vec<String>>) -> PyResult<String> {
+        self.build_table(kpis, sort_by)?
+            .to_json()
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))
+    }
+}
+
+/// Measured time series read from CSV with units in the headers, e.g.
+/// `time [h],biomass [g/L],biomass_std [g/L],glucose [g/L]`.
+///
+/// Values in a known unit are stored in the units of the API (e.g. seconds and kg/m3), other
+/// units such as `%` or `OD` are kept as written. The time column must be in a known time unit.
+/// Rows are sorted by time. `biomass` is compared to the spatial average biomass concentration,
+/// any other concentration to the liquid concentration of the species of the same name.
+///
+/// # Example
+///
+/// ```python
+/// data = ExperimentalData.from_file("batch_1.csv")
+/// data.rename("glc", "glucose")
+/// for name, a in data.agreement(post_process).items():
+///     print(name, a["rmse"], a["r2"])
+/// ```
+#[pyclass(name = "ExperimentalData")]
+struct PythonExperimentalData {
+    inner: ExperimentalData,
+}
+
+#[pymethods]
+impl PythonExperimentalData {
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        ExperimentalData::from_file(path)
+            .map(|inner| Self { inner })
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[staticmethod]
+    fn from_csv(csv: &str) -> PyResult<Self> {
+        ExperimentalData::from_csv(csv)
+            .map(|inner| Self { inner })
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn names(&self) -> Vec<String> {
+        self.inner
+            .measurements
+            .iter()
+            .map(|m| m.name.clone())
+            .collect()
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> PyResult<()> {
+        self.inner
+            .rename(from, to)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Samples of a variable as a dict with `time`, `values` and `std` (None if not measured)
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let m = self
+            .inner
+            .get(name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown variable '{}'", name)))?;
+        let dict = PyDict::new(py);
+        dict.set_item("time", PyArray1::from_vec(py, m.time.clone()))?;
+        dict.set_item("values", PyArray1::from_vec(py, m.values.clone()))?;
+        match &m.std {
+            Some(std) => dict.set_item("std", PyArray1::from_vec(py, std.clone()))?,
+            None => dict.set_item("std", py.None())?,
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Samples of a variable interpolated onto a time grid, NaN outside the sampled range
+    fn on_grid(&self, py: Python<'_>, name: &str, time: Vec<f64>) -> PyResult<Py<PyArray1<f64>>> {
+        let m = self
+            .inner
+            .get(name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown variable '{}'", name)))?;
+        Ok(PyArray1::from_owned_array(py, m.on_grid(&time)).unbind())
+    }
+
+    /// Fit metrics and residuals of every variable against a run.
+    ///
+    /// Variables that are not concentrations in a known unit (e.g. `od [OD]` or `mu [1/h]`) and
+    /// variables that are neither `biomass` nor a species of the run are skipped.
+    ///
+    /// # Returns
+    ///
+    /// * `dict`: For each variable, a dict with `time`, `measured`, `simulated`, `residuals`,
+    ///   `rmse`, `nrmse`, `r2` and `chi2`.
+    fn agreement(&self, py: Python<'_>, post_process: &PythonPostProcess) -> PyResult<PyObject> {
+        let agreements = self
+            .inner
+            .agreement(&post_process.inner)
+            .map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let dict = PyDict::new(py);
+        for a in agreements {
+            let item = PyDict::new(py);
+            item.set_item("time", PyArray1::from_owned_array(py, a.time))?;
+            item.set_item("measured", PyArray1::from_owned_array(py, a.measured))?;
+            item.set_item("simulated", PyArray1::from_owned_array(py, a.simulated))?;
+            item.set_item("residuals", PyArray1::from_owned_array(py, a.residuals))?;
+            item.set_item("rmse", a.rmse)?;
+            item.set_item("nrmse", a.nrmse)?;
+            item.set_item("r2", a.r2)?;
+            item.set_item("chi2", a.chi2)?;
+            dict.set_item(a.name, item)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+}
+
+/// Iterator over the particles of an export, created by `PostProcess.particle_chunks`
+#[pyclass(name = "ParticleChunks", unsendable)]
+struct PythonParticleChunks {
+    inner: ParticleChunks,
+}
+
+#[pymethods]
+impl PythonParticleChunks {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let Some(chunk) = self.inner.next() else {
+            return Ok(None);
+        };
+        let chunk = chunk.map_err(|e| PyErr::new::<PyRuntimeError, _>(e.to_string()))?;
+        let dict = PyDict::new(py);
+        dict.set_item("rank", chunk.rank)?;
+        for (key, column) in self.inner.keys().iter().zip(chunk.values.columns()) {
+            dict.set_item(key, PyArray1::from_owned_array(py, column.to_owned()))?;
+        }
+        Ok(Some(dict.into_any().unbind()))
+    }
+
+    #[getter]
+    fn keys(&self) -> Vec<String> {
+        self.inner.keys().to_vec()
+    }
+}
+
+#[pymodule]
+mod biomc_pp {
+    #[pymodule_export]
+    use super::Estimator;
+    #[pymodule_export]
+    use super::PythonEnsemble;
+    #[pymodule_export]
+    use super::PythonExperimentalData;
+    #[pymodule_export]
+    use super::Phase;
+    #[pymodule_export]
+    use super::PythonParticleChunks;
+    #[pymodule_export]
+    use super::PythonParticleFilter;
+    #[pymodule_export]
+    use super::PythonPostProcess;
+    #[pymodule_export]
+    use super::PythonPostProcessView;
+    #[pymodule_export]
+    use super::PythonStudy;
+    #[pymodule_export]
+    use super::PythonZoning;
+    #[pymodule_export]
+    use super::validate;
+    #[pymodule_export]
+    use super::convert;
+    #[pymodule_export]
+    use super::compare;
+    #[pymodule_export]
+    use super::compare_distributions;
+    #[pymodule_export]
+    use super::fit;
 }